@@ -0,0 +1,94 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Counts the heap allocations made while preparing requests and parsing
+// responses during a bulk backfill:
+//
+// cargo bench --bench allocations
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_derive::Deserialize;
+
+use forecast::{ExcludeBlock, Flags, ForecastRequestBuilder};
+
+// counting allocator
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// constants
+
+const ITERATIONS: usize = 10_000;
+
+static FLAGS: &str = "{\"sources\":[\"isd\",\"nearest-precip\",\"nwspa\",\"cmc\",\"gfs\",\
+                      \"hrrr\",\"madis\",\"nam\",\"sref\",\"darksky\"],\"units\":\"us\"}";
+
+// Flags as they were parsed before sources were interned.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct UninternedFlags {
+    sources: Vec<String>,
+    units: String
+}
+
+fn count<F: FnMut()>(mut f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let exclude = count(|| {
+        let builder = ForecastRequestBuilder::new("api_key", 42.3736, -71.1097)
+            .exclude_block(ExcludeBlock::Minutely)
+            .exclude_block(ExcludeBlock::Hourly)
+            .exclude_block(ExcludeBlock::Alerts);
+
+        std::hint::black_box(builder);
+    });
+
+    let uninterned = count(|| {
+        std::hint::black_box(serde_json::from_str::<UninternedFlags>(FLAGS).unwrap());
+    });
+
+    let interned = count(|| {
+        std::hint::black_box(serde_json::from_str::<Flags>(FLAGS).unwrap());
+    });
+
+    println!("allocations per iteration, over {} iterations:", ITERATIONS);
+    println!("  builder with 3 excluded blocks: {:.2}", exclude as f64 / ITERATIONS as f64);
+    println!("  flags, sources as String:       {:.2}", uninterned as f64 / ITERATIONS as f64);
+    println!("  flags, sources interned:        {:.2}", interned as f64 / ITERATIONS as f64);
+}
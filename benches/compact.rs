@@ -0,0 +1,55 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Compares the memory used by a year of hourly DataPoints against the
+// same data held as CompactDataPoints, and times the conversions:
+//
+// cargo bench --bench compact
+
+use std::fs::File;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use forecast::{ApiResponse, CompactDataPoint, DataPoint};
+
+// constants
+
+const HOURS: usize = 24 * 365;
+
+fn main() {
+    let mut path_buf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path_buf.push("resources/tests/forecast_response_10-23-2016.json");
+
+    let response: ApiResponse = serde_json::from_reader(File::open(path_buf).unwrap()).unwrap();
+    let hourly = response.hourly.unwrap().data;
+
+    let points: Vec<DataPoint> = hourly.iter().cycle().take(HOURS).cloned().collect();
+
+    let start = Instant::now();
+    let compact: Vec<CompactDataPoint> = points.iter().map(CompactDataPoint::from).collect();
+    let compact_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    let restored: Vec<DataPoint> = compact.iter().map(|c| c.to_data_point()).collect();
+    let restore_elapsed = start.elapsed();
+
+    assert_eq!(restored, points);
+
+    println!("{} hourly data points:", HOURS);
+    println!("  DataPoint:        {} bytes", HOURS * size_of::<DataPoint>());
+    println!("  CompactDataPoint: {} bytes", HOURS * size_of::<CompactDataPoint>());
+    println!("  DataPoint -> CompactDataPoint: {:?}", compact_elapsed);
+    println!("  CompactDataPoint -> DataPoint: {:?}", restore_elapsed);
+}
@@ -0,0 +1,95 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Compares the latency of a burst of multi-location fetches made with
+// connection reuse disabled against the same burst made over a warm
+// pooled connection. Sends real requests, so it needs an API key:
+//
+// FORECAST_API_KEY=$YOUR_FORECAST_API_KEY cargo bench --bench pooled_fetch
+
+use std::time::{Duration, Instant};
+
+use forecast::{ApiClient, ApiClientBuilder, ForecastRequestBuilder, ExcludeBlock};
+
+// constants
+
+const LOCATIONS: [(f64, f64); 8] = [
+    (42.3736, -71.1097),
+    (40.7128, -74.0060),
+    (51.5074, -0.1278),
+    (48.8566, 2.3522),
+    (35.6762, 139.6503),
+    (-33.8688, 151.2093),
+    (37.7749, -122.4194),
+    (52.5200, 13.4050)
+];
+
+const BURSTS: u32 = 5;
+
+async fn burst(api_client: &ApiClient<'_>, api_key: &str) -> Duration {
+    let start = Instant::now();
+
+    for &(lat, long) in LOCATIONS.iter() {
+        let request = ForecastRequestBuilder::new(api_key, lat, long)
+            .exclude([ExcludeBlock::Minutely, ExcludeBlock::Hourly, ExcludeBlock::Daily])
+            .build();
+
+        api_client.get_forecast_parsed(request).await.unwrap();
+    }
+
+    start.elapsed()
+}
+
+async fn mean_burst(api_client: &ApiClient<'_>, api_key: &str) -> Duration {
+    // warm up, so that a pooled client has an open connection
+    burst(api_client, api_key).await;
+
+    let mut total = Duration::from_secs(0);
+
+    for _ in 0..BURSTS {
+        total += burst(api_client, api_key).await;
+    }
+
+    total / BURSTS
+}
+
+#[tokio::main]
+async fn main() {
+    let api_key = match std::env::var("FORECAST_API_KEY") {
+        Ok(api_key) => api_key,
+        Err(_) => {
+            eprintln!("FORECAST_API_KEY is not set, skipping");
+            return;
+        }
+    };
+
+    let unpooled = ApiClientBuilder::new()
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+
+    let pooled = ApiClientBuilder::new()
+        .pool_max_idle_per_host(4)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_keepalive(Duration::from_secs(30))
+        .build()
+        .unwrap();
+
+    let unpooled_mean = mean_burst(&unpooled, &api_key).await;
+    let pooled_mean = mean_burst(&pooled, &api_key).await;
+
+    println!("{} locations per burst, mean of {} bursts:", LOCATIONS.len(), BURSTS);
+    println!("  no connection reuse: {:?}", unpooled_mean);
+    println!("  warm pooled:         {:?}", pooled_mean);
+}
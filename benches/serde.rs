@@ -0,0 +1,142 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Criterion benchmarks for response deserialization and serialization,
+// and for request URL construction:
+//
+// cargo bench --bench serde
+
+use std::fs;
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use forecast::{ApiResponse, ExcludeBlock, ExtendBy, ForecastRequestBuilder, Lang,
+               TimeMachineRequestBuilder, Units};
+
+// constants
+
+const LAT: f64 = 42.3736;
+const LONG: f64 = -71.1097;
+const TIME: u64 = 1505899999;
+
+const EXTENDED_HOURS: usize = 168;
+
+static API_KEY: &str = "some_api_key";
+
+static SMALL: &str = "{\"latitude\":42.3736,\"longitude\":-71.1097,\
+                      \"timezone\":\"America/New_York\",\"offset\":-4,\
+                      \"currently\":{\"time\":1508794800,\"summary\":\"Rain\",\
+                      \"icon\":\"rain\",\"temperature\":65.72,\"humidity\":0.96}}";
+
+// payloads
+
+fn full() -> String {
+    let mut path_buf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path_buf.push("resources/tests/forecast_response_01-21-2018.json");
+
+    fs::read_to_string(path_buf).unwrap()
+}
+
+// the full payload with its hourly block extended to 168 hours, as
+// returned for an `extend=hourly` request
+fn extended_hourly(full: &str) -> String {
+    let mut response: ApiResponse = serde_json::from_str(full).unwrap();
+
+    if let Some(hourly) = response.hourly.as_mut() {
+        let first = hourly.data[0].clone();
+        let step = hourly.data.get(1).map_or(3600, |p| p.time - first.time);
+
+        hourly.data = (0..EXTENDED_HOURS)
+            .map(|i| {
+                let mut point = hourly.data[i % hourly.data.len()].clone();
+                point.time = first.time + step * i as i64;
+                point
+            })
+            .collect();
+    }
+
+    serde_json::to_string(&response).unwrap()
+}
+
+// benchmarks
+
+fn deserialize(c: &mut Criterion) {
+    let full = full();
+    let extended = extended_hourly(&full);
+
+    let mut group = c.benchmark_group("deserialize");
+
+    for (name, payload) in [("small", SMALL), ("full", &full), ("extended_hourly", &extended)] {
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| serde_json::from_str::<ApiResponse>(black_box(payload)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn serialize(c: &mut Criterion) {
+    let full = full();
+    let extended = extended_hourly(&full);
+
+    let mut group = c.benchmark_group("serialize");
+
+    for (name, payload) in [("small", SMALL), ("full", &full), ("extended_hourly", &extended)] {
+        let response: ApiResponse = serde_json::from_str(payload).unwrap();
+
+        group.bench_function(name, |b| {
+            b.iter(|| serde_json::to_string(black_box(&response)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn build_url(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_url");
+
+    group.bench_function("forecast_defaults", |b| {
+        b.iter(|| ForecastRequestBuilder::new(API_KEY, black_box(LAT), black_box(LONG)).build())
+    });
+
+    group.bench_function("forecast_all_params", |b| {
+        b.iter(|| {
+            ForecastRequestBuilder::new(API_KEY, black_box(LAT), black_box(LONG))
+                .exclude_block(ExcludeBlock::Minutely)
+                .exclude_block(ExcludeBlock::Alerts)
+                .extend(ExtendBy::Hourly)
+                .lang(Lang::German)
+                .units(Units::SI)
+                .build()
+        })
+    });
+
+    group.bench_function("time_machine_all_params", |b| {
+        b.iter(|| {
+            TimeMachineRequestBuilder::new(API_KEY, black_box(LAT), black_box(LONG), TIME)
+                .exclude_block(ExcludeBlock::Minutely)
+                .exclude_block(ExcludeBlock::Alerts)
+                .lang(Lang::German)
+                .units(Units::SI)
+                .build()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, deserialize, serialize, build_url);
+criterion_main!(benches);
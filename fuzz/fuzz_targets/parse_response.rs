@@ -0,0 +1,33 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Feeds arbitrary bytes to the strict ApiResponse deserializer, and
+// checks that anything it accepts survives a round trip through the
+// wire format:
+//
+// cargo +nightly fuzz run parse_response
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use forecast::{wire, ApiResponse};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(response) = serde_json::from_slice::<ApiResponse>(data) {
+        let serialized = wire::to_vec(&response).unwrap();
+
+        serde_json::from_slice::<ApiResponse>(&serialized).unwrap();
+    }
+});
@@ -0,0 +1,122 @@
+#ifndef FORECAST_H
+#define FORECAST_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdint.h>
+
+/**
+ * The outcome of an FFI call.
+ */
+typedef enum ForecastStatus {
+  FORECAST_STATUS_OK = 0,
+  /**
+   * A pointer argument was null.
+   */
+  FORECAST_STATUS_NULL_ARGUMENT = 1,
+  /**
+   * A string argument wasn't valid UTF-8.
+   */
+  FORECAST_STATUS_INVALID_UTF8 = 2,
+  /**
+   * The request couldn't be sent, or the response couldn't be read.
+   */
+  FORECAST_STATUS_HTTP = 3,
+  /**
+   * The API responded with a non-success status.
+   */
+  FORECAST_STATUS_STATUS = 4,
+  /**
+   * The API rejected the request as invalid.
+   */
+  FORECAST_STATUS_BAD_REQUEST = 5,
+  /**
+   * The response couldn't be parsed.
+   */
+  FORECAST_STATUS_PARSE = 6,
+  /**
+   * The response was degraded.
+   */
+  FORECAST_STATUS_DEGRADED = 7,
+  /**
+   * The client or runtime couldn't be constructed.
+   */
+  FORECAST_STATUS_INTERNAL = 8,
+  /**
+   * The response has no current conditions.
+   */
+  FORECAST_STATUS_NO_DATA = 9,
+  /**
+   * The daily quota of the client's rate limiter is spent.
+   */
+  FORECAST_STATUS_QUOTA_EXHAUSTED = 10,
+  /**
+   * The API rejected the request with `429 Too Many Requests`.
+   */
+  FORECAST_STATUS_RATE_LIMITED = 11,
+  /**
+   * The response exceeded the client's maximum response size.
+   */
+  FORECAST_STATUS_RESPONSE_TOO_LARGE = 12,
+  /**
+   * Covering a time window would take more requests than allowed.
+   */
+  FORECAST_STATUS_ESCALATION_LIMIT = 13,
+} ForecastStatus;
+
+/**
+ * Current conditions. Measurements the API didn't report are NaN.
+ */
+typedef struct ForecastCurrent {
+  int64_t time;
+  double temperature;
+  double apparent_temperature;
+  double dew_point;
+  double humidity;
+  double pressure;
+  double wind_speed;
+  double wind_bearing;
+  double cloud_cover;
+  double precip_intensity;
+  double precip_probability;
+  double visibility;
+} ForecastCurrent;
+
+/**
+ * Fetch the current conditions at a location into `out`.
+ *
+ * # Safety
+ *
+ * `api_key` must be a valid null-terminated string and `out` must
+ * point to writable memory for a `ForecastCurrent`.
+ */
+enum ForecastStatus forecast_get_current(const char *api_key,
+                                         double latitude,
+                                         double longitude,
+                                         struct ForecastCurrent *out);
+
+/**
+ * Fetch the forecast for a location as a JSON string, stored in
+ * `out_json`. The string must be freed with `forecast_string_free`.
+ *
+ * # Safety
+ *
+ * `api_key` must be a valid null-terminated string and `out_json` must
+ * point to writable memory for a pointer.
+ */
+enum ForecastStatus forecast_get_forecast_json(const char *api_key,
+                                               double latitude,
+                                               double longitude,
+                                               char **out_json);
+
+/**
+ * Free a string returned by this library. Does nothing if `s` is null.
+ *
+ * # Safety
+ *
+ * `s` must be null or a string returned by this library which hasn't
+ * already been freed.
+ */
+void forecast_string_free(char *s);
+
+#endif  /* FORECAST_H */
@@ -0,0 +1,128 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Text alternatives for icons and alerts, e.g. for `aria-label` and
+//! `alt` attributes, and a color for each alert severity, so frontends
+//! describe the weather consistently.
+
+use crate::{Alert, Icon, Severity};
+
+// constants
+
+// Alert titles are cut off at the first of these, e.g. "Flood Warning
+// issued March 3 at 4:00PM EST until March 4 at 10:00AM EST by NWS".
+static TITLE_QUALIFIERS: [&str; 4] = [" issued ", " until ", " in effect ", " by "];
+
+const MAX_TITLE_CHARS: usize = 40;
+
+impl Icon {
+    /// A short English description of the icon for screen readers, e.g.
+    /// "Partly cloudy".
+    pub fn aria_label(&self) -> &'static str {
+        match self {
+            Icon::ClearDay => "Clear",
+            Icon::ClearNight => "Clear night",
+            Icon::Rain => "Rain",
+            Icon::Snow => "Snow",
+            Icon::Sleet => "Sleet",
+            Icon::Wind => "Windy",
+            Icon::Fog => "Fog",
+            Icon::Cloudy => "Cloudy",
+            Icon::PartlyCloudyDay => "Partly cloudy",
+            Icon::PartlyCloudyNight => "Partly cloudy night",
+            Icon::Hail => "Hail",
+            Icon::Thunderstorm => "Thunderstorm",
+            Icon::Tornado => "Tornado"
+        }
+    }
+}
+
+impl Alert {
+    /// The alert's title without the issuing details which some
+    /// authorities append, e.g. "Flood Warning", shortened to at most 40
+    /// characters at a word boundary.
+    pub fn short_title(&self) -> String {
+        let title = self.title.trim();
+        let end = TITLE_QUALIFIERS.iter()
+            .filter_map(|qualifier| title.find(qualifier))
+            .filter(|&end| end > 0)
+            .min()
+            .unwrap_or(title.len());
+        let title = title[..end].trim();
+
+        if title.chars().count() <= MAX_TITLE_CHARS {
+            return title.to_string();
+        }
+
+        let cut = title.char_indices().nth(MAX_TITLE_CHARS - 1).map_or(title.len(), |(i, _)| i);
+        let cut = title[..cut].rfind(' ').unwrap_or(cut);
+
+        format!("{}…", title[..cut].trim_end())
+    }
+}
+
+impl Severity {
+    /// A color token for the severity, following the usual warning
+    /// colors: "yellow" for advisories, "orange" for watches and "red"
+    /// for warnings. Don't rely on color alone; pair it with the
+    /// severity's name.
+    pub fn color_token(&self) -> &'static str {
+        match self {
+            Severity::Advisory => "yellow",
+            Severity::Watch => "orange",
+            Severity::Warning => "red"
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::{Alert, Icon, Severity};
+
+    fn alert(title: &str) -> Alert {
+        Alert {
+            description: String::new(),
+            expires: 0,
+            regions: Vec::new(),
+            severity: Severity::Warning,
+            time: 0,
+            title: title.to_string(),
+            uri: String::new()
+        }
+    }
+
+    #[test]
+    fn test_short_title() {
+        assert_eq!(
+            alert("Flood Warning issued March 3 at 4:00PM EST until March 4 at 10:00AM EST by NWS")
+                .short_title(),
+            "Flood Warning"
+        );
+        assert_eq!(alert(" Wind Advisory ").short_title(), "Wind Advisory");
+        assert_eq!(
+            alert("Special Weather Statement regarding strong thunderstorms over the coast")
+                .short_title(),
+            "Special Weather Statement regarding…"
+        );
+    }
+
+    #[test]
+    fn test_labels_and_colors() {
+        assert_eq!(Icon::PartlyCloudyNight.aria_label(), "Partly cloudy night");
+        assert_eq!(Severity::Advisory.color_token(), "yellow");
+        assert_eq!(Severity::Warning.color_token(), "red");
+    }
+}
@@ -0,0 +1,312 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Hourly air quality from the [Open-Meteo Air Quality
+//! API](https://open-meteo.com/en/docs/air-quality-api), joined with
+//! the hourly forecast for the same location.
+//!
+//! ```no_run
+//! # async fn example(forecast: forecast::ApiResponse) -> forecast::error::Result<()> {
+//! use forecast::air_quality::{AirQualityClient, join_hourly};
+//!
+//! let air_quality = AirQualityClient::new(forecast::http::Client::new())
+//!     .get_hourly(forecast.latitude, forecast.longitude)
+//!     .await?;
+//!
+//! for (point, air) in join_hourly(&forecast, &air_quality) {
+//!     let aqi = air.and_then(|air| air.us_aqi);
+//!
+//!     println!("{}: {:?}°, AQI {:?}", point.time, point.temperature, aqi);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `AirQualityClient` is also a `PollenSource`, for Open-Meteo's
+//! European pollen forecast.
+//!
+//! Requires the `air-quality` feature.
+
+use std::collections::HashMap;
+
+use crate::error::{self, Error};
+use crate::http::{self, Client, Url};
+use crate::pollen::{PollenCount, PollenFuture, PollenSource};
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+static AIR_QUALITY_URL: &str = "https://air-quality-api.open-meteo.com/v1/air-quality";
+static HOURLY_VARIABLES: &str = "pm2_5,pm10,us_aqi";
+static POLLEN_VARIABLES: &str =
+    "alder_pollen,birch_pollen,olive_pollen,grass_pollen,mugwort_pollen,ragweed_pollen";
+
+/// The air quality in one hour.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct AirQuality {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// Fine particulate matter, in μg/m³.
+    pub pm2_5: Option<f64>,
+
+    /// Coarse particulate matter, in μg/m³.
+    pub pm10: Option<f64>,
+
+    /// The US EPA Air Quality Index.
+    pub us_aqi: Option<f64>
+}
+
+#[derive(Deserialize)]
+struct AirQualityResponse {
+    hourly: HourlyAirQuality
+}
+
+#[derive(Deserialize)]
+struct HourlyAirQuality {
+    time: Vec<i64>,
+
+    #[serde(default)]
+    pm2_5: Vec<Option<f64>>,
+
+    #[serde(default)]
+    pm10: Vec<Option<f64>>,
+
+    #[serde(default)]
+    us_aqi: Vec<Option<f64>>,
+
+    #[serde(default)]
+    alder_pollen: Vec<Option<f64>>,
+
+    #[serde(default)]
+    birch_pollen: Vec<Option<f64>>,
+
+    #[serde(default)]
+    olive_pollen: Vec<Option<f64>>,
+
+    #[serde(default)]
+    grass_pollen: Vec<Option<f64>>,
+
+    #[serde(default)]
+    mugwort_pollen: Vec<Option<f64>>,
+
+    #[serde(default)]
+    ragweed_pollen: Vec<Option<f64>>
+}
+
+impl HourlyAirQuality {
+    fn parse(body: &[u8]) -> Result<HourlyAirQuality, serde_json::Error> {
+        Ok(serde_json::from_slice::<AirQualityResponse>(body)?.hourly)
+    }
+
+    fn air_quality(&self) -> Vec<AirQuality> {
+        self.time.iter()
+            .enumerate()
+            .map(|(index, time)| AirQuality {
+                time: *time,
+                pm2_5: HourlyAirQuality::value(&self.pm2_5, index),
+                pm10: HourlyAirQuality::value(&self.pm10, index),
+                us_aqi: HourlyAirQuality::value(&self.us_aqi, index)
+            })
+            .collect()
+    }
+
+    fn pollen(&self) -> Vec<PollenCount> {
+        let trees = [&self.alder_pollen[..], &self.birch_pollen, &self.olive_pollen];
+        let weeds = [&self.mugwort_pollen[..], &self.ragweed_pollen];
+
+        self.time.iter()
+            .enumerate()
+            .map(|(index, time)| PollenCount {
+                time: *time,
+                grass: HourlyAirQuality::value(&self.grass_pollen, index),
+                tree: HourlyAirQuality::total(&trees, index),
+                weed: HourlyAirQuality::total(&weeds, index)
+            })
+            .collect()
+    }
+
+    fn value(values: &[Option<f64>], index: usize) -> Option<f64> {
+        values.get(index).cloned().flatten()
+    }
+
+    // the total of the counts present, or None if none are
+    fn total(series: &[&[Option<f64>]], index: usize) -> Option<f64> {
+        series.iter()
+            .filter_map(|values| HourlyAirQuality::value(values, index))
+            .fold(None, |total, count| Some(total.unwrap_or(0.0) + count))
+    }
+}
+
+/// Fetches hourly air quality from the Open-Meteo Air Quality API.
+#[derive(Debug, Clone)]
+pub struct AirQualityClient {
+    client: Client,
+    base_url: Url
+}
+
+impl AirQualityClient {
+    pub fn new(client: Client) -> AirQualityClient {
+        let base_url = Url::parse(AIR_QUALITY_URL).expect("the air quality URL is valid");
+
+        AirQualityClient { client, base_url }
+    }
+
+    /// Send requests to `base_url` instead of the public Open-Meteo
+    /// API, e.g. to a self-hosted instance.
+    pub fn with_base_url(mut self, base_url: Url) -> AirQualityClient {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Fetch the hourly air quality forecast for a location.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the API responds with a
+    /// non-success status, or the response can't be deserialized.
+    pub async fn get_hourly(&self, latitude: f64, longitude: f64)
+        -> error::Result<Vec<AirQuality>> {
+        Ok(self.get(latitude, longitude, HOURLY_VARIABLES).await?.air_quality())
+    }
+
+    /// Fetch the hourly pollen forecast for a location. Open-Meteo
+    /// forecasts pollen in Europe only; elsewhere the counts are `None`.
+    /// Tree pollen is the total of alder, birch and olive pollen, and
+    /// weed pollen the total of mugwort and ragweed pollen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as `get_hourly`.
+    pub async fn get_pollen(&self, latitude: f64, longitude: f64)
+        -> error::Result<Vec<PollenCount>> {
+        Ok(self.get(latitude, longitude, POLLEN_VARIABLES).await?.pollen())
+    }
+
+    async fn get(&self, latitude: f64, longitude: f64, variables: &str)
+        -> error::Result<HourlyAirQuality> {
+        let mut url = self.base_url.clone();
+
+        url.query_pairs_mut()
+            .append_pair("latitude", &latitude.to_string())
+            .append_pair("longitude", &longitude.to_string())
+            .append_pair("hourly", variables)
+            .append_pair("timeformat", "unixtime");
+
+        let response = self.client.0.get(url).send().await.map_err(http::Error::new)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(http::Error::new)?;
+
+        if !status.is_success() {
+            return Err(Error::from_response(status, &body));
+        }
+
+        Ok(HourlyAirQuality::parse(&body)?)
+    }
+}
+
+impl PollenSource for AirQualityClient {
+    fn fetch(&self, latitude: f64, longitude: f64) -> PollenFuture<'_> {
+        Box::pin(self.get_pollen(latitude, longitude))
+    }
+}
+
+/// Pair each point of `response`'s hourly block with the air quality
+/// for the same hour, if there is one.
+pub fn join_hourly<'a>(response: &'a ApiResponse, air_quality: &'a [AirQuality])
+    -> Vec<(&'a DataPoint, Option<&'a AirQuality>)> {
+    let by_time: HashMap<i64, &AirQuality> = air_quality.iter()
+        .map(|hour| (hour.time, hour))
+        .collect();
+
+    response.hourly.iter()
+        .flatten()
+        .map(|point| (point, by_time.get(&point.time).cloned()))
+        .collect()
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{join_hourly, AirQuality, HourlyAirQuality};
+
+    use crate::pollen::PollenCount;
+    use crate::testing;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_hourly() {
+        let body = json!({
+            "latitude": 52.52, "longitude": 13.42,
+            "hourly_units": {"time": "unixtime", "pm2_5": "μg/m³"},
+            "hourly": {
+                "time": [0, 3600, 7200],
+                "pm2_5": [10.5, null, 12.0],
+                "pm10": [20.0, 21.0],
+                "us_aqi": [44, 45, 47]
+            }
+        });
+
+        let hours = HourlyAirQuality::parse(body.to_string().as_bytes()).unwrap().air_quality();
+
+        assert_eq!(hours.len(), 3);
+        assert_eq!(hours[0], AirQuality {
+            time: 0, pm2_5: Some(10.5), pm10: Some(20.0), us_aqi: Some(44.0)
+        });
+        assert_eq!(hours[1].pm2_5, None);
+        assert_eq!(hours[2].pm10, None);
+        assert!(HourlyAirQuality::parse(b"{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_pollen() {
+        let body = json!({
+            "hourly": {
+                "time": [0, 3600],
+                "alder_pollen": [1.0, null],
+                "birch_pollen": [2.5, null],
+                "olive_pollen": [0.0, null],
+                "grass_pollen": [12.0, 3.0],
+                "mugwort_pollen": [null, null],
+                "ragweed_pollen": [null, 4.0]
+            }
+        });
+
+        let pollen = HourlyAirQuality::parse(body.to_string().as_bytes()).unwrap().pollen();
+
+        assert_eq!(pollen, vec![
+            PollenCount { time: 0, grass: Some(12.0), tree: Some(3.5), weed: None },
+            PollenCount { time: 3600, grass: Some(3.0), tree: None, weed: Some(4.0) }
+        ]);
+    }
+
+    #[test]
+    fn test_join_hourly() {
+        let response = testing::response(json!({
+            "hourly": {"data": [{"time": 0}, {"time": 3600}]}
+        }));
+        let air_quality = vec![
+            AirQuality { time: 3600, pm2_5: Some(5.0), pm10: None, us_aqi: Some(21.0) }
+        ];
+
+        let joined = join_hourly(&response, &air_quality);
+
+        assert_eq!(joined.len(), 2);
+        assert_eq!(joined[0].1, None);
+        assert_eq!(joined[1].0.time, 3600);
+        assert_eq!(joined[1].1, Some(&air_quality[0]));
+    }
+}
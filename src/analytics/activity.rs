@@ -0,0 +1,279 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Hourly scores of how pleasant conditions are for outdoor exercise
+//! such as running or cycling, and the best times to go out.
+//!
+//! Each hour is scored from 0 to 100 as the product of a factor from 0
+//! to 1 for each of:
+//!
+//! * temperature: 1 within the ideal range, falling to 0 at
+//!   `temperature_tolerance` degrees outside it;
+//! * wind speed: 1 up to half of `max_wind_speed`, falling to 0 at it;
+//! * chance of precipitation: falling from 1 with no chance to 0 at
+//!   `max_precip_probability`;
+//! * UV index: 1 up to 5 (moderate), falling to 0 at `max_uv_index`;
+//! * humidity: 1 up to 60%, falling to 0.5 at 100%, since humid air is
+//!   uncomfortable but rarely a reason to stay in.
+//!
+//! A factor whose field the forecast omits is 1. Every parameter is in
+//! SI units, whatever units the response is in.
+
+use crate::convert::{celsius, meters_per_second, response_units};
+use crate::{ApiResponse, DataPoint, Units};
+
+// constants
+
+const MODERATE_UV_INDEX: f64 = 5.0;
+const COMFORTABLE_HUMIDITY: f64 = 0.6;
+const MAX_HUMIDITY_PENALTY: f64 = 0.5;
+const SECONDS_PER_HOUR: i64 = 3600;
+
+// the look-ahead of `best_windows`
+const HORIZON_HOURS: usize = 48;
+
+/// An hour's score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyScore {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// The score, from 0 (stay in) to 100 (perfect).
+    pub score: f64
+}
+
+/// A run of consecutive hours, as found by `ActivityScorer::best_windows`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window {
+    /// The start of the first hour, in seconds since the UNIX epoch.
+    pub start: i64,
+
+    /// The end of the last hour, in seconds since the UNIX epoch.
+    pub end: i64,
+
+    /// The mean score of the hours.
+    pub score: f64
+}
+
+/// Scores hours for an outdoor activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityScorer {
+    ideal_temperature: (f64, f64),
+    temperature_tolerance: f64,
+    max_wind_speed: f64,
+    max_precip_probability: f64,
+    max_uv_index: f64
+}
+
+impl ActivityScorer {
+    /// A scorer for running: ideally 8–16 °C, in winds up to 15 m/s.
+    pub fn running() -> ActivityScorer {
+        ActivityScorer {
+            ideal_temperature: (8.0, 16.0),
+            temperature_tolerance: 15.0,
+            max_wind_speed: 15.0,
+            max_precip_probability: 0.8,
+            max_uv_index: 11.0
+        }
+    }
+
+    /// A scorer for cycling: ideally 14–24 °C, in winds up to 12 m/s.
+    pub fn cycling() -> ActivityScorer {
+        ActivityScorer {
+            ideal_temperature: (14.0, 24.0),
+            temperature_tolerance: 15.0,
+            max_wind_speed: 12.0,
+            max_precip_probability: 0.6,
+            max_uv_index: 11.0
+        }
+    }
+
+    /// The ideal range of temperatures, in °C.
+    pub fn ideal_temperature(mut self, low: f64, high: f64) -> ActivityScorer {
+        self.ideal_temperature = (low, high);
+        self
+    }
+
+    /// How many degrees outside the ideal range the temperature factor
+    /// reaches 0.
+    pub fn temperature_tolerance(mut self, degrees: f64) -> ActivityScorer {
+        self.temperature_tolerance = degrees;
+        self
+    }
+
+    /// The wind speed, in m/s, at which the wind factor reaches 0.
+    pub fn max_wind_speed(mut self, speed: f64) -> ActivityScorer {
+        self.max_wind_speed = speed;
+        self
+    }
+
+    /// The chance of precipitation, from 0 to 1, at which the
+    /// precipitation factor reaches 0.
+    pub fn max_precip_probability(mut self, probability: f64) -> ActivityScorer {
+        self.max_precip_probability = probability;
+        self
+    }
+
+    /// The UV index at which the UV factor reaches 0.
+    pub fn max_uv_index(mut self, uv_index: f64) -> ActivityScorer {
+        self.max_uv_index = uv_index;
+        self
+    }
+
+    /// The score of each point of `response`'s hourly block which has a
+    /// temperature.
+    pub fn hourly(&self, response: &ApiResponse) -> Vec<HourlyScore> {
+        let units = response_units(response);
+
+        response.hourly.iter()
+            .flatten()
+            .filter_map(|point| {
+                Some(HourlyScore { time: point.time, score: self.score(point, units)? })
+            })
+            .collect()
+    }
+
+    /// Up to `count` non-overlapping windows of `hours` consecutive
+    /// hours with the highest mean scores within the first 48 hours of
+    /// `response`'s hourly block, best first.
+    pub fn best_windows(&self, response: &ApiResponse, hours: usize, count: usize)
+        -> Vec<Window> {
+        let scores: Vec<HourlyScore> = self.hourly(response).into_iter()
+            .take(HORIZON_HOURS)
+            .collect();
+
+        if hours == 0 {
+            return Vec::new();
+        }
+
+        let span = (hours as i64 - 1) * SECONDS_PER_HOUR;
+        let mut candidates: Vec<Window> = scores.windows(hours)
+            .filter(|run| run[hours - 1].time - run[0].time == span)
+            .map(|run| Window {
+                start: run[0].time,
+                end: run[hours - 1].time + SECONDS_PER_HOUR,
+                score: run.iter().map(|hour| hour.score).sum::<f64>() / hours as f64
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.start.cmp(&b.start)));
+
+        let mut windows: Vec<Window> = Vec::new();
+
+        for candidate in candidates {
+            if windows.len() == count {
+                break;
+            }
+
+            if windows.iter().all(|w| candidate.end <= w.start || w.end <= candidate.start) {
+                windows.push(candidate);
+            }
+        }
+
+        windows
+    }
+
+    fn score(&self, point: &DataPoint, units: &Units) -> Option<f64> {
+        let temperature = celsius(point.temperature?, units);
+        let (low, high) = self.ideal_temperature;
+        let outside = (low - temperature).max(temperature - high).max(0.0);
+
+        let factors = [
+            falloff(outside, 0.0, self.temperature_tolerance),
+            point.wind_speed.map_or(1.0, |speed| {
+                let speed = meters_per_second(speed, units);
+
+                falloff(speed, self.max_wind_speed / 2.0, self.max_wind_speed)
+            }),
+            point.precip_probability.map_or(1.0, |p| falloff(p, 0.0, self.max_precip_probability)),
+            point.uv_index.map_or(1.0, |uv| falloff(uv, MODERATE_UV_INDEX, self.max_uv_index)),
+            point.humidity.map_or(1.0, |humidity| {
+                1.0 - MAX_HUMIDITY_PENALTY * (1.0 - falloff(humidity, COMFORTABLE_HUMIDITY, 1.0))
+            })
+        ];
+
+        Some(100.0 * factors.iter().product::<f64>())
+    }
+}
+
+// 1 at or below `good`, 0 at or above `bad`, linear in between
+fn falloff(value: f64, good: f64, bad: f64) -> f64 {
+    if bad <= good {
+        return if value <= good { 1.0 } else { 0.0 };
+    }
+
+    ((bad - value) / (bad - good)).clamp(0.0, 1.0)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{ActivityScorer, Window};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    fn response(hourly: Vec<Value>) -> ApiResponse {
+        testing::response(json!({
+            "hourly": {"data": hourly},
+            "flags": {"sources": [], "units": "si"}
+        }))
+    }
+
+    #[test]
+    fn test_hourly() {
+        let response = response(vec![
+            json!({"time": 0, "temperature": 12.0, "windSpeed": 3.0, "precipProbability": 0.0,
+                   "uvIndex": 2.0, "humidity": 0.5}),
+            json!({"time": 3600, "temperature": 23.5}),
+            json!({"time": 7200, "temperature": 12.0, "precipProbability": 0.4}),
+            json!({"time": 10800, "temperature": 12.0, "windSpeed": 20.0}),
+            json!({"time": 14400, "temperature": 12.0, "humidity": 1.0}),
+            json!({"time": 18000})
+        ]);
+
+        let scores: Vec<f64> = ActivityScorer::running().hourly(&response).iter()
+            .map(|hour| hour.score)
+            .collect();
+
+        assert_eq!(scores, vec![100.0, 50.0, 50.0, 0.0, 50.0]);
+
+        let cycling = ActivityScorer::cycling().hourly(&response);
+
+        assert_eq!(cycling[1].score, 100.0);
+    }
+
+    #[test]
+    fn test_best_windows() {
+        let temperatures = [30.0, 12.0, 12.0, 20.0, 12.0, 16.0, 12.0];
+        let response = response(temperatures.iter()
+            .enumerate()
+            .map(|(hour, t)| json!({"time": hour as i64 * 3600, "temperature": t}))
+            .collect());
+
+        let windows = ActivityScorer::running().best_windows(&response, 2, 3);
+
+        assert_eq!(windows, vec![
+            Window { start: 3600, end: 10_800, score: 100.0 },
+            Window { start: 14_400, end: 21_600, score: 100.0 }
+        ]);
+        assert_eq!(ActivityScorer::running().best_windows(&response, 3, 1)[0].start, 14_400);
+
+        assert!(ActivityScorer::running().best_windows(&response, 0, 3).is_empty());
+        assert!(ActivityScorer::running().best_windows(&response, 8, 3).is_empty());
+    }
+}
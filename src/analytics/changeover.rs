@@ -0,0 +1,192 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Changes in the type of precipitation, such as rain turning to snow,
+//! forecast by a block, usually the `hourly` block.
+//!
+//! An hour counts as precipitating when the API forecasts a non-zero
+//! intensity and a type. Each precipitating hour whose type differs
+//! from that of the last precipitating hour before it is reported as a
+//! `Changeover`, along with the temperature trend over the hours
+//! leading up to it, which tells whether the changeover is backed by
+//! warming or cooling or is more likely an artifact of the model.
+
+use crate::{DataBlock, DataPoint, PrecipType};
+
+// constants
+
+// the number of points over which the temperature trend is measured
+const TREND_POINTS: usize = 3;
+
+// temperature changes smaller than this, in either unit, are steady
+const STEADY_DELTA: f64 = 0.5;
+
+/// Which way the temperature is heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Falling,
+    Steady,
+    Rising
+}
+
+/// A change in the type of precipitation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Changeover {
+    /// The start, in seconds since the UNIX epoch, of the first point
+    /// with the new type of precipitation.
+    pub time: i64,
+
+    pub from: PrecipType,
+
+    pub to: PrecipType,
+
+    /// The temperature at `time`, if known.
+    pub temperature: Option<f64>,
+
+    /// The temperature trend over the points leading up to `time`, if
+    /// known.
+    pub trend: Option<Trend>
+}
+
+impl Changeover {
+    /// Whether the temperature trend agrees with the changeover: falling
+    /// as rain turns to sleet or snow, or rising as snow turns to sleet
+    /// or rain.
+    pub fn is_consistent(&self) -> bool {
+        let colder = coldness(&self.to) > coldness(&self.from);
+
+        match self.trend {
+            Some(Trend::Falling) => colder,
+            Some(Trend::Rising) => !colder,
+            _ => false
+        }
+    }
+}
+
+impl DataBlock {
+    /// The changes in the type of precipitation forecast by this block,
+    /// in chronological order. The block must be sorted by time.
+    pub fn precip_changeovers(&self) -> Vec<Changeover> {
+        let mut changeovers = Vec::new();
+        let mut current: Option<&PrecipType> = None;
+
+        for (index, point) in self.data.iter().enumerate() {
+            let precip_type = match precip_type(point) {
+                Some(precip_type) => precip_type,
+                None => continue
+            };
+
+            if let Some(from) = current.filter(|from| *from != precip_type) {
+                changeovers.push(Changeover {
+                    time: point.time,
+                    from: *from,
+                    to: *precip_type,
+                    temperature: point.temperature,
+                    trend: trend(&self.data[index.saturating_sub(TREND_POINTS)..=index])
+                });
+            }
+
+            current = Some(precip_type);
+        }
+
+        changeovers
+    }
+}
+
+fn precip_type(point: &DataPoint) -> Option<&PrecipType> {
+    point.precip_type.as_ref().filter(|_| point.precip_intensity.is_some_and(|i| i > 0.0))
+}
+
+fn coldness(precip_type: &PrecipType) -> u8 {
+    match precip_type {
+        PrecipType::Rain => 0,
+        PrecipType::Sleet => 1,
+        PrecipType::Snow => 2
+    }
+}
+
+fn trend(points: &[DataPoint]) -> Option<Trend> {
+    let first = points.iter().find_map(|point| point.temperature)?;
+    let last = points.last()?.temperature?;
+    let delta = last - first;
+
+    Some(if delta <= -STEADY_DELTA {
+        Trend::Falling
+    } else if delta >= STEADY_DELTA {
+        Trend::Rising
+    } else {
+        Trend::Steady
+    })
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::Trend;
+
+    use crate::{DataBlock, PrecipType};
+
+    use serde_json::json;
+
+    fn block(hours: &[(Option<&str>, f64)]) -> DataBlock {
+        let data: Vec<_> = hours.iter()
+            .enumerate()
+            .map(|(hour, (precip_type, temperature))| json!({
+                "time": hour as i64 * 3600,
+                "precipIntensity": if precip_type.is_some() { 0.05 } else { 0.0 },
+                "precipType": precip_type,
+                "temperature": temperature
+            }))
+            .collect();
+
+        serde_json::from_value(json!({"data": data})).unwrap()
+    }
+
+    #[test]
+    fn test_precip_changeovers() {
+        let block = block(&[
+            (Some("rain"), 36.0),
+            (Some("rain"), 35.0),
+            (None, 34.0),
+            (Some("snow"), 32.0),
+            (Some("snow"), 32.0),
+            (Some("sleet"), 32.2)
+        ]);
+
+        let changeovers = block.precip_changeovers();
+
+        assert_eq!(changeovers.len(), 2);
+
+        assert_eq!(changeovers[0].time, 3 * 3600);
+        assert_eq!(changeovers[0].from, PrecipType::Rain);
+        assert_eq!(changeovers[0].to, PrecipType::Snow);
+        assert_eq!(changeovers[0].temperature, Some(32.0));
+        assert_eq!(changeovers[0].trend, Some(Trend::Falling));
+        assert!(changeovers[0].is_consistent());
+
+        assert_eq!(changeovers[1].time, 5 * 3600);
+        assert_eq!(changeovers[1].to, PrecipType::Sleet);
+        assert_eq!(changeovers[1].trend, Some(Trend::Falling));
+        assert!(!changeovers[1].is_consistent());
+    }
+
+    #[test]
+    fn test_no_precip_changeovers() {
+        assert!(block(&[(Some("rain"), 40.0), (None, 30.0), (Some("rain"), 35.0)])
+            .precip_changeovers()
+            .is_empty());
+        assert!(block(&[]).precip_changeovers().is_empty());
+    }
+}
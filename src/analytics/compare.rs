@@ -0,0 +1,231 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Comparison of forecasts for the same location obtained from
+//! different sources.
+//!
+//! Fetching is left to the caller: each response is passed to
+//! `compare` along with a label identifying where it came from, and the
+//! resulting `ComparisonReport` describes how far apart the sources are
+//! for each numeric field of the `currently`, `hourly`, and `daily`
+//! blocks.
+
+use crate::{ApiResponse, DataBlock, DataPoint};
+
+type Accessor = fn(&DataPoint) -> Option<f64>;
+
+static COMPARED_FIELDS: &[(&str, Accessor)] = &[
+    ("temperature", |p| p.temperature),
+    ("apparentTemperature", |p| p.apparent_temperature),
+    ("temperatureHigh", |p| p.temperature_high),
+    ("temperatureLow", |p| p.temperature_low),
+    ("precipProbability", |p| p.precip_probability),
+    ("precipIntensity", |p| p.precip_intensity),
+    ("humidity", |p| p.humidity),
+    ("pressure", |p| p.pressure),
+    ("windSpeed", |p| p.wind_speed),
+    ("windGust", |p| p.wind_gust),
+    ("cloudCover", |p| p.cloud_cover)
+];
+
+/// The values reported for a single field by each source, along with
+/// their spread (the difference between the largest and smallest
+/// value).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldComparison {
+    pub field: &'static str,
+
+    pub values: Vec<(String, f64)>,
+
+    pub spread: f64
+}
+
+impl FieldComparison {
+    /// Whether every source agrees on this field to within `tolerance`.
+    pub fn agrees(&self, tolerance: f64) -> bool {
+        self.spread <= tolerance
+    }
+}
+
+/// The comparison of every field reported by at least two sources for
+/// a single point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointComparison {
+    pub time: i64,
+
+    pub fields: Vec<FieldComparison>
+}
+
+impl PointComparison {
+    /// Return the comparison for `field`, named as in the API response
+    /// (e.g. `"precipProbability"`).
+    pub fn field(&self, field: &str) -> Option<&FieldComparison> {
+        self.fields.iter().find(|f| f.field == field)
+    }
+
+    /// Return the fields on which the sources disagree by more than
+    /// `tolerance`.
+    pub fn disagreements(&self, tolerance: f64) -> Vec<&FieldComparison> {
+        self.fields.iter().filter(|f| !f.agrees(tolerance)).collect()
+    }
+}
+
+/// Per-field agreement report across several sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport {
+    pub sources: Vec<String>,
+
+    pub currently: Option<PointComparison>,
+
+    pub hourly: Vec<PointComparison>,
+
+    pub daily: Vec<PointComparison>
+}
+
+impl ComparisonReport {
+    /// The largest spread observed for `field` across the `currently`,
+    /// `hourly`, and `daily` comparisons.
+    pub fn max_spread(&self, field: &str) -> Option<f64> {
+        self.currently.iter()
+            .chain(self.hourly.iter())
+            .chain(self.daily.iter())
+            .filter_map(|p| p.field(field))
+            .map(|f| f.spread)
+            .fold(None, |max, spread| Some(max.map_or(spread, |m: f64| m.max(spread))))
+    }
+}
+
+/// Compare responses for the same location from several sources, each
+/// labelled by name. Points within the `hourly` and `daily` blocks are
+/// aligned by their `time`; only times reported by at least two sources
+/// are compared.
+pub fn compare(responses: &[(&str, &ApiResponse)]) -> ComparisonReport {
+    let currently: Vec<(&str, &DataPoint)> = responses.iter()
+        .filter_map(|(source, r)| r.currently.as_ref().map(|p| (*source, p)))
+        .collect();
+
+    ComparisonReport {
+        sources: responses.iter().map(|(source, _)| source.to_string()).collect(),
+        currently: compare_points(&currently),
+        hourly: compare_blocks(responses, |r| r.hourly.as_ref()),
+        daily: compare_blocks(responses, |r| r.daily.as_ref())
+    }
+}
+
+fn compare_blocks<F>(responses: &[(&str, &ApiResponse)], block: F) -> Vec<PointComparison>
+    where F: Fn(&ApiResponse) -> Option<&DataBlock> {
+    let mut times: Vec<i64> = responses.iter()
+        .filter_map(|(_, r)| block(r))
+        .flat_map(|b| b.data.iter().map(|p| p.time))
+        .collect();
+
+    times.sort_unstable();
+    times.dedup();
+
+    times.into_iter()
+        .filter_map(|time| {
+            let points: Vec<(&str, &DataPoint)> = responses.iter()
+                .filter_map(|(source, r)| {
+                    block(r)
+                        .and_then(|b| b.data.iter().find(|p| p.time == time))
+                        .map(|p| (*source, p))
+                })
+                .collect();
+
+            compare_points(&points)
+        })
+        .collect()
+}
+
+fn compare_points(points: &[(&str, &DataPoint)]) -> Option<PointComparison> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let fields = COMPARED_FIELDS.iter()
+        .filter_map(|(field, accessor)| {
+            let values: Vec<(String, f64)> = points.iter()
+                .filter_map(|(source, p)| accessor(p).map(|v| (source.to_string(), v)))
+                .collect();
+
+            if values.len() < 2 {
+                return None;
+            }
+
+            let max = values.iter().map(|(_, v)| *v).fold(f64::NEG_INFINITY, f64::max);
+            let min = values.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+
+            Some(FieldComparison { field, values, spread: max - min })
+        })
+        .collect();
+
+    Some(PointComparison { time: points[0].1.time, fields })
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::compare;
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    fn response(temperature: f64, precip_probability: f64) -> ApiResponse {
+        testing::response(json!({
+            "currently": {"time": 10, "temperature": temperature},
+            "hourly": {"data": [
+                {"time": 10, "precipProbability": precip_probability},
+                {"time": 20, "precipProbability": precip_probability}
+            ]}
+        }))
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = response(10.0, 0.2);
+        let b = response(12.5, 0.7);
+        let c = response(11.0, 0.2);
+
+        let report = compare(&[("a", &a), ("b", &b), ("c", &c)]);
+
+        assert_eq!(report.sources, vec!["a", "b", "c"]);
+
+        let currently = report.currently.as_ref().unwrap();
+        let temperature = currently.field("temperature").unwrap();
+
+        assert_eq!(temperature.values.len(), 3);
+        assert_eq!(temperature.spread, 2.5);
+        assert!(!temperature.agrees(1.0));
+        assert!(temperature.agrees(3.0));
+
+        assert_eq!(report.hourly.len(), 2);
+        assert_eq!(report.hourly[1].time, 20);
+        assert_eq!(report.hourly[1].disagreements(0.1).len(), 1);
+        assert!(report.daily.is_empty());
+        assert!((report.max_spread("precipProbability").unwrap() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_single_source() {
+        let a = response(10.0, 0.2);
+
+        let report = compare(&[("a", &a)]);
+
+        assert!(report.currently.is_none());
+        assert!(report.hourly.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! A heuristic confidence score for each hour of the forecast, so a UI
+//! can say how sure it is, e.g. by fading out uncertain hours.
+//!
+//! The score, from 0 to 1, is the product of three factors:
+//!
+//! * lead time: 1 for the current hour, falling linearly to 0.5 for an
+//!   hour a week away;
+//! * precipitation: how close the chance of precipitation is to a coin
+//!   toss, or, for hours covered by the `minutely` block, how much the
+//!   chance varies between its minutes, whichever is worse, costs up
+//!   to 0.4;
+//! * flags: a degraded response, i.e. one with `warnings`, scores half.
+//!
+//! The score is not a calibrated probability; it only ranks hours.
+
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+const MAX_LEAD_HOURS: f64 = 168.0;
+const LEAD_PENALTY: f64 = 0.5;
+const PRECIP_PENALTY: f64 = 0.4;
+const DEGRADED_FACTOR: f64 = 0.5;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// How confident the forecast is for one hour.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HourConfidence {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// Hours from the time of the forecast until `time`.
+    pub lead_hours: f64,
+
+    /// How confident the forecast is, from 0 to 1.
+    pub confidence: f64
+}
+
+impl ApiResponse {
+    /// The confidence of each point of the `hourly` block, in order.
+    /// Empty if the response has no hourly data.
+    pub fn hourly_confidence(&self) -> Vec<HourConfidence> {
+        let hourly = match &self.hourly {
+            Some(hourly) => hourly,
+            None => return Vec::new()
+        };
+        let now = match self.currently.as_ref().or_else(|| hourly.first()) {
+            Some(point) => point.time,
+            None => return Vec::new()
+        };
+        let flags = if self.warnings().is_empty() { 1.0 } else { DEGRADED_FACTOR };
+
+        hourly.iter()
+            .map(|point| {
+                let lead_hours = ((point.time - now) as f64 / SECONDS_PER_HOUR as f64).max(0.0);
+                let lead = 1.0 - LEAD_PENALTY * (lead_hours / MAX_LEAD_HOURS).min(1.0);
+                let precip = 1.0 - PRECIP_PENALTY * self.precip_ambiguity(point);
+
+                HourConfidence {
+                    time: point.time,
+                    lead_hours,
+                    confidence: (lead * precip * flags).clamp(0.0, 1.0)
+                }
+            })
+            .collect()
+    }
+
+    /// How uncertain precipitation is during the hour starting at
+    /// `point`, from 0 to 1.
+    fn precip_ambiguity(&self, point: &DataPoint) -> f64 {
+        let toss = point.precip_probability.map_or(0.0, |p| 1.0 - (2.0 * p - 1.0).abs());
+
+        let minutes: Vec<f64> = self.minutely.iter()
+            .flatten()
+            .filter(|minute| minute.time >= point.time && minute.time < point.time + SECONDS_PER_HOUR)
+            .filter_map(|minute| minute.precip_probability)
+            .collect();
+        let spread = minutes.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+            - minutes.iter().cloned().fold(f64::INFINITY, f64::min);
+
+        if minutes.is_empty() { toss } else { toss.max(spread) }.clamp(0.0, 1.0)
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    fn response(hourly: Vec<Value>, minutely: Vec<Value>, flags: Value) -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.4, "longitude": -71.1, "timezone": "America/New_York", "offset": -5,
+            "minutely": {"data": minutely},
+            "hourly": {"data": hourly},
+            "flags": flags
+        }))
+    }
+
+    fn hour(i: i64, probability: f64) -> Value {
+        json!({"time": i * 3600, "precipProbability": probability})
+    }
+
+    fn flags() -> Value {
+        json!({"sources": [], "units": "us"})
+    }
+
+    #[test]
+    fn test_lead_time_and_precipitation() {
+        let hourly = vec![hour(0, 0.0), hour(1, 0.5), hour(84, 0.0), hour(168, 1.0), hour(200, 0.0)];
+
+        let scores = response(hourly, Vec::new(), flags()).hourly_confidence();
+
+        assert_eq!(scores.len(), 5);
+        assert_eq!(scores[0].confidence, 1.0);
+        assert!(scores[1].confidence < 0.6);
+        assert_eq!(scores[2].lead_hours, 84.0);
+        assert_eq!(scores[2].confidence, 0.75);
+        assert_eq!(scores[3].confidence, 0.5);
+        assert_eq!(scores[4].confidence, 0.5);
+    }
+
+    #[test]
+    fn test_minutely_spread_and_flags() {
+        let minutely: Vec<Value> = (0..60)
+            .map(|i| json!({"time": i * 60, "precipProbability": if i < 30 { 0.0 } else { 1.0 }}))
+            .collect();
+        let degraded = json!({"darksky-unavailable": "1", "sources": [], "units": "us"});
+
+        let scores = response(vec![hour(0, 1.0)], minutely.clone(), flags()).hourly_confidence();
+        assert_eq!(scores[0].confidence, 0.6);
+
+        let scores = response(vec![hour(0, 1.0)], Vec::new(), degraded).hourly_confidence();
+        assert_eq!(scores[0].confidence, 0.5);
+
+        assert!(response(Vec::new(), minutely, flags()).hourly_confidence().is_empty());
+    }
+}
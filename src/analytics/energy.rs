@@ -0,0 +1,211 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Building heating and cooling load forecasts from degree-days, for
+//! smart thermostats and energy planning.
+//!
+//! A building's heat loss is modeled as a single coefficient, its
+//! overall heat transfer coefficient UA in kW/K: the power needed to
+//! hold the inside one degree above the outside. For each hour:
+//!
+//! * The heating load is `UA × (heating base − T)` when the outside
+//!   temperature `T` is below the heating base temperature, raised by
+//!   `wind_factor` per m/s of wind for the extra air infiltration and
+//!   convective loss wind causes.
+//! * The cooling load is `UA × (T − cooling base)` when `T` is above
+//!   the cooling base temperature, raised by `humidity_factor` per
+//!   percentage point of relative humidity above 50% for the latent
+//!   load of dehumidifying the air.
+//!
+//! Both base temperatures already allow for internal gains from people,
+//! appliances and the sun, which is why the conventional heating base
+//! of 18 °C sits below a typical thermostat setting.
+
+use crate::convert::{celsius, meters_per_second, response_units};
+use crate::ApiResponse;
+
+// constants
+
+const LATENT_HUMIDITY: f64 = 0.5;
+
+/// The estimated loads in one hour, in kW.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyLoad {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    pub heating: f64,
+
+    pub cooling: f64
+}
+
+/// Heating and cooling degree-days for one day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeDays {
+    /// The start of the day, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    pub heating: f64,
+
+    pub cooling: f64
+}
+
+/// A building's thermal model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatLoad {
+    coefficient: f64,
+    heating_base: f64,
+    cooling_base: f64,
+    wind_factor: f64,
+    humidity_factor: f64
+}
+
+impl HeatLoad {
+    /// A building whose overall heat transfer coefficient is
+    /// `coefficient` kW/K.
+    pub fn new(coefficient: f64) -> HeatLoad {
+        HeatLoad {
+            coefficient,
+            heating_base: 18.0,
+            cooling_base: 22.0,
+            wind_factor: 0.02,
+            humidity_factor: 0.01
+        }
+    }
+
+    /// The outside temperature, in °C, below which the building needs
+    /// heating. Defaults to 18.
+    pub fn heating_base(mut self, temperature: f64) -> HeatLoad {
+        self.heating_base = temperature;
+        self
+    }
+
+    /// The outside temperature, in °C, above which the building needs
+    /// cooling. Defaults to 22.
+    pub fn cooling_base(mut self, temperature: f64) -> HeatLoad {
+        self.cooling_base = temperature;
+        self
+    }
+
+    /// The fractional increase in heating load per m/s of wind.
+    /// Defaults to 0.02.
+    pub fn wind_factor(mut self, factor: f64) -> HeatLoad {
+        self.wind_factor = factor;
+        self
+    }
+
+    /// The fractional increase in cooling load per percentage point of
+    /// relative humidity above 50%. Defaults to 0.01.
+    pub fn humidity_factor(mut self, factor: f64) -> HeatLoad {
+        self.humidity_factor = factor;
+        self
+    }
+
+    /// The estimated loads for each point of `response`'s hourly block
+    /// which has a temperature. Calm, 50% humid air is assumed where the
+    /// forecast omits wind or humidity.
+    pub fn hourly(&self, response: &ApiResponse) -> Vec<HourlyLoad> {
+        let units = response_units(response);
+
+        response.hourly.iter()
+            .flatten()
+            .filter_map(|point| {
+                let temperature = celsius(point.temperature?, units);
+                let wind_speed = point.wind_speed.map_or(0.0, |s| meters_per_second(s, units));
+                let humidity = point.humidity.unwrap_or(LATENT_HUMIDITY);
+
+                let heating = (self.heating_base - temperature).max(0.0)
+                    * (1.0 + self.wind_factor * wind_speed);
+                let cooling = (temperature - self.cooling_base).max(0.0)
+                    * (1.0 + self.humidity_factor * 100.0 * (humidity - LATENT_HUMIDITY).max(0.0));
+
+                Some(HourlyLoad {
+                    time: point.time,
+                    heating: self.coefficient * heating,
+                    cooling: self.coefficient * cooling
+                })
+            })
+            .collect()
+    }
+
+    /// The heating and cooling degree-days for each point of
+    /// `response`'s daily block which has a high and low temperature,
+    /// taking the day's mean temperature to be their average.
+    pub fn degree_days(&self, response: &ApiResponse) -> Vec<DegreeDays> {
+        let units = response_units(response);
+
+        response.daily.iter()
+            .flatten()
+            .filter_map(|point| {
+                let high = celsius(point.temperature_high?, units);
+                let low = celsius(point.temperature_low?, units);
+                let mean = (high + low) / 2.0;
+
+                Some(DegreeDays {
+                    time: point.time,
+                    heating: (self.heating_base - mean).max(0.0),
+                    cooling: (mean - self.cooling_base).max(0.0)
+                })
+            })
+            .collect()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{DegreeDays, HeatLoad, HourlyLoad};
+
+    use crate::testing;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_hourly() {
+        let response = testing::response(json!({
+            "hourly": {"data": [
+                {"time": 0, "temperature": 8.0},
+                {"time": 3600, "temperature": 8.0, "windSpeed": 10.0},
+                {"time": 7200, "temperature": 27.0, "humidity": 0.7},
+                {"time": 10800, "temperature": 20.0},
+                {"time": 14400}
+            ]},
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        let loads = HeatLoad::new(0.25).hourly(&response);
+
+        assert_eq!(loads, vec![
+            HourlyLoad { time: 0, heating: 2.5, cooling: 0.0 },
+            HourlyLoad { time: 3600, heating: 3.0, cooling: 0.0 },
+            HourlyLoad { time: 7200, heating: 0.0, cooling: 1.5 },
+            HourlyLoad { time: 10800, heating: 0.0, cooling: 0.0 }
+        ]);
+    }
+
+    #[test]
+    fn test_degree_days() {
+        let response = testing::response(json!({
+            "daily": {"data": [
+                {"time": 0, "temperatureHigh": 50.0, "temperatureLow": 32.0},
+                {"time": 86400, "temperatureHigh": 50.0}
+            ]}
+        }));
+
+        let degree_days = HeatLoad::new(0.25).heating_base(15.5).degree_days(&response);
+
+        assert_eq!(degree_days, vec![DegreeDays { time: 0, heating: 10.5, cooling: 0.0 }]);
+    }
+}
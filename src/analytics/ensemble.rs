@@ -0,0 +1,217 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Consensus forecasts built from several responses for the same
+//! location.
+//!
+//! An `Ensemble` merges its member responses into a single
+//! `ApiResponse`. The first member serves as the template for every
+//! non-numeric field (summaries, icons, alerts, flags), and each numeric
+//! field of the `currently`, `hourly`, and `daily` blocks is replaced by
+//! the combination of the values reported by every member at the same
+//! `time`, according to the configured `CombineStrategy`.
+
+use crate::{ApiResponse, DataBlock, DataPoint};
+
+type Field = (fn(&DataPoint) -> Option<f64>, fn(&mut DataPoint) -> &mut Option<f64>);
+
+macro_rules! combined_fields {
+    ($($field:ident),*) => {
+        &[$((|p: &DataPoint| p.$field, |p: &mut DataPoint| &mut p.$field)),*]
+    }
+}
+
+static COMBINED_FIELDS: &[Field] = combined_fields!(
+    apparent_temperature, apparent_temperature_high, apparent_temperature_low, cloud_cover,
+    dew_point, humidity, ozone, precip_accumulation, precip_intensity, precip_intensity_max,
+    precip_probability, pressure, temperature, temperature_high, temperature_low, uv_index,
+    visibility, wind_gust, wind_speed
+);
+
+/// Strategy used to combine the values reported by each member of an
+/// `Ensemble`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CombineStrategy {
+    /// The arithmetic mean of every reported value.
+    Mean,
+
+    /// The median of every reported value.
+    Median,
+
+    /// The weighted mean of every reported value, with one weight per
+    /// member in the order the members are passed to
+    /// `Ensemble::combine`. Weights of members which don't report a
+    /// value are excluded from the normalization.
+    Weighted(Vec<f64>)
+}
+
+/// Merges responses from several sources into a single consensus
+/// `ApiResponse`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ensemble {
+    strategy: CombineStrategy
+}
+
+impl Ensemble {
+    /// Construct a new Ensemble which combines values using `strategy`.
+    pub fn new(strategy: CombineStrategy) -> Ensemble {
+        Ensemble { strategy }
+    }
+
+    /// Combine `members` into a single response. Returns `None` if
+    /// `members` is empty.
+    pub fn combine(&self, members: &[&ApiResponse]) -> Option<ApiResponse> {
+        let mut combined = (*members.first()?).clone();
+
+        if let Some(currently) = combined.currently.as_mut() {
+            let points: Vec<Option<&DataPoint>> = members.iter()
+                .map(|m| m.currently.as_ref())
+                .collect();
+
+            self.combine_point(currently, &points);
+        }
+
+        if let Some(hourly) = combined.hourly.as_mut() {
+            self.combine_block(hourly, members, |m| m.hourly.as_ref());
+        }
+
+        if let Some(daily) = combined.daily.as_mut() {
+            self.combine_block(daily, members, |m| m.daily.as_ref());
+        }
+
+        Some(combined)
+    }
+
+    fn combine_block<F>(&self, block: &mut DataBlock, members: &[&ApiResponse], member_block: F)
+        where F: Fn(&ApiResponse) -> Option<&DataBlock> {
+        for point in block.data.iter_mut() {
+            let time = point.time;
+
+            let points: Vec<Option<&DataPoint>> = members.iter()
+                .map(|m| member_block(m).and_then(|b| b.data.iter().find(|p| p.time == time)))
+                .collect();
+
+            self.combine_point(point, &points);
+        }
+    }
+
+    fn combine_point(&self, point: &mut DataPoint, members: &[Option<&DataPoint>]) {
+        for (get, get_mut) in COMBINED_FIELDS {
+            let values: Vec<(usize, f64)> = members.iter()
+                .enumerate()
+                .filter_map(|(i, p)| p.and_then(get).map(|v| (i, v)))
+                .collect();
+
+            *get_mut(point) = self.combine_values(&values);
+        }
+    }
+
+    fn combine_values(&self, values: &[(usize, f64)]) -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+
+        match &self.strategy {
+            CombineStrategy::Mean => {
+                Some(values.iter().map(|(_, v)| v).sum::<f64>() / values.len() as f64)
+            },
+            CombineStrategy::Median => {
+                let mut sorted: Vec<f64> = values.iter().map(|(_, v)| *v).collect();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+
+                let mid = sorted.len() / 2;
+
+                if sorted.len().is_multiple_of(2) {
+                    Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+                } else {
+                    Some(sorted[mid])
+                }
+            },
+            CombineStrategy::Weighted(weights) => {
+                let weight = |i: usize| weights.get(i).cloned().unwrap_or(0.0);
+                let total: f64 = values.iter().map(|(i, _)| weight(*i)).sum();
+
+                if total <= 0.0 {
+                    return None;
+                }
+
+                Some(values.iter().map(|(i, v)| weight(*i) * v).sum::<f64>() / total)
+            }
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{CombineStrategy, Ensemble};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    fn response(temperature: f64, summary: &str) -> ApiResponse {
+        testing::response(json!({
+            "currently": {"time": 10, "temperature": temperature, "summary": summary},
+            "hourly": {"data": [
+                {"time": 10, "temperature": temperature},
+                {"time": 20, "temperature": temperature}
+            ]}
+        }))
+    }
+
+    #[test]
+    fn test_combine_mean() {
+        let a = response(10.0, "Clear");
+        let b = response(14.0, "Rain");
+
+        let combined = Ensemble::new(CombineStrategy::Mean).combine(&[&a, &b]).unwrap();
+        let currently = combined.currently.unwrap();
+
+        assert_eq!(currently.temperature, Some(12.0));
+        assert_eq!(currently.summary, Some("Clear".to_string()));
+        assert_eq!(currently.humidity, None);
+        assert_eq!(combined.hourly.unwrap().data[1].temperature, Some(12.0));
+    }
+
+    #[test]
+    fn test_combine_median() {
+        let a = response(10.0, "Clear");
+        let b = response(30.0, "Clear");
+        let c = response(11.0, "Clear");
+
+        let combined = Ensemble::new(CombineStrategy::Median).combine(&[&a, &b, &c]).unwrap();
+
+        assert_eq!(combined.currently.unwrap().temperature, Some(11.0));
+    }
+
+    #[test]
+    fn test_combine_weighted() {
+        let a = response(10.0, "Clear");
+        let b = response(20.0, "Clear");
+
+        let combined = Ensemble::new(CombineStrategy::Weighted(vec![3.0, 1.0]))
+            .combine(&[&a, &b])
+            .unwrap();
+
+        assert_eq!(combined.currently.unwrap().temperature, Some(12.5));
+    }
+
+    #[test]
+    fn test_combine_empty() {
+        assert_eq!(Ensemble::new(CombineStrategy::Mean).combine(&[]), None);
+    }
+}
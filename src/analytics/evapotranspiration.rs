@@ -0,0 +1,219 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Reference evapotranspiration (ET₀) estimates from the daily
+//! forecast, for irrigation scheduling.
+//!
+//! ET₀ is the water, in millimeters per day, evaporated and transpired
+//! by a reference crop of well-watered grass. It's computed with the
+//! FAO-56 Penman–Monteith equation (Allen et al., *Crop
+//! Evapotranspiration*, FAO Irrigation and Drainage Paper 56, 1998),
+//! with two simplifications since the forecast doesn't include them:
+//!
+//! * Solar radiation is estimated from cloud cover with the Ångström
+//!   formula, taking the fraction of possible sunshine hours to be the
+//!   clear fraction of the sky.
+//! * The site is taken to be near sea level when computing clear-sky
+//!   radiation, and the atmospheric pressure defaults to sea level if
+//!   the forecast omits it.
+//!
+//! Multiply ET₀ by a crop coefficient to get a particular crop's water
+//! use.
+
+use std::f64::consts::PI;
+
+use crate::convert::{celsius, meters_per_second, response_units};
+use crate::datetime::{CivilDateTime, days_from_civil};
+use crate::{ApiResponse, DataPoint, Units};
+
+// constants
+
+const STEFAN_BOLTZMANN: f64 = 4.903e-9; // MJ K⁻⁴ m⁻² day⁻¹
+const SOLAR_CONSTANT: f64 = 0.0820; // MJ m⁻² min⁻¹
+const SEA_LEVEL_PRESSURE: f64 = 1013.25; // hPa
+const ALBEDO: f64 = 0.23;
+
+// Ångström coefficients
+const ANGSTROM_A: f64 = 0.25;
+const ANGSTROM_B: f64 = 0.50;
+
+// converts wind speed measured at 10 m, as forecast, to 2 m
+const WIND_10M_TO_2M: f64 = 0.748;
+
+/// A day's weather, in SI units, as input to `reference_evapotranspiration`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyWeather {
+    /// The day of the year, from 1 to 366.
+    pub day_of_year: u32,
+
+    /// Maximum temperature in °C.
+    pub temperature_max: f64,
+
+    /// Minimum temperature in °C.
+    pub temperature_min: f64,
+
+    /// Dew point in °C.
+    pub dew_point: f64,
+
+    /// Mean wind speed at 10 m in m/s.
+    pub wind_speed: f64,
+
+    /// The fraction of the sky covered by cloud, from 0 to 1.
+    pub cloud_cover: f64,
+
+    /// Sea-level air pressure in hPa.
+    pub pressure: f64
+}
+
+/// The reference evapotranspiration, in mm/day, at `latitude` on a day
+/// with `weather`.
+pub fn reference_evapotranspiration(latitude: f64, weather: &DailyWeather) -> f64 {
+    let t_max = weather.temperature_max;
+    let t_min = weather.temperature_min;
+    let t_mean = (t_max + t_min) / 2.0;
+
+    let slope = 4098.0 * saturation_vapor_pressure(t_mean) / (t_mean + 237.3).powi(2);
+    let psychrometric = 0.000_665 * weather.pressure / 10.0;
+
+    let es = (saturation_vapor_pressure(t_max) + saturation_vapor_pressure(t_min)) / 2.0;
+    let ea = saturation_vapor_pressure(weather.dew_point);
+
+    let ra = extraterrestrial_radiation(latitude, weather.day_of_year);
+    let rs = (ANGSTROM_A + ANGSTROM_B * (1.0 - weather.cloud_cover)) * ra;
+    let rso = (ANGSTROM_A + ANGSTROM_B) * ra;
+    let rns = (1.0 - ALBEDO) * rs;
+    let relative_shortwave = if rso > 0.0 { (rs / rso).min(1.0) } else { 0.0 };
+    let rnl = STEFAN_BOLTZMANN * ((t_max + 273.16).powi(4) + (t_min + 273.16).powi(4)) / 2.0
+        * (0.34 - 0.14 * ea.sqrt())
+        * (1.35 * relative_shortwave - 0.35);
+    let rn = rns - rnl;
+
+    let u2 = weather.wind_speed * WIND_10M_TO_2M;
+
+    let et0 = (0.408 * slope * rn + psychrometric * 900.0 / (t_mean + 273.0) * u2 * (es - ea))
+        / (slope + psychrometric * (1.0 + 0.34 * u2));
+
+    et0.max(0.0)
+}
+
+impl ApiResponse {
+    /// The reference evapotranspiration, in mm/day, for each point of
+    /// the daily block, as `(time, et0)`. Days missing a high or low
+    /// temperature, dew point, wind speed or cloud cover are skipped.
+    #[allow(deprecated)]
+    pub fn reference_evapotranspiration(&self) -> Vec<(i64, f64)> {
+        let units = response_units(self);
+        let offset = self.offset * 3600;
+
+        self.daily.iter()
+            .flatten()
+            .filter_map(|point| {
+                let weather = daily_weather(point, units, offset)?;
+
+                Some((point.time, reference_evapotranspiration(self.latitude, &weather)))
+            })
+            .collect()
+    }
+}
+
+fn daily_weather(point: &DataPoint, units: &Units, offset: i64) -> Option<DailyWeather> {
+    let date = CivilDateTime::from_unix(point.time, offset);
+    let day_of_year = days_from_civil(date.year, date.month, date.day)
+        - days_from_civil(date.year, 1, 1)
+        + 1;
+
+    Some(DailyWeather {
+        day_of_year: day_of_year as u32,
+        temperature_max: celsius(point.temperature_high?, units),
+        temperature_min: celsius(point.temperature_low?, units),
+        dew_point: celsius(point.dew_point?, units),
+        wind_speed: meters_per_second(point.wind_speed?, units),
+        cloud_cover: point.cloud_cover?,
+        pressure: point.pressure.unwrap_or(SEA_LEVEL_PRESSURE)
+    })
+}
+
+// in kPa, at `temperature` °C
+fn saturation_vapor_pressure(temperature: f64) -> f64 {
+    0.6108 * (17.27 * temperature / (temperature + 237.3)).exp()
+}
+
+// in MJ m⁻² day⁻¹
+fn extraterrestrial_radiation(latitude: f64, day_of_year: u32) -> f64 {
+    let latitude = latitude.to_radians();
+    let day_angle = 2.0 * PI * f64::from(day_of_year) / 365.0;
+    let inverse_distance = 1.0 + 0.033 * day_angle.cos();
+    let declination = 0.409 * (day_angle - 1.39).sin();
+    let sunset_hour_angle = (-latitude.tan() * declination.tan()).clamp(-1.0, 1.0).acos();
+
+    24.0 * 60.0 / PI * SOLAR_CONSTANT * inverse_distance
+        * (sunset_hour_angle * latitude.sin() * declination.sin()
+            + latitude.cos() * declination.cos() * sunset_hour_angle.sin())
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{DailyWeather, extraterrestrial_radiation, reference_evapotranspiration};
+
+    use crate::testing;
+
+    use serde_json::json;
+
+    // FAO-56 example 18: Brussels on 6 July
+    fn brussels() -> DailyWeather {
+        DailyWeather {
+            day_of_year: 187,
+            temperature_max: 21.5,
+            temperature_min: 12.3,
+            dew_point: 12.07,
+            wind_speed: 10.0 / 3.6,
+            cloud_cover: 1.0 - 9.25 / 16.1,
+            pressure: 1001.0
+        }
+    }
+
+    #[test]
+    fn test_extraterrestrial_radiation() {
+        // FAO-56 example 8: 20°S on 3 September
+        assert!((extraterrestrial_radiation(-20.0, 246) - 32.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_reference_evapotranspiration() {
+        let et0 = reference_evapotranspiration(50.8, &brussels());
+
+        assert!((et0 - 3.9).abs() < 0.1, "{}", et0);
+    }
+
+    #[test]
+    fn test_api_response_reference_evapotranspiration() {
+        let response = testing::response(json!({
+            "latitude": 50.8, "longitude": 4.35, "timezone": "Europe/Brussels", "offset": 2,
+            "daily": {"data": [
+                {"time": 1_530_828_000, "temperatureHigh": 21.5, "temperatureLow": 12.3,
+                 "dewPoint": 12.07, "windSpeed": 2.78, "cloudCover": 0.43, "pressure": 1001.0},
+                {"time": 1_530_914_400, "temperatureHigh": 22.0}
+            ]},
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        let et0 = response.reference_evapotranspiration();
+
+        assert_eq!(et0.len(), 1);
+        assert_eq!(et0[0].0, 1_530_828_000);
+        assert!((et0[0].1 - 3.9).abs() < 0.1, "{}", et0[0].1);
+    }
+}
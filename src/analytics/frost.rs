@@ -0,0 +1,274 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! An hourly risk index of frost and ice forming on roads and other
+//! exposed surfaces, for fleet and facility operators.
+//!
+//! # Methodology
+//!
+//! Ice forms when a surface below freezing is wetted, either by
+//! precipitation or by moisture condensing out of near-saturated air.
+//! The forecast gives the air temperature, not the surface temperature,
+//! so the index estimates the latter: at night under a clear sky and a
+//! light wind, surfaces radiate heat away and run several degrees colder
+//! than the air above them. For each hour:
+//!
+//! 1. The surface temperature is estimated as the air temperature less
+//!    `max_radiative_cooling`, scaled by the clear fraction of the sky
+//!    and by how calm the wind is relative to `calm_wind_speed`, at
+//!    night only (with the sun below the horizon).
+//! 2. The *cold* factor is 1 at or below 0 °C surface temperature,
+//!    falling linearly to 0 at `freezing_margin` above it.
+//! 3. The *wet* factor is 1 if precipitation is forecast; otherwise it
+//!    is 1 when the dew point equals the air temperature, falling
+//!    linearly to 0 at a spread of `saturation_spread`.
+//! 4. The index is the product of the two, from 0 to 1.
+//!
+//! Every parameter is in SI units, whatever units the response is in.
+
+use crate::convert::{celsius, meters_per_second, millimeters, response_units};
+use crate::solar::sun_position;
+use crate::{ApiResponse, DataPoint, Units};
+
+/// How likely icing is in an hour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    /// An index below 0.25.
+    None,
+
+    /// An index from 0.25.
+    Low,
+
+    /// An index from 0.5.
+    Moderate,
+
+    /// An index from 0.75.
+    High
+}
+
+impl RiskLevel {
+    /// The level of a risk index from 0 to 1.
+    pub fn from_index(index: f64) -> RiskLevel {
+        if index >= 0.75 {
+            RiskLevel::High
+        } else if index >= 0.5 {
+            RiskLevel::Moderate
+        } else if index >= 0.25 {
+            RiskLevel::Low
+        } else {
+            RiskLevel::None
+        }
+    }
+}
+
+/// The icing risk in one hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyRisk {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// The estimated surface temperature in °C.
+    pub surface_temperature: f64,
+
+    /// The risk index, from 0 to 1.
+    pub index: f64,
+
+    pub level: RiskLevel
+}
+
+/// The parameters of the frost risk index. See the module
+/// documentation for how each is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrostRisk {
+    max_radiative_cooling: f64,
+    calm_wind_speed: f64,
+    freezing_margin: f64,
+    saturation_spread: f64,
+    wet_precip_intensity: f64
+}
+
+impl Default for FrostRisk {
+    fn default() -> FrostRisk {
+        FrostRisk {
+            max_radiative_cooling: 3.0,
+            calm_wind_speed: 5.0,
+            freezing_margin: 1.0,
+            saturation_spread: 4.0,
+            wet_precip_intensity: 0.1
+        }
+    }
+}
+
+impl FrostRisk {
+    /// Construct a FrostRisk with the default parameters.
+    pub fn new() -> FrostRisk {
+        FrostRisk::default()
+    }
+
+    /// How much colder than the air, in °C, a surface gets on a clear,
+    /// still night. Defaults to 3.
+    pub fn max_radiative_cooling(mut self, degrees: f64) -> FrostRisk {
+        self.max_radiative_cooling = degrees;
+        self
+    }
+
+    /// The wind speed, in m/s, at and above which the wind mixes the air
+    /// enough to stop surfaces cooling below it. Defaults to 5.
+    pub fn calm_wind_speed(mut self, speed: f64) -> FrostRisk {
+        self.calm_wind_speed = speed;
+        self
+    }
+
+    /// How far above freezing, in °C, an estimated surface temperature
+    /// still carries some risk, allowing for error in the estimate.
+    /// Defaults to 1.
+    pub fn freezing_margin(mut self, degrees: f64) -> FrostRisk {
+        self.freezing_margin = degrees;
+        self
+    }
+
+    /// The dew point spread, in °C, at and above which the air is too
+    /// dry to deposit frost. Defaults to 4.
+    pub fn saturation_spread(mut self, degrees: f64) -> FrostRisk {
+        self.saturation_spread = degrees;
+        self
+    }
+
+    /// The precipitation intensity, in mm/h, at and above which
+    /// surfaces count as wet. Defaults to 0.1.
+    pub fn wet_precip_intensity(mut self, intensity: f64) -> FrostRisk {
+        self.wet_precip_intensity = intensity;
+        self
+    }
+
+    /// The risk for each point of `response`'s hourly block which has a
+    /// temperature.
+    pub fn hourly(&self, response: &ApiResponse) -> Vec<HourlyRisk> {
+        let units = response_units(response);
+
+        response.hourly.iter()
+            .flatten()
+            .filter_map(|point| self.score(response, point, units))
+            .collect()
+    }
+
+    fn score(&self, response: &ApiResponse, point: &DataPoint, units: &Units)
+        -> Option<HourlyRisk> {
+        let temperature = celsius(point.temperature?, units);
+
+        let night = sun_position(response.latitude, response.longitude, point.time).elevation < 0.0;
+        let clear = 1.0 - point.cloud_cover.unwrap_or(1.0);
+        let wind_speed = point.wind_speed
+            .map_or(self.calm_wind_speed, |speed| meters_per_second(speed, units));
+        let calm = 1.0 - (wind_speed / self.calm_wind_speed).min(1.0);
+        let cooling = if night { self.max_radiative_cooling * clear * calm } else { 0.0 };
+        let surface_temperature = temperature - cooling;
+
+        let cold = ramp(surface_temperature, 0.0, self.freezing_margin);
+        let precipitating = point.precip_intensity
+            .is_some_and(|i| millimeters(i, units) >= self.wet_precip_intensity);
+        let wet = if precipitating {
+            1.0
+        } else {
+            point.dew_point
+                .map_or(0.0, |d| ramp(temperature - celsius(d, units), 0.0, self.saturation_spread))
+        };
+
+        let index = cold * wet;
+
+        Some(HourlyRisk {
+            time: point.time,
+            surface_temperature,
+            index,
+            level: RiskLevel::from_index(index)
+        })
+    }
+}
+
+// 1 at or below `low`, 0 at or above `high`, linear in between
+fn ramp(value: f64, low: f64, high: f64) -> f64 {
+    if high <= low {
+        return if value <= low { 1.0 } else { 0.0 };
+    }
+
+    ((high - value) / (high - low)).clamp(0.0, 1.0)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{FrostRisk, RiskLevel};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    // 2018-01-22T05:00:00Z, midnight in New York
+    const MIDNIGHT: i64 = 1_516_597_200;
+
+    // 2018-01-22T17:00:00Z, noon in New York
+    const NOON: i64 = 1_516_640_400;
+
+    fn response(hourly: Vec<Value>) -> ApiResponse {
+        testing::response(json!({
+            "latitude": 40.7, "longitude": -74.0, "timezone": "America/New_York", "offset": -5,
+            "hourly": {"data": hourly},
+            "flags": {"sources": [], "units": "si"}
+        }))
+    }
+
+    #[test]
+    fn test_frost_risk() {
+        let response = response(vec![
+            // a clear, still night just above freezing, near saturation
+            json!({"time": MIDNIGHT, "temperature": 2.0, "dewPoint": 1.5, "cloudCover": 0.0,
+                   "windSpeed": 0.0}),
+            // the same conditions at noon get no radiative cooling
+            json!({"time": NOON, "temperature": 2.0, "dewPoint": 1.5, "cloudCover": 0.0,
+                   "windSpeed": 0.0}),
+            // freezing rain
+            json!({"time": NOON, "temperature": -1.0, "precipIntensity": 0.5}),
+            // cold and dry
+            json!({"time": NOON, "temperature": -10.0, "dewPoint": -20.0}),
+            // no temperature
+            json!({"time": NOON})
+        ]);
+
+        let risks = FrostRisk::new().hourly(&response);
+
+        assert_eq!(risks.len(), 4);
+
+        assert_eq!(risks[0].surface_temperature, -1.0);
+        assert_eq!(risks[0].index, 0.875);
+        assert_eq!(risks[0].level, RiskLevel::High);
+
+        assert_eq!(risks[1].surface_temperature, 2.0);
+        assert_eq!(risks[1].level, RiskLevel::None);
+
+        assert_eq!(risks[2].index, 1.0);
+        assert_eq!(risks[3].index, 0.0);
+    }
+
+    #[test]
+    fn test_frost_risk_parameters() {
+        let response = response(vec![
+            json!({"time": NOON, "temperature": 2.0, "dewPoint": 2.0})
+        ]);
+
+        assert_eq!(FrostRisk::new().hourly(&response)[0].index, 0.0);
+        assert_eq!(FrostRisk::new().freezing_margin(4.0).hourly(&response)[0].index, 0.5);
+    }
+}
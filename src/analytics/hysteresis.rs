@@ -0,0 +1,184 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Threshold alerts with hysteresis, e.g. for wind gusts, which don't
+//! flap when a forecast hovers around the threshold.
+//!
+//! An alert triggers when a field rises above the trigger threshold and
+//! clears only once it falls below the lower clear threshold; values in
+//! between, or missing, leave the alert as it was. Alerts lasting less
+//! than the minimum duration are dropped as noise:
+//!
+//! ```
+//! use forecast::analytics::hysteresis::HysteresisDetector;
+//! # use forecast::DataBlock;
+//! # let hourly: DataBlock = serde_json::from_str(
+//! #     "{\"data\":[{\"time\":0,\"windGust\":30},{\"time\":3600,\"windGust\":22},
+//! #                 {\"time\":7200,\"windGust\":10}]}").unwrap();
+//!
+//! let alerts = HysteresisDetector::new(25.0, 15.0)
+//!     .min_duration(2 * 3600)
+//!     .detect(&hourly, |p| p.wind_gust);
+//! # assert_eq!(alerts.len(), 1);
+//! ```
+//!
+//! Thresholds are in the units the response was requested in.
+
+use crate::{DataBlock, DataPoint};
+
+/// A period during which a field exceeded its trigger threshold.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThresholdAlert {
+    /// The time, in seconds since the UNIX epoch, of the point which
+    /// triggered the alert.
+    pub start: i64,
+
+    /// The time of the point which cleared the alert, or the end of the
+    /// block if it never cleared.
+    pub end: i64,
+
+    /// Whether the alert was still active at the end of the block.
+    pub ongoing: bool,
+
+    /// The highest value during the alert.
+    pub peak: f64,
+
+    /// The time of the point with the highest value.
+    pub peak_time: i64
+}
+
+/// Detects threshold alerts with hysteresis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HysteresisDetector {
+    trigger: f64,
+    clear: f64,
+    min_duration: i64
+}
+
+impl HysteresisDetector {
+    /// A detector which triggers above `trigger` and clears below
+    /// `clear`, which should be lower.
+    pub fn new(trigger: f64, clear: f64) -> HysteresisDetector {
+        HysteresisDetector { trigger, clear, min_duration: 0 }
+    }
+
+    /// Drop alerts lasting less than `seconds`. Defaults to 0, keeping
+    /// every alert.
+    pub fn min_duration(mut self, seconds: i64) -> HysteresisDetector {
+        self.min_duration = seconds;
+        self
+    }
+
+    /// The alerts on the field selected by `field` over `block`, which
+    /// must be sorted by time.
+    pub fn detect<F>(&self, block: &DataBlock, field: F) -> Vec<ThresholdAlert>
+        where F : Fn(&DataPoint) -> Option<f64> {
+        let mut alerts = Vec::new();
+        let mut active: Option<ThresholdAlert> = None;
+
+        for point in &block.data {
+            let value = match field(point) {
+                Some(value) => value,
+                None => continue
+            };
+
+            active = match active.take() {
+                None if value > self.trigger => Some(ThresholdAlert {
+                    start: point.time,
+                    end: point.time,
+                    ongoing: true,
+                    peak: value,
+                    peak_time: point.time
+                }),
+                Some(mut alert) if value < self.clear => {
+                    alert.end = point.time;
+                    alert.ongoing = false;
+                    alerts.push(alert);
+                    None
+                },
+                Some(mut alert) => {
+                    if value > alert.peak {
+                        alert.peak = value;
+                        alert.peak_time = point.time;
+                    }
+                    Some(alert)
+                },
+                None => None
+            };
+        }
+
+        if let Some(mut alert) = active {
+            alert.end = block.span().map_or(alert.start, |span| span.end);
+            alerts.push(alert);
+        }
+
+        alerts.retain(|alert| alert.end - alert.start >= self.min_duration);
+        alerts
+    }
+
+    /// The alerts on wind gusts over `block`.
+    pub fn gusts(&self, block: &DataBlock) -> Vec<ThresholdAlert> {
+        self.detect(block, |point| point.wind_gust)
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{HysteresisDetector, ThresholdAlert};
+
+    use crate::DataBlock;
+
+    use serde_json::json;
+
+    fn block(gusts: &[Option<f64>]) -> DataBlock {
+        let data: Vec<_> = gusts.iter()
+            .enumerate()
+            .map(|(hour, gust)| json!({"time": hour as i64 * 3600, "windGust": gust}))
+            .collect();
+
+        serde_json::from_value(json!({"data": data})).unwrap()
+    }
+
+    #[test]
+    fn test_gusts() {
+        let block = block(&[
+            Some(10.0), Some(26.0), Some(20.0), Some(24.0), Some(30.0), None, Some(14.0),
+            Some(26.0), Some(24.0)
+        ]);
+
+        let alerts = HysteresisDetector::new(25.0, 15.0).gusts(&block);
+
+        assert_eq!(alerts, vec![
+            ThresholdAlert {
+                start: 3600, end: 6 * 3600, ongoing: false, peak: 30.0, peak_time: 4 * 3600
+            },
+            ThresholdAlert {
+                start: 7 * 3600, end: 9 * 3600, ongoing: true, peak: 26.0, peak_time: 7 * 3600
+            }
+        ]);
+    }
+
+    #[test]
+    fn test_min_duration() {
+        let gusts = block(&[Some(30.0), Some(10.0), Some(30.0), Some(30.0), Some(30.0)]);
+
+        let alerts = HysteresisDetector::new(25.0, 15.0).min_duration(3 * 3600).gusts(&gusts);
+
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].start, 2 * 3600);
+        assert!(HysteresisDetector::new(25.0, 15.0).gusts(&block(&[])).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Hazards to small boats from wind and waves, for coastal users.
+//!
+//! Wave and swell fields aren't part of the Dark Sky API, but some
+//! compatible providers report them (see `ApiClient::with_base_url`).
+//! The hazard levels follow the thresholds of the US National Weather
+//! Service's marine warnings: a small craft advisory for sustained
+//! winds or frequent gusts of 22 to 33 knots or waves of 7 feet (2.1 m)
+//! or more, then gale, storm and hurricane force winds from 34, 48 and
+//! 64 knots. Local offices adjust the advisory thresholds, so they can
+//! be overridden.
+
+use crate::convert::{meters_per_second, response_units};
+use crate::{ApiResponse, DataPoint, Units};
+
+// constants
+
+const KNOTS_PER_MPS: f64 = 1.943_844;
+const METERS_PER_FOOT: f64 = 0.3048;
+
+const GALE_KNOTS: f64 = 34.0;
+const STORM_KNOTS: f64 = 48.0;
+const HURRICANE_FORCE_KNOTS: f64 = 64.0;
+
+/// A marine hazard, in increasing order of severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MarineHazard {
+    SmallCraftAdvisory,
+    Gale,
+    Storm,
+    HurricaneForce
+}
+
+/// The thresholds for a small craft advisory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallCraftThresholds {
+    wind_knots: f64,
+    wave_height: f64
+}
+
+impl Default for SmallCraftThresholds {
+    fn default() -> SmallCraftThresholds {
+        SmallCraftThresholds { wind_knots: 22.0, wave_height: 7.0 * METERS_PER_FOOT }
+    }
+}
+
+impl SmallCraftThresholds {
+    /// The default thresholds: 22 knots of wind or gusts, or 7 feet of
+    /// waves.
+    pub fn new() -> SmallCraftThresholds {
+        SmallCraftThresholds::default()
+    }
+
+    /// The wind or gust speed, in knots, from which an advisory applies.
+    pub fn wind_knots(mut self, knots: f64) -> SmallCraftThresholds {
+        self.wind_knots = knots;
+        self
+    }
+
+    /// The wave height, in meters, from which an advisory applies.
+    pub fn wave_height(mut self, meters: f64) -> SmallCraftThresholds {
+        self.wave_height = meters;
+        self
+    }
+
+    /// The most severe hazard at `point`, whose values are in `units`,
+    /// if any. Gusts count toward an advisory but not toward gale or
+    /// stronger warnings, which are for sustained winds.
+    pub fn hazard(&self, point: &DataPoint, units: &Units) -> Option<MarineHazard> {
+        let knots = |speed: f64| meters_per_second(speed, units) * KNOTS_PER_MPS;
+        let wind = point.wind_speed.map_or(0.0, knots);
+        let gust = point.wind_gust.map_or(0.0, knots);
+        let wave_height = point.wave_height.map_or(0.0, |height| match units {
+            Units::Imperial => height * METERS_PER_FOOT,
+            _ => height
+        });
+
+        if wind >= HURRICANE_FORCE_KNOTS {
+            Some(MarineHazard::HurricaneForce)
+        } else if wind >= STORM_KNOTS {
+            Some(MarineHazard::Storm)
+        } else if wind >= GALE_KNOTS {
+            Some(MarineHazard::Gale)
+        } else if wind.max(gust) >= self.wind_knots || wave_height >= self.wave_height {
+            Some(MarineHazard::SmallCraftAdvisory)
+        } else {
+            None
+        }
+    }
+}
+
+impl ApiResponse {
+    /// The most severe marine hazard in each hour of the hourly block
+    /// with one, as `(time, hazard)`, using the default small craft
+    /// advisory thresholds.
+    pub fn marine_hazards(&self) -> Vec<(i64, MarineHazard)> {
+        let thresholds = SmallCraftThresholds::default();
+        let units = response_units(self);
+
+        self.hourly.iter()
+            .flatten()
+            .filter_map(|point| Some((point.time, thresholds.hazard(point, units)?)))
+            .collect()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{MarineHazard, SmallCraftThresholds};
+
+    use crate::testing;
+    use crate::{DataPoint, Units};
+
+    use serde_json::json;
+
+    fn point(value: serde_json::Value) -> DataPoint {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_marine_fields() {
+        let point = point(json!({
+            "time": 0, "waveHeight": 1.5, "wavePeriod": 8.0, "swellHeight": 1.0,
+            "swellPeriod": 12.0, "swellDirection": 270.0
+        }));
+
+        assert_eq!(point.wave_height, Some(1.5));
+        assert_eq!(point.swell_period, Some(12.0));
+        assert_eq!(serde_json::to_value(&point).unwrap()["swellDirection"], 270.0);
+    }
+
+    #[test]
+    fn test_hazard() {
+        let thresholds = SmallCraftThresholds::new();
+        let hazard = |value| thresholds.hazard(&point(value), &Units::Imperial);
+
+        assert_eq!(hazard(json!({"time": 0, "windSpeed": 15.0})), None);
+        assert_eq!(
+            hazard(json!({"time": 0, "windSpeed": 15.0, "windGust": 30.0})),
+            Some(MarineHazard::SmallCraftAdvisory)
+        );
+        assert_eq!(
+            hazard(json!({"time": 0, "windSpeed": 5.0, "waveHeight": 8.0})),
+            Some(MarineHazard::SmallCraftAdvisory)
+        );
+        assert_eq!(hazard(json!({"time": 0, "windSpeed": 45.0})), Some(MarineHazard::Gale));
+        assert_eq!(hazard(json!({"time": 0, "windSpeed": 60.0})), Some(MarineHazard::Storm));
+        assert_eq!(
+            hazard(json!({"time": 0, "windSpeed": 80.0})),
+            Some(MarineHazard::HurricaneForce)
+        );
+
+        let strict = SmallCraftThresholds::new().wave_height(1.0);
+
+        assert_eq!(
+            strict.hazard(&point(json!({"time": 0, "waveHeight": 1.2})), &Units::SI),
+            Some(MarineHazard::SmallCraftAdvisory)
+        );
+    }
+
+    #[test]
+    fn test_marine_hazards() {
+        let response = testing::response(json!({
+            "hourly": {"data": [
+                {"time": 0, "windSpeed": 5.0, "waveHeight": 1.0},
+                {"time": 3600, "windSpeed": 12.0, "waveHeight": 2.5}
+            ]},
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        assert_eq!(response.marine_hazards(), vec![(3600, MarineHazard::SmallCraftAdvisory)]);
+    }
+}
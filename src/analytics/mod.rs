@@ -0,0 +1,37 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Derived forecasts and statistics computed locally from API
+//! responses, such as rolling statistics, threshold rules and
+//! domain-specific indices. Each module is also re-exported at the
+//! crate root under its previous path, e.g. `forecast::frost`.
+
+pub mod activity;
+pub mod changeover;
+pub mod compare;
+pub mod confidence;
+pub mod energy;
+pub mod ensemble;
+pub mod evapotranspiration;
+pub mod frost;
+pub mod hysteresis;
+pub mod marine;
+pub mod nowcast;
+pub mod rolling;
+pub mod rules;
+pub mod snowpack;
+pub mod solar;
+pub mod solar_pv;
+pub mod storm;
+pub mod wind_power;
@@ -0,0 +1,185 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Precipitation start and stop times from the `minutely` block, as
+//! in "rain starting in 12 minutes".
+//!
+//! A minute counts as precipitating when the API forecasts a non-zero
+//! intensity with at least a 50% chance. Each change between dry and
+//! precipitating minutes becomes a `PrecipEvent`, timed relative to the
+//! first point of the block, i.e. the time of the request.
+
+use std::fmt;
+
+use crate::{ApiResponse, DataPoint, PrecipType};
+
+// constants
+
+const WET_PRECIP_PROBABILITY: f64 = 0.5;
+const SECONDS_PER_MINUTE: i64 = 60;
+
+/// Whether precipitation starts or stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecipEventKind {
+    Starting,
+    Stopping
+}
+
+/// A change in precipitation forecast by the `minutely` block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecipEvent {
+    pub kind: PrecipEventKind,
+
+    /// The first minute, in seconds since the UNIX epoch, of the new
+    /// state.
+    pub time: i64,
+
+    /// Minutes from the start of the block until `time`.
+    pub minutes_from_now: i64,
+
+    /// The kind of precipitation starting or stopping, if known.
+    pub precip_type: Option<PrecipType>,
+
+    /// How likely the change is, from 0 to 1: the chance of
+    /// precipitation in the first minute of a `Starting` event, or the
+    /// chance of none in the first minute of a `Stopping` event. `None`
+    /// if the API omitted the probability.
+    pub confidence: Option<f64>
+}
+
+impl fmt::Display for PrecipEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precip = match self.precip_type {
+            Some(PrecipType::Rain) => "rain",
+            Some(PrecipType::Snow) => "snow",
+            Some(PrecipType::Sleet) => "sleet",
+            None => "precipitation"
+        };
+
+        let kind = match self.kind {
+            PrecipEventKind::Starting => "starting",
+            PrecipEventKind::Stopping => "stopping"
+        };
+
+        match self.minutes_from_now {
+            0 => write!(f, "{} {} now", precip, kind),
+            1 => write!(f, "{} {} in 1 minute", precip, kind),
+            minutes => write!(f, "{} {} in {} minutes", precip, kind, minutes)
+        }
+    }
+}
+
+impl ApiResponse {
+    /// The times at which precipitation starts and stops according to
+    /// the `minutely` block, in chronological order. Empty if the
+    /// response has no minutely data or the weather doesn't change.
+    pub fn minutely_precip_events(&self) -> Vec<PrecipEvent> {
+        let points: Vec<&DataPoint> = self.minutely.iter().flatten().collect();
+        let start = match points.first() {
+            Some(first) => first.time,
+            None => return Vec::new()
+        };
+
+        points.windows(2)
+            .filter_map(|pair| {
+                let (previous, point) = (pair[0], pair[1]);
+
+                let (kind, precip_type, confidence) = match (is_wet(previous), is_wet(point)) {
+                    (false, true) => (
+                        PrecipEventKind::Starting,
+                        point.precip_type,
+                        point.precip_probability
+                    ),
+                    (true, false) => (
+                        PrecipEventKind::Stopping,
+                        previous.precip_type,
+                        point.precip_probability.map(|p| 1.0 - p)
+                    ),
+                    _ => return None
+                };
+
+                Some(PrecipEvent {
+                    kind,
+                    time: point.time,
+                    minutes_from_now: (point.time - start) / SECONDS_PER_MINUTE,
+                    precip_type,
+                    confidence
+                })
+            })
+            .collect()
+    }
+}
+
+fn is_wet(point: &DataPoint) -> bool {
+    point.precip_intensity.is_some_and(|i| i > 0.0)
+        && point.precip_probability.is_none_or(|p| p >= WET_PRECIP_PROBABILITY)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::PrecipEventKind;
+
+    use crate::testing;
+    use crate::{ApiResponse, PrecipType};
+
+    use serde_json::{json, Value};
+
+    fn response(minutely: Vec<Value>) -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.4, "longitude": -71.1, "timezone": "America/New_York", "offset": -5,
+            "minutely": {"data": minutely}
+        }))
+    }
+
+    fn minute(i: i64, intensity: f64, probability: f64) -> Value {
+        json!({
+            "time": 1000 + i * 60,
+            "precipIntensity": intensity,
+            "precipProbability": probability,
+            "precipType": "rain"
+        })
+    }
+
+    #[test]
+    fn test_starting_and_stopping() {
+        let mut minutely: Vec<Value> = (0..12).map(|i| minute(i, 0.0, 0.0)).collect();
+        minutely.extend((12..30).map(|i| minute(i, 0.1, 0.8)));
+        minutely.extend((30..61).map(|i| minute(i, 0.0, 0.1)));
+
+        let events = response(minutely).minutely_precip_events();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, PrecipEventKind::Starting);
+        assert_eq!(events[0].minutes_from_now, 12);
+        assert_eq!(events[0].time, 1720);
+        assert_eq!(events[0].precip_type, Some(PrecipType::Rain));
+        assert_eq!(events[0].confidence, Some(0.8));
+        assert_eq!(events[0].to_string(), "rain starting in 12 minutes");
+        assert_eq!(events[1].kind, PrecipEventKind::Stopping);
+        assert_eq!(events[1].minutes_from_now, 30);
+        assert_eq!(events[1].confidence, Some(0.9));
+        assert_eq!(events[1].to_string(), "rain stopping in 30 minutes");
+    }
+
+    #[test]
+    fn test_unlikely_precipitation_is_ignored() {
+        let minutely: Vec<Value> = (0..61).map(|i| minute(i, 0.05, 0.2)).collect();
+
+        assert!(response(minutely).minutely_precip_events().is_empty());
+        assert!(response(Vec::new()).minutely_precip_events().is_empty());
+    }
+}
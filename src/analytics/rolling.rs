@@ -0,0 +1,256 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Rolling statistics over the numeric fields of a `DataBlock`.
+//!
+//! A `Rolling` window slides over the points of a block and computes a
+//! `Statistic` of one field, selected with an accessor such as
+//! `|p| p.temperature`. The result has one entry per point, carrying
+//! the point's `time`, so that it lines up with the block for charting:
+//!
+//! ```
+//! use forecast::analytics::rolling::{Rolling, Statistic};
+//! # use forecast::DataBlock;
+//! # let minutely: DataBlock = serde_json::from_str(
+//! #     "{\"data\":[{\"time\":0,\"precipIntensity\":0.1},{\"time\":60}]}").unwrap();
+//!
+//! let smoothed = Rolling::new(5)
+//!     .centered()
+//!     .apply(&minutely, Statistic::Mean, |p| p.precip_intensity);
+//! # assert_eq!(smoothed, vec![(0, Some(0.1)), (60, Some(0.1))]);
+//! ```
+//!
+//! Points missing the field are skipped, and windows at the edges of
+//! the block are truncated, so a window's statistic is computed over
+//! however many values it holds. Windows holding fewer than
+//! `min_values` values yield `None`.
+
+use crate::{DataBlock, DataPoint};
+
+/// A statistic computed over each window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Statistic {
+    /// The arithmetic mean.
+    Mean,
+
+    /// The smallest value.
+    Min,
+
+    /// The largest value.
+    Max,
+
+    /// The given percentile, from 0 to 100, linearly interpolated
+    /// between the closest values.
+    Percentile(f64)
+}
+
+/// Where each window sits relative to the point it is reported at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// The window ends at the point, so only earlier values are used.
+    Trailing,
+
+    /// The window is centered on the point.
+    Centered
+}
+
+/// Every statistic of one window, as returned by `Rolling::summary`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollingSummary {
+    pub time: i64,
+
+    pub mean: Option<f64>,
+
+    pub min: Option<f64>,
+
+    pub max: Option<f64>,
+
+    pub p10: Option<f64>,
+
+    pub p90: Option<f64>
+}
+
+/// A sliding window over the points of a `DataBlock`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rolling {
+    window: usize,
+    alignment: Alignment,
+    min_values: usize
+}
+
+impl Rolling {
+    /// A trailing window of `window` points. A window of zero points is
+    /// treated as a single point.
+    pub fn new(window: usize) -> Rolling {
+        Rolling { window: window.max(1), alignment: Alignment::Trailing, min_values: 1 }
+    }
+
+    /// Center the window on each point.
+    pub fn centered(mut self) -> Rolling {
+        self.alignment = Alignment::Centered;
+        self
+    }
+
+    /// Set the alignment of the window.
+    pub fn alignment(mut self, alignment: Alignment) -> Rolling {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Yield `None` for windows holding fewer than `min_values` values
+    /// (default: 1).
+    pub fn min_values(mut self, min_values: usize) -> Rolling {
+        self.min_values = min_values.max(1);
+        self
+    }
+
+    /// Compute `statistic` of the field selected by `field` over each
+    /// window of `block`.
+    pub fn apply<F>(&self, block: &DataBlock, statistic: Statistic, field: F)
+        -> Vec<(i64, Option<f64>)> where F : Fn(&DataPoint) -> Option<f64> {
+        self.windows(block, field)
+            .map(|(time, values)| (time, compute(&values, statistic)))
+            .collect()
+    }
+
+    /// Compute the mean, minimum, maximum, 10th and 90th percentile of
+    /// the field selected by `field` over each window of `block`.
+    pub fn summary<F>(&self, block: &DataBlock, field: F) -> Vec<RollingSummary>
+        where F : Fn(&DataPoint) -> Option<f64> {
+        self.windows(block, field)
+            .map(|(time, values)| RollingSummary {
+                time,
+                mean: compute(&values, Statistic::Mean),
+                min: compute(&values, Statistic::Min),
+                max: compute(&values, Statistic::Max),
+                p10: compute(&values, Statistic::Percentile(10.0)),
+                p90: compute(&values, Statistic::Percentile(90.0))
+            })
+            .collect()
+    }
+
+    // The time of each point with the values in its window, sorted, or
+    // no values if there are fewer than `min_values`.
+    fn windows<'a, F>(&'a self, block: &'a DataBlock, field: F)
+        -> impl Iterator<Item = (i64, Vec<f64>)> + 'a where F : Fn(&DataPoint) -> Option<f64> + 'a {
+        let values: Vec<Option<f64>> = block.iter().map(&field).collect();
+        let (before, after) = match self.alignment {
+            Alignment::Trailing => (self.window - 1, 0),
+            Alignment::Centered => ((self.window - 1) / 2, self.window / 2)
+        };
+
+        block.iter().enumerate().map(move |(i, point)| {
+            let start = i.saturating_sub(before);
+            let end = (i + after + 1).min(values.len());
+
+            let mut window: Vec<f64> = values[start..end].iter().flatten().cloned().collect();
+
+            if window.len() < self.min_values {
+                window.clear();
+            }
+
+            window.sort_by(|a, b| a.total_cmp(b));
+
+            (point.time, window)
+        })
+    }
+}
+
+// Compute `statistic` over sorted `values`.
+fn compute(values: &[f64], statistic: Statistic) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    match statistic {
+        Statistic::Mean => Some(values.iter().sum::<f64>() / values.len() as f64),
+        Statistic::Min => values.first().cloned(),
+        Statistic::Max => values.last().cloned(),
+        Statistic::Percentile(percentile) => {
+            let rank = percentile.clamp(0.0, 100.0) / 100.0 * (values.len() - 1) as f64;
+            let lower = rank.floor() as usize;
+            let upper = rank.ceil() as usize;
+
+            Some(values[lower] + (values[upper] - values[lower]) * (rank - lower as f64))
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Rolling, Statistic};
+
+    use crate::DataBlock;
+
+    use serde_json::json;
+
+    fn block() -> DataBlock {
+        serde_json::from_value(json!({"data": [
+            {"time": 0, "temperature": 1.0},
+            {"time": 1, "temperature": 5.0},
+            {"time": 2},
+            {"time": 3, "temperature": 3.0},
+            {"time": 4, "temperature": 7.0}
+        ]})).unwrap()
+    }
+
+    #[test]
+    fn test_trailing_mean() {
+        let means = Rolling::new(2).apply(&block(), Statistic::Mean, |p| p.temperature);
+
+        assert_eq!(means, vec![
+            (0, Some(1.0)), (1, Some(3.0)), (2, Some(5.0)), (3, Some(3.0)), (4, Some(5.0))
+        ]);
+
+        let means = Rolling::new(2).min_values(2)
+            .apply(&block(), Statistic::Mean, |p| p.temperature);
+
+        assert_eq!(means.iter().map(|(_, m)| *m).collect::<Vec<_>>(),
+                   vec![None, Some(3.0), None, None, Some(5.0)]);
+    }
+
+    #[test]
+    fn test_centered_min_max() {
+        let rolling = Rolling::new(3).centered();
+
+        assert_eq!(
+            rolling.apply(&block(), Statistic::Min, |p| p.temperature)
+                .into_iter().map(|(_, v)| v.unwrap()).collect::<Vec<_>>(),
+            vec![1.0, 1.0, 3.0, 3.0, 3.0]
+        );
+        assert_eq!(
+            rolling.apply(&block(), Statistic::Max, |p| p.temperature)
+                .into_iter().map(|(_, v)| v.unwrap()).collect::<Vec<_>>(),
+            vec![5.0, 5.0, 5.0, 7.0, 7.0]
+        );
+    }
+
+    #[test]
+    fn test_summary_percentiles() {
+        let summary = Rolling::new(5).summary(&block(), |p| p.temperature);
+        let last = summary.last().unwrap();
+
+        // the last window holds 1, 3, 5 and 7
+        assert_eq!(last.time, 4);
+        assert_eq!(last.mean, Some(4.0));
+        assert_eq!(last.min, Some(1.0));
+        assert_eq!(last.max, Some(7.0));
+        assert!((last.p10.unwrap() - 1.6).abs() < 1e-9);
+        assert!((last.p90.unwrap() - 6.4).abs() < 1e-9);
+        assert_eq!(summary[0].p90, Some(1.0));
+    }
+}
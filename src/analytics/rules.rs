@@ -0,0 +1,275 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Threshold rules evaluated against responses.
+//!
+//! A `Rule` declares a condition on a numeric field of a `DataPoint`
+//! over a part of the forecast, e.g. "wind gusts above 20 within the
+//! next 24 hours". Rules are plain data, so a `RuleSet` can be loaded
+//! from configuration:
+//!
+//! ```
+//! use forecast::analytics::rules::RuleSet;
+//!
+//! let rules: RuleSet = serde_json::from_str(r#"{"rules": [
+//!     {"name": "gusty", "field": "wind_gust", "condition": {"above": 20},
+//!      "when": {"within_hours": 24}},
+//!     {"name": "wet tomorrow", "field": "precip_probability", "condition": {"above": 0.7},
+//!      "when": "tomorrow"}
+//! ]}"#).unwrap();
+//! ```
+//!
+//! Fields are named as in `DataPoint`, and thresholds are in the units
+//! the response was requested in. Evaluating a `RuleSet` yields a
+//! `RuleMatch` for every rule whose condition holds.
+
+use std::fmt;
+
+use crate::{ApiResponse, DataPoint};
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+type Accessor = fn(&DataPoint) -> Option<f64>;
+
+macro_rules! rule_fields {
+    ($($field:ident),*) => {
+        &[$((stringify!($field), |p: &DataPoint| p.$field)),*]
+    }
+}
+
+static RULE_FIELDS: &[(&str, Accessor)] = rule_fields!(
+    apparent_temperature, apparent_temperature_high, apparent_temperature_low, cape,
+    cloud_cover, dew_point, humidity, moon_phase, nearest_storm_bearing, nearest_storm_distance, ozone,
+    precip_accumulation, precip_intensity, precip_intensity_max, precip_probability, pressure,
+    temperature, temperature_high, temperature_low, uv_index, visibility, wind_bearing,
+    wind_gust, wind_speed
+);
+
+/// A comparison of a field against a threshold.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Above(f64),
+    AtLeast(f64),
+    Below(f64),
+    AtMost(f64)
+}
+
+impl Condition {
+    /// Whether `value` satisfies this condition.
+    pub fn holds(&self, value: f64) -> bool {
+        match *self {
+            Condition::Above(threshold) => value > threshold,
+            Condition::AtLeast(threshold) => value >= threshold,
+            Condition::Below(threshold) => value < threshold,
+            Condition::AtMost(threshold) => value <= threshold
+        }
+    }
+}
+
+/// The part of a response a rule is evaluated against.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum When {
+    /// The `currently` block.
+    #[default]
+    Currently,
+
+    /// The points of the `hourly` block within this many hours of the
+    /// current conditions.
+    WithinHours(u32),
+
+    /// The first day of the `daily` block.
+    Today,
+
+    /// The second day of the `daily` block.
+    Tomorrow
+}
+
+/// A named condition on a field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+
+    pub field: String,
+
+    pub condition: Condition,
+
+    #[serde(default)]
+    pub when: When
+}
+
+impl Rule {
+    /// Evaluate this rule against `response`, returning the first point
+    /// at which the condition holds.
+    pub fn evaluate(&self, response: &ApiResponse) -> Result<Option<RuleMatch>, RuleError> {
+        let (_, accessor) = RULE_FIELDS.iter()
+            .find(|(name, _)| *name == self.field)
+            .ok_or_else(|| RuleError::UnknownField(self.field.clone()))?;
+
+        let matched = points(response, self.when)
+            .into_iter()
+            .filter_map(|point| accessor(point).map(|value| (point, value)))
+            .find(|(_, value)| self.condition.holds(*value));
+
+        Ok(matched.map(|(point, value)| RuleMatch {
+            rule: self.name.clone(),
+            time: point.time,
+            value
+        }))
+    }
+}
+
+/// A collection of rules evaluated together.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>
+}
+
+impl RuleSet {
+    /// Construct a RuleSet from `rules`.
+    pub fn new(rules: Vec<Rule>) -> RuleSet {
+        RuleSet { rules }
+    }
+
+    /// Check that every rule refers to a known field, e.g. right after
+    /// loading the rules.
+    pub fn validate(&self) -> Result<(), RuleError> {
+        match self.rules.iter().find(|r| RULE_FIELDS.iter().all(|(name, _)| *name != r.field)) {
+            Some(rule) => Err(RuleError::UnknownField(rule.field.clone())),
+            None => Ok(())
+        }
+    }
+
+    /// Evaluate every rule against `response`, returning a match for
+    /// each rule whose condition holds, in the order of the rules.
+    pub fn evaluate(&self, response: &ApiResponse) -> Result<Vec<RuleMatch>, RuleError> {
+        let mut matches = Vec::new();
+
+        for rule in &self.rules {
+            matches.extend(rule.evaluate(response)?);
+        }
+
+        Ok(matches)
+    }
+}
+
+/// A rule whose condition held.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    /// The name of the rule.
+    pub rule: String,
+
+    /// The time of the first point at which the condition held.
+    pub time: i64,
+
+    /// The value of the field at that point.
+    pub value: f64
+}
+
+/// Errors which can occur while evaluating rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// A rule refers to a field which doesn't exist or isn't numeric.
+    UnknownField(String)
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::UnknownField(field) => write!(f, "unknown field: {}", field)
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+fn points(response: &ApiResponse, when: When) -> Vec<&DataPoint> {
+    match when {
+        When::Currently => response.currently.iter().collect(),
+        When::WithinHours(hours) => {
+            let hourly: Vec<&DataPoint> = response.hourly.iter().flatten().collect();
+            let start = response.currently.as_ref()
+                .or(hourly.first().cloned())
+                .map_or(0, |point| point.time);
+            let end = start + hours as i64 * SECONDS_PER_HOUR;
+
+            hourly.into_iter().filter(|point| point.time < end).collect()
+        },
+        When::Today => response.daily.iter().flat_map(|daily| daily.data.first()).collect(),
+        When::Tomorrow => response.daily.iter().flat_map(|daily| daily.data.get(1)).collect()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Condition, Rule, RuleError, RuleMatch, RuleSet, When};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.4, "longitude": -71.1, "timezone": "America/New_York", "offset": -5,
+            "currently": {"time": 0, "windGust": 5.0},
+            "hourly": {"data": [
+                {"time": 0, "windGust": 5.0},
+                {"time": 36000, "windGust": 22.0},
+                {"time": 90000, "windGust": 30.0}
+            ]},
+            "daily": {"data": [
+                {"time": 0, "precipProbability": 0.2},
+                {"time": 86400, "precipProbability": 0.8}
+            ]}
+        }))
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let rules: RuleSet = serde_json::from_value(json!({"rules": [
+            {"name": "gusty", "field": "wind_gust", "condition": {"above": 20},
+             "when": {"within_hours": 24}},
+            {"name": "calm", "field": "wind_gust", "condition": {"at_most": 5}},
+            {"name": "wet today", "field": "precip_probability", "condition": {"above": 0.7},
+             "when": "today"},
+            {"name": "wet tomorrow", "field": "precip_probability", "condition": {"above": 0.7},
+             "when": "tomorrow"}
+        ]})).unwrap();
+
+        assert_eq!(rules.validate(), Ok(()));
+        assert_eq!(rules.evaluate(&response()).unwrap(), vec![
+            RuleMatch { rule: "gusty".to_string(), time: 36000, value: 22.0 },
+            RuleMatch { rule: "calm".to_string(), time: 0, value: 5.0 },
+            RuleMatch { rule: "wet tomorrow".to_string(), time: 86400, value: 0.8 }
+        ]);
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let rules = RuleSet::new(vec![Rule {
+            name: "bad".to_string(),
+            field: "summary".to_string(),
+            condition: Condition::Below(0.0),
+            when: When::Currently
+        }]);
+
+        assert_eq!(rules.validate(), Err(RuleError::UnknownField("summary".to_string())));
+        assert!(rules.evaluate(&response()).is_err());
+    }
+}
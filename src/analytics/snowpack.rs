@@ -0,0 +1,218 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Snow depth tracked across observed and forecast hours, for ski
+//! resorts and transport operators.
+//!
+//! A `Snowpack` starts from a known depth and steps through hourly
+//! points in time order, typically Time Machine responses for the past
+//! days followed by a forecast. Each hour it:
+//!
+//! * adds the hour's `precip_accumulation` of snow;
+//! * melts `melt_factor` centimeters for every degree the temperature is
+//!   above `melt_temperature`, the degree-hour method;
+//! * settles the pack by `settling_rate` of its depth, as fresh snow
+//!   compacts under its own weight.
+//!
+//! Every parameter is in SI units, whatever units the responses are in.
+
+use std::collections::BTreeMap;
+
+use crate::convert::{celsius, centimeters, response_units};
+use crate::{ApiResponse, PrecipType};
+
+// constants
+
+const STEADY_DEPTH: f64 = 1.0; // cm
+
+/// The snow depth at the end of one hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnowDepth {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// The depth at the end of the hour, in cm.
+    pub depth: f64,
+
+    /// The snow which fell in the hour, in cm.
+    pub snowfall: f64,
+
+    /// The snow which melted or settled in the hour, in cm.
+    pub loss: f64
+}
+
+/// Which way the snow depth is heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnowTrend {
+    Building,
+    Steady,
+    Melting
+}
+
+/// A model of the snowpack at a location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snowpack {
+    initial_depth: f64,
+    melt_factor: f64,
+    melt_temperature: f64,
+    settling_rate: f64
+}
+
+impl Snowpack {
+    /// A snowpack `initial_depth` cm deep.
+    pub fn new(initial_depth: f64) -> Snowpack {
+        Snowpack {
+            initial_depth,
+            melt_factor: 0.1,
+            melt_temperature: 0.0,
+            settling_rate: 0.002
+        }
+    }
+
+    /// The depth of snow, in cm, melted per degree-hour above the melt
+    /// temperature. Defaults to 0.1.
+    pub fn melt_factor(mut self, centimeters: f64) -> Snowpack {
+        self.melt_factor = centimeters;
+        self
+    }
+
+    /// The temperature, in °C, above which snow melts. Defaults to 0.
+    pub fn melt_temperature(mut self, temperature: f64) -> Snowpack {
+        self.melt_temperature = temperature;
+        self
+    }
+
+    /// The fraction of its depth the snowpack settles per hour. Defaults
+    /// to 0.002, about 5% a day.
+    pub fn settling_rate(mut self, rate: f64) -> Snowpack {
+        self.settling_rate = rate;
+        self
+    }
+
+    /// Track the depth through the hourly blocks of `responses`, merged
+    /// in time order. Where responses overlap, the later one in
+    /// `responses` wins, so pass observations before forecasts.
+    pub fn track(&self, responses: &[&ApiResponse]) -> Vec<SnowDepth> {
+        let mut hours = BTreeMap::new();
+
+        for response in responses {
+            let units = response_units(response);
+
+            for point in response.hourly.iter().flatten() {
+                let snowfall = match point.precip_type {
+                    Some(PrecipType::Snow) => point.precip_accumulation
+                        .map_or(0.0, |accumulation| centimeters(accumulation, units)),
+                    _ => 0.0
+                };
+                let temperature = point.temperature.map(|t| celsius(t, units));
+
+                hours.insert(point.time, (snowfall, temperature));
+            }
+        }
+
+        let mut depth = self.initial_depth;
+
+        hours.into_iter()
+            .map(|(time, (snowfall, temperature))| {
+                let degrees = temperature.map_or(0.0, |t| (t - self.melt_temperature).max(0.0));
+                let with_snowfall = depth + snowfall;
+                let melted = (self.melt_factor * degrees).min(with_snowfall);
+                let settled = (with_snowfall - melted) * self.settling_rate;
+
+                depth = with_snowfall - melted - settled;
+
+                SnowDepth { time, depth, snowfall, loss: melted + settled }
+            })
+            .collect()
+    }
+}
+
+/// The trend of `depths` as tracked by `Snowpack::track`: whether the
+/// depth at the end is more than a centimeter above or below the depth
+/// at the start.
+pub fn trend(depths: &[SnowDepth]) -> SnowTrend {
+    let start = depths.first().map_or(0.0, |first| first.depth - first.snowfall + first.loss);
+    let end = depths.last().map_or(start, |last| last.depth);
+
+    if end - start >= STEADY_DEPTH {
+        SnowTrend::Building
+    } else if start - end >= STEADY_DEPTH {
+        SnowTrend::Melting
+    } else {
+        SnowTrend::Steady
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{trend, SnowTrend, Snowpack};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    fn response(units: &str, hourly: Vec<Value>) -> ApiResponse {
+        testing::response(json!({
+            "hourly": {"data": hourly},
+            "flags": {"sources": [], "units": units}
+        }))
+    }
+
+    #[test]
+    fn test_track() {
+        let observed = response("us", vec![
+            json!({"time": 0, "temperature": 20.0, "precipType": "snow",
+                   "precipAccumulation": 1.0}),
+            json!({"time": 3600, "temperature": 20.0, "precipType": "snow",
+                   "precipAccumulation": 1.0})
+        ]);
+        let forecast = response("si", vec![
+            json!({"time": 3600, "temperature": -5.0, "precipType": "snow",
+                   "precipAccumulation": 3.0}),
+            json!({"time": 7200, "temperature": 5.0}),
+            json!({"time": 10_800, "temperature": 5.0, "precipType": "rain",
+                   "precipAccumulation": 1.0})
+        ]);
+
+        let snowpack = Snowpack::new(10.0).settling_rate(0.0);
+        let depths = snowpack.track(&[&observed, &forecast]);
+        let values: Vec<(i64, f64)> = depths.iter()
+            .map(|hour| (hour.time, (hour.depth * 1000.0).round() / 1000.0))
+            .collect();
+
+        assert_eq!(values, vec![(0, 12.54), (3600, 15.54), (7200, 15.04), (10_800, 14.54)]);
+        assert_eq!(trend(&depths), SnowTrend::Building);
+        assert_eq!(trend(&depths[2..]), SnowTrend::Melting);
+        assert_eq!(trend(&[]), SnowTrend::Steady);
+    }
+
+    #[test]
+    fn test_melt_and_settle() {
+        let warm = response("si", (0..24)
+            .map(|hour| json!({"time": hour * 3600, "temperature": 10.0}))
+            .collect());
+
+        let depths = Snowpack::new(10.0).track(&[&warm]);
+
+        assert_eq!(depths[9].depth, 0.0);
+        assert_eq!(depths[23].depth, 0.0);
+
+        let settled = Snowpack::new(100.0).melt_factor(0.0).track(&[&warm]);
+
+        assert!((settled[23].depth - 100.0 * 0.998f64.powi(24)).abs() < 1e-9);
+    }
+}
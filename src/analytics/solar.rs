@@ -0,0 +1,189 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The position of the sun and the length of the day, computed locally
+//! from a location's latitude and longitude, e.g. for photography or
+//! solar energy applications.
+//!
+//! The sun's position follows the low-precision formulas of the
+//! Astronomical Almanac, which are good to about 0.01° between 1950
+//! and 2050; refraction isn't accounted for. Day lengths run from the
+//! time the upper limb of the sun rises to the time it sets, as with the
+//! API's `sunrise_time` and `sunset_time`.
+
+use crate::ApiResponse;
+
+// constants
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// 2000-01-01T12:00:00Z, the J2000.0 epoch
+const J2000: f64 = 946_728_000.0;
+
+// the altitude of the sun's center at sunrise and sunset, allowing for
+// refraction and the sun's radius
+const SUNRISE_ALTITUDE: f64 = -0.833;
+
+/// The position of the sun in the sky.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunPosition {
+    /// Degrees above the horizon, negative when the sun is below it.
+    pub elevation: f64,
+
+    /// Degrees clockwise from true north.
+    pub azimuth: f64
+}
+
+/// The position of the sun seen from `latitude` and `longitude` at
+/// `time` (seconds since the UNIX epoch).
+pub fn sun_position(latitude: f64, longitude: f64, time: i64) -> SunPosition {
+    let days = days_since_j2000(time);
+    let (right_ascension, declination) = equatorial(days);
+
+    let sidereal_time = 280.460_618_37 + 360.985_647_366_29 * days + longitude;
+    let hour_angle = (sidereal_time - right_ascension).to_radians();
+    let latitude = latitude.to_radians();
+    let declination = declination.to_radians();
+
+    let elevation = (latitude.sin() * declination.sin()
+        + latitude.cos() * declination.cos() * hour_angle.cos()).asin();
+    let azimuth = (-hour_angle.sin()).atan2(
+        declination.tan() * latitude.cos() - latitude.sin() * hour_angle.cos());
+
+    SunPosition {
+        elevation: elevation.to_degrees(),
+        azimuth: azimuth.to_degrees().rem_euclid(360.0)
+    }
+}
+
+/// The length of the day, in seconds, at `latitude` on the day around
+/// `time` (seconds since the UNIX epoch): 0 during polar night and
+/// 86,400 during the midnight sun.
+pub fn daylight_duration(latitude: f64, time: i64) -> i64 {
+    let (_, declination) = equatorial(days_since_j2000(time));
+    let latitude = latitude.to_radians();
+    let declination = declination.to_radians();
+
+    let cos_hour_angle = (SUNRISE_ALTITUDE.to_radians().sin()
+        - latitude.sin() * declination.sin()) / (latitude.cos() * declination.cos());
+    let hour_angle = cos_hour_angle.clamp(-1.0, 1.0).acos();
+
+    (hour_angle / std::f64::consts::PI * SECONDS_PER_DAY).round() as i64
+}
+
+/// How much longer, in seconds, the day around `time` is at `latitude`
+/// than the day before; negative as the days shorten.
+pub fn daylight_change(latitude: f64, time: i64) -> i64 {
+    daylight_duration(latitude, time) - daylight_duration(latitude, time - SECONDS_PER_DAY as i64)
+}
+
+impl ApiResponse {
+    /// The position of the sun at this response's location at `time`
+    /// (seconds since the UNIX epoch).
+    pub fn sun_position(&self, time: i64) -> SunPosition {
+        sun_position(self.latitude, self.longitude, time)
+    }
+
+    /// The length of the day, and its change from the day before, for
+    /// each point of the daily block, as `(time, duration, change)` in
+    /// seconds.
+    pub fn daylight(&self) -> Vec<(i64, i64, i64)> {
+        self.daily.iter()
+            .flatten()
+            .map(|point| {
+                let noon = point.time + SECONDS_PER_DAY as i64 / 2;
+
+                (
+                    point.time,
+                    daylight_duration(self.latitude, noon),
+                    daylight_change(self.latitude, noon)
+                )
+            })
+            .collect()
+    }
+}
+
+fn days_since_j2000(time: i64) -> f64 {
+    (time as f64 - J2000) / SECONDS_PER_DAY
+}
+
+// the sun's right ascension and declination, in degrees
+fn equatorial(days: f64) -> (f64, f64) {
+    let mean_longitude = 280.460 + 0.985_647_4 * days;
+    let mean_anomaly = (357.528 + 0.985_600_3 * days).to_radians();
+    let longitude = (mean_longitude + 1.915 * mean_anomaly.sin()
+        + 0.020 * (2.0 * mean_anomaly).sin()).to_radians();
+    let obliquity = (23.439 - 0.000_000_4 * days).to_radians();
+
+    let right_ascension = (obliquity.cos() * longitude.sin()).atan2(longitude.cos());
+    let declination = (obliquity.sin() * longitude.sin()).asin();
+
+    (right_ascension.to_degrees(), declination.to_degrees())
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{daylight_change, daylight_duration, sun_position};
+
+    // 2018-06-21T12:00:00Z, just after the June solstice
+    const SOLSTICE: i64 = 1_529_582_400;
+
+    // 2018-03-20T12:00:00Z, just before the March equinox
+    const EQUINOX: i64 = 1_521_547_200;
+
+    #[test]
+    fn test_sun_position() {
+        // the sun is overhead the Tropic of Cancer at noon on the solstice
+        let position = sun_position(23.44, 0.0, SOLSTICE);
+
+        assert!(position.elevation > 89.5, "{:?}", position);
+
+        // in London it's due south, 62° up
+        let position = sun_position(51.5, 0.0, SOLSTICE);
+
+        assert!((position.elevation - 62.0).abs() < 0.2, "{:?}", position);
+        assert!((position.azimuth - 180.0).abs() < 1.0, "{:?}", position);
+
+        // at 6:00 on the equator at the equinox, it's rising due east,
+        // running about 7 minutes late by the equation of time
+        let position = sun_position(0.0, 0.0, EQUINOX - 6 * 3600);
+
+        assert!((position.elevation + 1.8).abs() < 0.3, "{:?}", position);
+        assert!((position.azimuth - 90.0).abs() < 1.0, "{:?}", position);
+    }
+
+    #[test]
+    fn test_daylight_duration() {
+        // London's longest day is 16h38m
+        assert!((daylight_duration(51.5, SOLSTICE) - 59_880).abs() < 120);
+
+        // a day is a little longer than 12 hours at the equinox
+        assert!((daylight_duration(0.0, EQUINOX) - 43_560).abs() < 120);
+
+        assert_eq!(daylight_duration(80.0, SOLSTICE), 86_400);
+        assert_eq!(daylight_duration(-80.0, SOLSTICE), 0);
+    }
+
+    #[test]
+    fn test_daylight_change() {
+        // around the equinox, London's days lengthen by almost 4 minutes a day
+        let change = daylight_change(51.5, EQUINOX);
+
+        assert!(change > 200 && change < 240, "{}", change);
+        assert!(daylight_change(51.5, SOLSTICE).abs() < 10);
+        assert!(daylight_change(-51.5, EQUINOX) < -200);
+    }
+}
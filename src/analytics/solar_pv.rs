@@ -0,0 +1,251 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Estimated output of a photovoltaic array from the hourly forecast.
+//!
+//! For each hour, evaluated at its midpoint:
+//!
+//! 1. Clear-sky global horizontal irradiance (GHI) follows the Haurwitz
+//!    model from the sun's zenith angle (see the `solar` module).
+//! 2. Clouds attenuate it by the Kasten–Czeplak factor
+//!    `1 - 0.75 C^3.4` for cloud cover `C`. When the forecast has a UV
+//!    index and the sun is high enough for it to be meaningful, the
+//!    attenuation is averaged with the ratio of the UV index to its
+//!    clear-sky value, which reflects cloud thickness as well as cover.
+//! 3. GHI is split into direct and diffuse irradiance with the Erbs
+//!    correlation, and transposed onto the plane of the array with an
+//!    isotropic sky and a ground albedo of 0.2.
+//! 4. Output is the array's capacity scaled by the plane-of-array
+//!    irradiance relative to 1000 W/m², times a performance ratio which
+//!    accounts for inverter, wiring, soiling and temperature losses.
+
+use crate::calendar::LocalDate;
+use crate::solar::sun_position;
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+const SOLAR_CONSTANT: f64 = 1367.0; // W/m²
+const STANDARD_IRRADIANCE: f64 = 1000.0; // W/m²
+const GROUND_ALBEDO: f64 = 0.2;
+const SECONDS_PER_HOUR: i64 = 3600;
+
+// clear-sky UV index at the zenith, and its falloff with the zenith angle
+const CLEAR_SKY_UV_INDEX: f64 = 12.5;
+const CLEAR_SKY_UV_EXPONENT: f64 = 2.42;
+
+// clear-sky UV indices below this are too coarse to estimate clouds from
+const MIN_CLEAR_SKY_UV_INDEX: f64 = 3.0;
+
+/// The estimated output of an array in one hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyGeneration {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// The estimated irradiance on the plane of the array, in W/m².
+    pub irradiance: f64,
+
+    /// The estimated mean output over the hour, in kW, which is also
+    /// the energy generated in the hour in kWh.
+    pub power: f64
+}
+
+/// A photovoltaic array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PvArray {
+    capacity: f64,
+    tilt: f64,
+    azimuth: f64,
+    performance_ratio: f64
+}
+
+impl PvArray {
+    /// An array with a peak output of `capacity` kW at 1000 W/m²,
+    /// lying flat.
+    pub fn new(capacity: f64) -> PvArray {
+        PvArray { capacity, tilt: 0.0, azimuth: 180.0, performance_ratio: 0.8 }
+    }
+
+    /// The tilt of the array from horizontal, in degrees. Defaults to 0.
+    pub fn tilt(mut self, degrees: f64) -> PvArray {
+        self.tilt = degrees;
+        self
+    }
+
+    /// The direction the array faces, in degrees clockwise from true
+    /// north. Defaults to 180, i.e. south.
+    pub fn azimuth(mut self, degrees: f64) -> PvArray {
+        self.azimuth = degrees;
+        self
+    }
+
+    /// The fraction of the array's nominal output delivered after
+    /// losses. Defaults to 0.8.
+    pub fn performance_ratio(mut self, ratio: f64) -> PvArray {
+        self.performance_ratio = ratio;
+        self
+    }
+
+    /// The estimated output for each point of `response`'s hourly block.
+    /// Hours without a cloud cover are taken to be overcast.
+    pub fn hourly(&self, response: &ApiResponse) -> Vec<HourlyGeneration> {
+        response.hourly.iter()
+            .flatten()
+            .map(|point| {
+                let irradiance = self.irradiance(response, point);
+
+                HourlyGeneration {
+                    time: point.time,
+                    irradiance,
+                    power: self.capacity * irradiance / STANDARD_IRRADIANCE
+                        * self.performance_ratio
+                }
+            })
+            .collect()
+    }
+
+    /// The estimated energy generated, in kWh, on each local day of
+    /// `response`'s hourly block.
+    pub fn daily(&self, response: &ApiResponse) -> Vec<(LocalDate, f64)> {
+        let calendar = response.local_calendar();
+        let mut days: Vec<(LocalDate, f64)> = Vec::new();
+
+        for hour in self.hourly(response) {
+            let date = calendar.date(hour.time);
+
+            match days.last_mut() {
+                Some((last, energy)) if *last == date => *energy += hour.power,
+                _ => days.push((date, hour.power))
+            }
+        }
+
+        days
+    }
+
+    // plane-of-array irradiance, in W/m²
+    fn irradiance(&self, response: &ApiResponse, point: &DataPoint) -> f64 {
+        let midpoint = point.time + SECONDS_PER_HOUR / 2;
+        let sun = sun_position(response.latitude, response.longitude, midpoint);
+        let cos_zenith = sun.elevation.to_radians().sin();
+
+        if cos_zenith <= 0.0 {
+            return 0.0;
+        }
+
+        let clear_sky = 1098.0 * cos_zenith * (-0.057 / cos_zenith).exp();
+        let cloud_cover = point.cloud_cover.unwrap_or(1.0);
+        let mut transmittance = 1.0 - 0.75 * cloud_cover.powf(3.4);
+        let clear_sky_uv = CLEAR_SKY_UV_INDEX * cos_zenith.powf(CLEAR_SKY_UV_EXPONENT);
+
+        if let Some(uv_index) = point.uv_index.filter(|_| clear_sky_uv >= MIN_CLEAR_SKY_UV_INDEX) {
+            transmittance = (transmittance + (uv_index / clear_sky_uv).min(1.0)) / 2.0;
+        }
+
+        let ghi = clear_sky * transmittance;
+        let clearness = (ghi / (SOLAR_CONSTANT * cos_zenith)).min(1.0);
+        let dhi = ghi * diffuse_fraction(clearness);
+        let dni = (ghi - dhi) / cos_zenith;
+
+        let tilt = self.tilt.to_radians();
+        let zenith = (90.0 - sun.elevation).to_radians();
+        let cos_incidence = zenith.cos() * tilt.cos()
+            + zenith.sin() * tilt.sin() * (sun.azimuth - self.azimuth).to_radians().cos();
+
+        dni * cos_incidence.max(0.0)
+            + dhi * (1.0 + tilt.cos()) / 2.0
+            + ghi * GROUND_ALBEDO * (1.0 - tilt.cos()) / 2.0
+    }
+}
+
+// the Erbs correlation
+fn diffuse_fraction(clearness: f64) -> f64 {
+    if clearness <= 0.22 {
+        1.0 - 0.09 * clearness
+    } else if clearness <= 0.8 {
+        0.9511 - 0.1604 * clearness + 4.388 * clearness.powi(2) - 16.638 * clearness.powi(3)
+            + 12.336 * clearness.powi(4)
+    } else {
+        0.165
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::PvArray;
+
+    use crate::calendar::LocalDate;
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    // 2018-06-21T00:00:00Z
+    const SOLSTICE: i64 = 1_529_539_200;
+
+    fn response(cloud_cover: f64, uv_index: Option<f64>) -> ApiResponse {
+        let hourly: Vec<Value> = (0..24)
+            .map(|hour| json!({
+                "time": SOLSTICE + hour * 3600, "cloudCover": cloud_cover, "uvIndex": uv_index
+            }))
+            .collect();
+
+        testing::response(json!({
+            "latitude": 51.5, "longitude": 0.0, "timezone": "Europe/London",
+            "hourly": {"data": hourly}
+        }))
+    }
+
+    #[test]
+    fn test_hourly() {
+        let array = PvArray::new(4.0).tilt(35.0);
+        let clear = array.hourly(&response(0.0, None));
+        let overcast = array.hourly(&response(1.0, None));
+
+        assert_eq!(clear.len(), 24);
+        assert_eq!(clear[0].power, 0.0);
+
+        // about 900 W/m² on a clear midsummer noon
+        assert!(clear[11].irradiance > 800.0 && clear[11].irradiance < 1000.0, "{:?}", clear[11]);
+        assert!(overcast[11].power < clear[11].power / 3.0);
+
+        // a south-facing array outperforms a north-facing one
+        let north = PvArray::new(4.0).tilt(35.0).azimuth(0.0).hourly(&response(0.0, None));
+
+        assert!(north[11].power < clear[11].power);
+    }
+
+    #[test]
+    fn test_uv_index() {
+        let array = PvArray::new(4.0);
+        let clear = array.hourly(&response(0.0, None));
+        let hazy = array.hourly(&response(0.0, Some(4.0)));
+
+        assert!(hazy[11].power < clear[11].power);
+        assert_eq!(hazy[3].power, clear[3].power);
+    }
+
+    #[test]
+    fn test_daily() {
+        let days = PvArray::new(4.0).tilt(35.0).daily(&response(0.0, None));
+
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].0, LocalDate::new(2018, 6, 21));
+
+        // a clear midsummer day in London yields around 6 kWh per kW
+        assert!(days[0].1 > 20.0 && days[0].1 < 30.0, "{:?}", days);
+    }
+}
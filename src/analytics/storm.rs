@@ -0,0 +1,177 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Windows of thunderstorm risk in the hourly forecast.
+//!
+//! Each hour's risk is the strongest of these signals:
+//!
+//! * a `thunderstorm`, `hail` or `tornado` icon (confidence 0.9);
+//! * a summary mentioning thunder (0.8);
+//! * convective available potential energy (CAPE), where the provider
+//!   reports it, of at least 1000 J/kg, scaled by the chance of
+//!   precipitation (up to 0.7);
+//! * failing CAPE, a proxy for instability: a warm, humid hour (at
+//!   least 20 °C with a dew point of at least 16 °C) with convective
+//!   precipitation of at least 4 mm/h, scaled by the chance of
+//!   precipitation (up to 0.5).
+//!
+//! Consecutive hours with a risk of at least 0.3 form a `StormWindow`,
+//! whose confidence is that of its riskiest hour.
+
+use crate::convert::{celsius, millimeters, response_units};
+use crate::{ApiResponse, DataPoint, Icon, Units};
+
+// constants
+
+const ICON_CONFIDENCE: f64 = 0.9;
+const SUMMARY_CONFIDENCE: f64 = 0.8;
+const CAPE_CONFIDENCE: f64 = 0.7;
+const PROXY_CONFIDENCE: f64 = 0.5;
+const MIN_CONFIDENCE: f64 = 0.3;
+
+const UNSTABLE_CAPE: f64 = 1000.0; // J/kg
+const WARM_TEMPERATURE: f64 = 20.0; // °C
+const HUMID_DEW_POINT: f64 = 16.0; // °C
+const CONVECTIVE_INTENSITY: f64 = 4.0; // mm/h
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// A run of hours at risk of thunderstorms.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StormWindow {
+    /// The start of the first hour, in seconds since the UNIX epoch.
+    pub start: i64,
+
+    /// The end of the last hour, in seconds since the UNIX epoch.
+    pub end: i64,
+
+    /// How likely thunderstorms are, from 0 to 1.
+    pub confidence: f64
+}
+
+impl ApiResponse {
+    /// The windows of thunderstorm risk in the hourly block, in
+    /// chronological order. See the `storm` module.
+    pub fn storm_windows(&self) -> Vec<StormWindow> {
+        let units = response_units(self);
+        let mut windows: Vec<StormWindow> = Vec::new();
+
+        for point in self.hourly.iter().flatten() {
+            let confidence = storm_risk(point, units);
+
+            if confidence < MIN_CONFIDENCE {
+                continue;
+            }
+
+            match windows.last_mut() {
+                Some(window) if window.end == point.time => {
+                    window.end = point.time + SECONDS_PER_HOUR;
+                    window.confidence = window.confidence.max(confidence);
+                },
+                _ => windows.push(StormWindow {
+                    start: point.time,
+                    end: point.time + SECONDS_PER_HOUR,
+                    confidence
+                })
+            }
+        }
+
+        windows
+    }
+}
+
+/// The risk of thunderstorms at `point`, whose values are in `units`,
+/// from 0 to 1.
+pub fn storm_risk(point: &DataPoint, units: &Units) -> f64 {
+    let icon = match point.icon {
+        Some(Icon::Thunderstorm) | Some(Icon::Hail) | Some(Icon::Tornado) => ICON_CONFIDENCE,
+        _ => 0.0
+    };
+    let summary = point.summary.as_ref()
+        .filter(|summary| summary.to_lowercase().contains("thunder"))
+        .map_or(0.0, |_| SUMMARY_CONFIDENCE);
+    let probability = point.precip_probability.unwrap_or(0.0);
+
+    let instability = match point.cape {
+        Some(cape) if cape >= UNSTABLE_CAPE => CAPE_CONFIDENCE * probability,
+        Some(_) => 0.0,
+        None => {
+            let warm = point.temperature.is_some_and(|t| celsius(t, units) >= WARM_TEMPERATURE);
+            let humid = point.dew_point.is_some_and(|d| celsius(d, units) >= HUMID_DEW_POINT);
+            let convective = point.precip_intensity
+                .is_some_and(|i| millimeters(i, units) >= CONVECTIVE_INTENSITY);
+
+            if warm && humid && convective { PROXY_CONFIDENCE * probability } else { 0.0 }
+        }
+    };
+
+    icon.max(summary).max(instability)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{storm_risk, StormWindow};
+
+    use crate::testing;
+    use crate::{DataPoint, Units};
+
+    use serde_json::{json, Value};
+
+    fn point(value: Value) -> DataPoint {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_storm_risk() {
+        let risk = |value| storm_risk(&point(value), &Units::SI);
+
+        assert_eq!(risk(json!({"time": 0})), 0.0);
+        assert_eq!(risk(json!({"time": 0, "icon": "thunderstorm"})), 0.9);
+        assert_eq!(risk(json!({"time": 0, "summary": "Possible Thunderstorms"})), 0.8);
+        assert_eq!(risk(json!({"time": 0, "cape": 2000.0, "precipProbability": 0.5})), 0.35);
+        assert_eq!(
+            risk(json!({"time": 0, "cape": 200.0, "precipProbability": 0.5, "temperature": 30.0,
+                        "dewPoint": 20.0, "precipIntensity": 10.0})),
+            0.0
+        );
+        assert_eq!(
+            risk(json!({"time": 0, "precipProbability": 0.8, "temperature": 30.0,
+                        "dewPoint": 20.0, "precipIntensity": 10.0})),
+            0.4
+        );
+    }
+
+    #[test]
+    fn test_storm_windows() {
+        let response = testing::response(json!({
+            "hourly": {"data": [
+                {"time": 0, "icon": "rain"},
+                {"time": 3600, "summary": "Thunderstorms"},
+                {"time": 7200, "icon": "thunderstorm"},
+                {"time": 10_800},
+                {"time": 14_400, "cape": 1500.0, "precipProbability": 0.6}
+            ]},
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        let windows = response.storm_windows();
+
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0], StormWindow { start: 3600, end: 10_800, confidence: 0.9 });
+        assert_eq!(windows[1].start, 14_400);
+        assert!((windows[1].confidence - 0.42).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,212 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Air density and the estimated output of a wind turbine, for
+//! renewable energy forecasting.
+//!
+//! A turbine's output is read off its power curve, which gives output
+//! against hub-height wind speed at the standard air density of 1.225
+//! kg/m³. The forecast wind speed, at 10 m, is extrapolated to the hub
+//! height with the power law `v = v₁₀ (h / 10)^α`, and then normalized
+//! to standard density as in IEC 61400-12-1, `v (ρ / 1.225)^⅓`, since
+//! thinner air carries less power at the same speed.
+
+use crate::convert::{celsius, meters_per_second, response_units};
+use crate::ApiResponse;
+
+// constants
+
+const STANDARD_DENSITY: f64 = 1.225; // kg/m³
+const SEA_LEVEL_PRESSURE: f64 = 1013.25; // hPa
+const DRY_AIR_GAS_CONSTANT: f64 = 287.058; // J/(kg K)
+const WATER_VAPOR_GAS_CONSTANT: f64 = 461.495; // J/(kg K)
+const FORECAST_HEIGHT: f64 = 10.0; // m
+
+/// The density of air, in kg/m³, at `temperature` °C, `pressure` hPa
+/// and `humidity` from 0 to 1.
+pub fn air_density(temperature: f64, pressure: f64, humidity: f64) -> f64 {
+    let kelvin = temperature + 273.15;
+    let vapor_pressure = humidity * saturation_vapor_pressure(temperature);
+    let dry_pressure = pressure * 100.0 - vapor_pressure;
+
+    dry_pressure / (DRY_AIR_GAS_CONSTANT * kelvin)
+        + vapor_pressure / (WATER_VAPOR_GAS_CONSTANT * kelvin)
+}
+
+/// A turbine's output against hub-height wind speed at standard air
+/// density, as `(speed, power)` points in m/s and kW sorted by speed.
+/// Output is interpolated linearly between points, and is zero below
+/// the first point (the cut-in speed) and above the last (the cut-out
+/// speed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerCurve {
+    points: Vec<(f64, f64)>
+}
+
+impl PowerCurve {
+    pub fn new(mut points: Vec<(f64, f64)>) -> PowerCurve {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        PowerCurve { points }
+    }
+
+    /// The output, in kW, at `speed` m/s.
+    pub fn power(&self, speed: f64) -> f64 {
+        let index = self.points.partition_point(|(s, _)| *s <= speed);
+
+        match (index.checked_sub(1).map(|i| self.points[i]), self.points.get(index)) {
+            (Some((s0, p0)), Some((s1, p1))) => p0 + (p1 - p0) * (speed - s0) / (s1 - s0),
+            (Some((s0, p0)), None) if speed == s0 => p0,
+            _ => 0.0
+        }
+    }
+}
+
+/// The estimated output of a turbine in one hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyOutput {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    /// The estimated wind speed at the hub, in m/s.
+    pub hub_wind_speed: f64,
+
+    /// The air density, in kg/m³.
+    pub air_density: f64,
+
+    /// The estimated output, in kW.
+    pub power: f64
+}
+
+/// A wind turbine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindTurbine {
+    power_curve: PowerCurve,
+    hub_height: f64,
+    shear_exponent: f64
+}
+
+impl WindTurbine {
+    /// A turbine with `power_curve`, its hub at 80 m.
+    pub fn new(power_curve: PowerCurve) -> WindTurbine {
+        WindTurbine { power_curve, hub_height: 80.0, shear_exponent: 1.0 / 7.0 }
+    }
+
+    /// The height of the hub in meters. Defaults to 80.
+    pub fn hub_height(mut self, height: f64) -> WindTurbine {
+        self.hub_height = height;
+        self
+    }
+
+    /// The wind shear exponent α, which depends on the terrain: about
+    /// 0.1 over open water, 0.14 over open land, and 0.25 or more over
+    /// forest or towns. Defaults to 1/7.
+    pub fn shear_exponent(mut self, exponent: f64) -> WindTurbine {
+        self.shear_exponent = exponent;
+        self
+    }
+
+    /// The estimated output for each point of `response`'s hourly block
+    /// which has a wind speed and temperature. Standard sea-level
+    /// pressure and dry air are assumed where the forecast omits them.
+    pub fn hourly_output(&self, response: &ApiResponse) -> Vec<HourlyOutput> {
+        let units = response_units(response);
+        let shear = (self.hub_height / FORECAST_HEIGHT).powf(self.shear_exponent);
+
+        response.hourly.iter()
+            .flatten()
+            .filter_map(|point| {
+                let wind_speed = meters_per_second(point.wind_speed?, units);
+                let density = air_density(
+                    celsius(point.temperature?, units),
+                    point.pressure.unwrap_or(SEA_LEVEL_PRESSURE),
+                    point.humidity.unwrap_or(0.0)
+                );
+                let hub_wind_speed = wind_speed * shear;
+                let normalized = hub_wind_speed * (density / STANDARD_DENSITY).cbrt();
+
+                Some(HourlyOutput {
+                    time: point.time,
+                    hub_wind_speed,
+                    air_density: density,
+                    power: self.power_curve.power(normalized)
+                })
+            })
+            .collect()
+    }
+}
+
+// in Pa, at `temperature` °C
+fn saturation_vapor_pressure(temperature: f64) -> f64 {
+    610.78 * (17.27 * temperature / (temperature + 237.3)).exp()
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{air_density, PowerCurve, WindTurbine};
+
+    use crate::testing;
+
+    use serde_json::json;
+
+    fn curve() -> PowerCurve {
+        PowerCurve::new(vec![(3.0, 0.0), (12.0, 2000.0), (25.0, 2000.0), (8.0, 800.0)])
+    }
+
+    #[test]
+    fn test_air_density() {
+        assert!((air_density(15.0, 1013.25, 0.0) - 1.225).abs() < 0.001);
+
+        // humid air is lighter than dry air
+        assert!(air_density(30.0, 1013.25, 1.0) < air_density(30.0, 1013.25, 0.0));
+        assert!(air_density(-10.0, 1013.25, 0.5) > 1.3);
+    }
+
+    #[test]
+    fn test_power_curve() {
+        let curve = curve();
+
+        assert_eq!(curve.power(2.0), 0.0);
+        assert_eq!(curve.power(3.0), 0.0);
+        assert_eq!(curve.power(5.5), 400.0);
+        assert_eq!(curve.power(10.0), 1400.0);
+        assert_eq!(curve.power(25.0), 2000.0);
+        assert_eq!(curve.power(26.0), 0.0);
+    }
+
+    #[test]
+    fn test_hourly_output() {
+        let response = testing::response(json!({
+            "hourly": {"data": [
+                {"time": 0, "windSpeed": 8.0, "temperature": 15.0, "pressure": 1013.25,
+                 "humidity": 0.0},
+                {"time": 3600, "windSpeed": 8.0}
+            ]},
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        let output = WindTurbine::new(curve()).hub_height(10.0).hourly_output(&response);
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].hub_wind_speed, 8.0);
+        assert!((output[0].power - 800.0).abs() < 1.0);
+
+        let output = WindTurbine::new(curve()).hourly_output(&response);
+
+        // 8 m/s at 10 m is about 10.8 m/s at 80 m
+        assert!((output[0].hub_wind_speed - 10.8).abs() < 0.1);
+    }
+}
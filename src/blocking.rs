@@ -0,0 +1,202 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! A blocking client, for programs which don't otherwise use async.
+//!
+//! ```no_run
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use forecast::blocking;
+//! use forecast::{ApiClientBuilder, ForecastRequestBuilder};
+//!
+//! let api_client = blocking::ApiClient::new(ApiClientBuilder::new().build()?)?;
+//! let request = ForecastRequestBuilder::new("my_dark_sky_api_key", 6.66, 66.6).build();
+//! let response = api_client.get_forecast_parsed(request)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `blocking` feature.
+
+use std::borrow::Borrow;
+#[cfg(any(feature = "ffi", feature = "python"))]
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+#[cfg(any(feature = "ffi", feature = "python"))]
+use std::sync::mpsc;
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::error;
+use crate::sourced::Sourced;
+use crate::{ApiResponse, CurrentlyDataPoint, DailyDataPoint, ForecastRequest,
+            TimeMachineRequest};
+
+/// Wraps an `ApiClient`, running each request to completion on a
+/// background runtime while the calling thread blocks. The runtime is
+/// shared by every blocking client in the process, so that pooled
+/// connections stay usable between calls.
+///
+/// Calling a method from within an async runtime panics; use the
+/// `ApiClient` itself there.
+#[derive(Debug)]
+pub struct ApiClient<'a> {
+    client: crate::ApiClient<'a>,
+    runtime: &'static Runtime
+}
+
+impl<'a> ApiClient<'a> {
+    /// Construct a new blocking ApiClient which sends requests with
+    /// `client`, configured as usual.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the background runtime can't be started.
+    pub fn new(client: crate::ApiClient<'a>) -> io::Result<ApiClient<'a>> {
+        Ok(ApiClient { client, runtime: runtime()? })
+    }
+
+    /// The wrapped ApiClient.
+    pub fn get_ref(&self) -> &crate::ApiClient<'a> {
+        &self.client
+    }
+
+    /// Like `ApiClient::get_forecast_parsed`.
+    pub fn get_forecast_parsed<'b, T>(&self, request: T) -> error::Result<ApiResponse>
+        where T : Borrow<ForecastRequest<'b>> + Sized {
+        self.runtime.block_on(self.client.get_forecast_parsed(request))
+    }
+
+    /// Like `ApiClient::get_forecast_sourced`.
+    pub fn get_forecast_sourced<'b, T>(&self, request: T) -> error::Result<Sourced<ApiResponse>>
+        where T : Borrow<ForecastRequest<'b>> + Sized {
+        self.runtime.block_on(self.client.get_forecast_sourced(request))
+    }
+
+    /// Like `ApiClient::get_time_machine_parsed`.
+    pub fn get_time_machine_parsed<'b, T>(&self, request: T) -> error::Result<ApiResponse>
+        where T : Borrow<TimeMachineRequest<'b>> + Sized {
+        self.runtime.block_on(self.client.get_time_machine_parsed(request))
+    }
+
+    /// Like `ApiClient::get_time_machine_sourced`.
+    pub fn get_time_machine_sourced<'b, T>(&self, request: T)
+        -> error::Result<Sourced<ApiResponse>> where T : Borrow<TimeMachineRequest<'b>> + Sized {
+        self.runtime.block_on(self.client.get_time_machine_sourced(request))
+    }
+
+    /// Like `ApiClient::get_forecast_window`.
+    pub fn get_forecast_window(
+        &self,
+        api_key: &str,
+        latitude: f64,
+        longitude: f64,
+        window: Range<i64>
+    ) -> error::Result<ApiResponse> {
+        self.runtime.block_on(self.client.get_forecast_window(api_key, latitude, longitude, window))
+    }
+
+    /// Like `ApiClient::get_currently`.
+    pub fn get_currently(&self, api_key: &str, latitude: f64, longitude: f64)
+        -> error::Result<CurrentlyDataPoint> {
+        self.runtime.block_on(self.client.get_currently(api_key, latitude, longitude))
+    }
+
+    /// Like `ApiClient::get_daily`.
+    pub fn get_daily(&self, api_key: &str, latitude: f64, longitude: f64, days: usize)
+        -> error::Result<Vec<DailyDataPoint>> {
+        self.runtime.block_on(self.client.get_daily(api_key, latitude, longitude, days))
+    }
+}
+
+/// Run `future` to completion on the runtime shared by the blocking
+/// clients, blocking the calling thread, for the language bindings.
+/// Returns `None` if the runtime couldn't be started or the future
+/// panicked.
+#[cfg(any(feature = "ffi", feature = "python"))]
+pub(crate) fn block_on<F>(future: F) -> Option<F::Output>
+    where F : Future + Send + 'static, F::Output : Send {
+    let (sender, receiver) = mpsc::channel();
+
+    runtime().ok()?.spawn(async move {
+        let _ = sender.send(future.await);
+    });
+
+    receiver.recv().ok()
+}
+
+// The runtime lives for the rest of the process. If two threads start
+// it at once, the runtime of the one which loses is dropped.
+fn runtime() -> io::Result<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+
+    let runtime = Builder::new_multi_thread().enable_all().build()?;
+
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::ApiClient;
+
+    use crate::http::Url;
+    use crate::testing;
+    use crate::ForecastRequestBuilder;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_blocking_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).unwrap();
+                let body = testing::response_json(serde_json::json!({
+                    "currently": {"time": 1, "temperature": 2.5}
+                })).to_string();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                ).unwrap();
+            }
+        });
+
+        let base_url = Url::parse(&format!("http://{}/forecast", addr)).unwrap();
+        let api_client =
+            ApiClient::new(crate::ApiClient::default().with_base_url(base_url)).unwrap();
+        let request = ForecastRequestBuilder::new("key", 1.0, 2.0).build();
+
+        let response = api_client.get_forecast_parsed(&request).unwrap();
+
+        assert_eq!(response.currently.unwrap().temperature, Some(2.5));
+        assert_eq!(api_client.get_currently("key", 1.0, 2.0).unwrap().time, 1);
+    }
+}
@@ -0,0 +1,304 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! An in-memory cache of forecasts.
+//!
+//! A `ForecastCache` keeps the latest response for every `Site` it has
+//! been asked about. `ForecastCache::get_forecast_cached_or_refresh`
+//! serves the cached response while it's younger than a given age and
+//! fetches a new one otherwise, so that e.g. a UI can paint immediately
+//! and only wait on the API when its data has gone stale.
+//!
+//! `ForecastCache::subscribe` goes further, refreshing a site in a
+//! background task. The returned `Subscription` always holds the latest
+//! forecast and can be awaited for changes, so that a GUI can bind to it
+//! directly.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::watch;
+
+use url::Url;
+
+use crate::cancel::CancellationToken;
+use crate::error::Result;
+use crate::portfolio::Site;
+use crate::sourced::{CacheStatus, Sourced};
+use crate::{ApiClient, ApiResponse};
+
+/// Forecasts for any number of sites, fetched with one API key. Clones
+/// share the same cached forecasts.
+#[derive(Debug, Clone)]
+pub struct ForecastCache {
+    client: Arc<ApiClient<'static>>,
+    api_key: Arc<str>,
+    entries: Arc<Mutex<HashMap<Url, Sourced<ApiResponse>>>>
+}
+
+impl ForecastCache {
+    /// Construct an empty ForecastCache which fetches forecasts with
+    /// `client` using `api_key`.
+    pub fn new(client: Arc<ApiClient<'static>>, api_key: &str) -> ForecastCache {
+        ForecastCache {
+            client,
+            api_key: api_key.into(),
+            entries: Arc::new(Mutex::new(HashMap::new()))
+        }
+    }
+
+    /// The cached forecast for `site`, however old, without contacting
+    /// the API.
+    pub fn cached(&self, site: &Site) -> Option<Sourced<ApiResponse>> {
+        let entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+
+        entries.get(&self.key(site)).cloned().map(|mut sourced| {
+            sourced.cache_status = CacheStatus::Hit;
+            sourced
+        })
+    }
+
+    /// Store `response` as the forecast for `site`, e.g. one restored
+    /// from disk at startup.
+    pub fn insert(&self, site: &Site, response: Sourced<ApiResponse>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+
+        entries.insert(self.key(site), response);
+    }
+
+    /// The cached forecast for `site` if it's at most `max_age` old,
+    /// otherwise a freshly fetched one, which replaces it in the cache.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a fetch was needed and failed, in which case the stale
+    /// forecast, if any, stays cached and can still be had from
+    /// `cached`.
+    pub async fn get_forecast_cached_or_refresh(
+        &self,
+        site: &Site,
+        max_age: Duration
+    ) -> Result<Sourced<ApiResponse>> {
+        match self.cached(site) {
+            Some(cached) if !cached.is_stale(max_age) => Ok(cached),
+            _ => self.refresh(site).await
+        }
+    }
+
+    /// Fetch a new forecast for `site`, replacing the cached one.
+    pub async fn refresh(&self, site: &Site) -> Result<Sourced<ApiResponse>> {
+        let request = site.request(&self.api_key).build();
+        let response = self.client.get_forecast_sourced(&request).await?;
+
+        self.insert(site, response.clone());
+
+        Ok(response)
+    }
+
+    /// Keep the forecast for `site` fresh in a background task, which
+    /// refreshes it every `interval` plus a random delay of up to
+    /// `jitter`, so that many subscriptions don't all hit the API at
+    /// once. A cached forecast younger than `interval` is served until
+    /// it's due for a refresh. Failed refreshes keep the previous
+    /// forecast. The task stops when the `Subscription` is dropped.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn subscribe(&self, site: Site, interval: Duration, jitter: Duration) -> Subscription {
+        let cached = self.cached(&site);
+        let (sender, receiver) = watch::channel(cached.clone());
+
+        let token = CancellationToken::new();
+        let cache = self.clone();
+        let task_token = token.clone();
+
+        tokio::spawn(async move {
+            let mut delay = cached.map_or(Duration::from_secs(0), |cached| {
+                interval.checked_sub(Duration::from_secs(cached.age(unix_now())))
+                    .unwrap_or_default()
+            });
+
+            loop {
+                tokio::select! {
+                    _ = task_token.cancelled() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+
+                let result = tokio::select! {
+                    _ = task_token.cancelled() => return,
+                    result = cache.refresh(&site) => result
+                };
+
+                match result {
+                    Ok(response) => {
+                        if sender.send(Some(response)).is_err() {
+                            return;
+                        }
+                    },
+                    Err(error) => tracing::debug!(%error, "background forecast refresh failed")
+                }
+
+                delay = interval + random_fraction(jitter);
+            }
+        });
+
+        Subscription { receiver, token }
+    }
+
+    fn key(&self, site: &Site) -> Url {
+        site.request(&self.api_key).build().url()
+    }
+}
+
+/// A handle on a forecast kept fresh by `ForecastCache::subscribe`.
+/// Dropping it stops the background refresh.
+#[derive(Debug)]
+pub struct Subscription {
+    receiver: watch::Receiver<Option<Sourced<ApiResponse>>>,
+    token: CancellationToken
+}
+
+impl Subscription {
+    /// The latest forecast, or `None` if none has been fetched yet.
+    pub fn latest(&self) -> Option<Sourced<ApiResponse>> {
+        self.receiver.borrow().clone()
+    }
+
+    /// Wait for the background task to fetch a new forecast, and return
+    /// it. Returns `None` if the task has stopped.
+    pub async fn changed(&mut self) -> Option<Sourced<ApiResponse>> {
+        loop {
+            self.receiver.changed().await.ok()?;
+
+            if let Some(response) = self.receiver.borrow_and_update().clone() {
+                return Some(response);
+            }
+        }
+    }
+
+    /// A receiver for the latest forecast, e.g. to hand to a UI
+    /// framework which binds to watch channels. It stops receiving
+    /// updates once this subscription is dropped.
+    pub fn watch(&self) -> watch::Receiver<Option<Sourced<ApiResponse>>> {
+        self.receiver.clone()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// A random duration of at most `max`. The standard library's randomly
+// seeded hasher is random enough to spread out refreshes.
+fn random_fraction(max: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{random_fraction, ForecastCache};
+
+    use crate::portfolio::Site;
+    use crate::sourced::{CacheStatus, Sourced};
+    use crate::testing;
+    use crate::{ApiClient, ApiResponse, Units};
+
+    use serde_json::json;
+
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    static API_KEY: &str = "some_api_key";
+
+    fn sourced(site: &Site, fetched_at: u64) -> Sourced<ApiResponse> {
+        let response = testing::response(json!({}));
+        let request = site.request(API_KEY).build();
+
+        Sourced::new(response, &request.url(), API_KEY, fetched_at)
+    }
+
+    #[tokio::test]
+    async fn test_get_forecast_cached_or_refresh() {
+        let cache = ForecastCache::new(Arc::new(ApiClient::default()), API_KEY);
+        let site = Site::new(1.0, 2.0).units(Units::SI);
+
+        assert!(cache.cached(&site).is_none());
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        cache.insert(&site, sourced(&site, now - 60));
+
+        let response = cache
+            .get_forecast_cached_or_refresh(&site, Duration::from_secs(300))
+            .await
+            .unwrap();
+
+        assert_eq!(response.cache_status, CacheStatus::Hit);
+        assert_eq!(response.latitude, 1.0);
+        assert!(cache.cached(&Site::new(1.0, 2.0)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let cache = ForecastCache::new(Arc::new(ApiClient::default()), API_KEY);
+        let site = Site::new(1.0, 2.0);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        cache.insert(&site, sourced(&site, now));
+
+        // the cached forecast is fresh, so nothing is fetched for an hour
+        let mut subscription = cache.subscribe(
+            site.clone(),
+            Duration::from_secs(3600),
+            Duration::from_secs(60)
+        );
+
+        let latest = subscription.latest().unwrap();
+
+        assert_eq!(latest.fetched_at, now);
+        assert_eq!(latest.cache_status, CacheStatus::Hit);
+
+        let changed = tokio::time::timeout(Duration::from_millis(50), subscription.changed()).await;
+
+        assert!(changed.is_err());
+
+        let mut watch = subscription.watch();
+
+        drop(subscription);
+
+        let closed = tokio::time::timeout(Duration::from_secs(1), watch.changed()).await;
+
+        assert!(closed.unwrap().is_err());
+    }
+
+    #[test]
+    fn test_random_fraction() {
+        let max = Duration::from_secs(10);
+
+        assert!((0..100).map(|_| random_fraction(max)).all(|jitter| jitter <= max));
+        assert_eq!(random_fraction(Duration::from_secs(0)), Duration::from_secs(0));
+    }
+}
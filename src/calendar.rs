@@ -0,0 +1,404 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Local calendar days at a forecast's location.
+//!
+//! Bucketing hourly data into days by adding a fixed UTC offset goes
+//! wrong on the days around DST transitions. A `LocalCalendar` instead
+//! takes its day boundaries from the `daily` block of a response, whose
+//! points start at local midnight as computed by the API with the
+//! location's full timezone rules, and falls back to the response's UTC
+//! offset only for times the daily block doesn't cover:
+//!
+//! ```
+//! # use forecast::ApiResponse;
+//! # let response: ApiResponse = serde_json::from_str(
+//! #     "{\"latitude\":0,\"longitude\":0,\"timezone\":\"UTC\",\"offset\":0,
+//! #       \"hourly\":{\"data\":[{\"time\":0},{\"time\":3600},{\"time\":86400}]}}").unwrap();
+//! let calendar = response.local_calendar();
+//!
+//! for (date, points) in response.hourly.as_ref().unwrap().group_by_day(&calendar) {
+//!     println!("{}: {} hours", date, points.len());
+//! }
+//! ```
+//!
+//! A calendar also resolves everyday phrases such as "tonight" or "this
+//! weekend" into time ranges at the location, for selecting the points
+//! of a block with `DataBlock::within`.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::datetime::{CivilDateTime, civil_from_days, days_from_civil};
+use crate::{ApiResponse, DataBlock, DataPoint};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const SECONDS_PER_HOUR: i64 = 3600;
+
+// DST transitions happen in the small hours nearly everywhere, so any
+// later hour of a day lies after its transition
+const LATEST_TRANSITION_HOUR: u32 = 3;
+
+const EVENING_HOUR: u32 = 18;
+const MORNING_HOUR: u32 = 6;
+const BUSINESS_HOURS: Range<u32> = 9..17;
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday
+}
+
+static WEEKDAYS: [Weekday; 7] = [
+    Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday, Weekday::Friday,
+    Weekday::Saturday, Weekday::Sunday
+];
+
+/// A calendar date at a forecast's location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LocalDate {
+    pub year: i64,
+
+    pub month: u32,
+
+    pub day: u32
+}
+
+impl LocalDate {
+    pub fn new(year: i64, month: u32, day: u32) -> LocalDate {
+        LocalDate { year, month, day }
+    }
+
+    /// The day of the week of this date.
+    pub fn weekday(&self) -> Weekday {
+        // the UNIX epoch was a Thursday
+        WEEKDAYS[(self.days() + 3).rem_euclid(7) as usize]
+    }
+
+    /// The date `days` days after this one, or before it if negative.
+    pub fn add_days(&self, days: i64) -> LocalDate {
+        let (year, month, day) = civil_from_days(self.days() + days);
+
+        LocalDate::new(year, month, day)
+    }
+
+    fn from_civil(civil: CivilDateTime) -> LocalDate {
+        LocalDate::new(civil.year, civil.month, civil.day)
+    }
+
+    // days since the UNIX epoch
+    fn days(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day)
+    }
+}
+
+impl fmt::Display for LocalDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Resolves times to the local dates they fall on at a location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalCalendar {
+    offset_seconds: i64,
+    midnights: Vec<i64>
+}
+
+impl LocalCalendar {
+    /// A calendar which is always `offset_seconds` east of UTC.
+    pub fn fixed(offset_seconds: i64) -> LocalCalendar {
+        LocalCalendar { offset_seconds, midnights: Vec::new() }
+    }
+
+    /// A calendar whose days start at the times of the points of
+    /// `response`'s daily block, outside of which it's the response's
+    /// UTC offset.
+    #[allow(deprecated)]
+    pub fn from_response(response: &ApiResponse) -> LocalCalendar {
+        let mut midnights: Vec<i64> = response.daily.iter()
+            .flat_map(|daily| daily.data.iter().map(|point| point.time))
+            .collect();
+
+        midnights.sort_unstable();
+        midnights.dedup();
+
+        LocalCalendar { offset_seconds: response.offset * 3600, midnights }
+    }
+
+    /// The local date at `time` (seconds since the UNIX epoch).
+    pub fn date(&self, time: i64) -> LocalDate {
+        match self.day_start(time) {
+            // the local date at noon can't be off by a DST shift
+            Some(midnight) => self.fixed_date(midnight + SECONDS_PER_DAY / 2),
+            None => self.fixed_date(time)
+        }
+    }
+
+    /// The time range of the local day `date`.
+    pub fn day(&self, date: LocalDate) -> Range<i64> {
+        self.midnight(date)..self.midnight(date.add_days(1))
+    }
+
+    /// The time (seconds since the UNIX epoch) at which `hour` o'clock
+    /// starts on the local day `date`.
+    pub fn at(&self, date: LocalDate, hour: u32) -> i64 {
+        let day = self.day(date);
+        let shift = if hour >= LATEST_TRANSITION_HOUR {
+            day.end - day.start - SECONDS_PER_DAY
+        } else {
+            0
+        };
+
+        day.start + i64::from(hour) * SECONDS_PER_HOUR + shift
+    }
+
+    /// The rest of the local day containing `now`.
+    pub fn today(&self, now: i64) -> Range<i64> {
+        now..self.day(self.date(now)).end
+    }
+
+    /// The local day after the one containing `now`.
+    pub fn tomorrow(&self, now: i64) -> Range<i64> {
+        self.day(self.date(now).add_days(1))
+    }
+
+    /// The rest of the night from 18:00 to 06:00 which hasn't ended yet
+    /// at `now`.
+    pub fn tonight(&self, now: i64) -> Range<i64> {
+        let today = self.date(now);
+        let evening = if now < self.at(today, MORNING_HOUR) { today.add_days(-1) } else { today };
+        let start = self.at(evening, EVENING_HOUR);
+
+        start.max(now)..self.at(evening.add_days(1), MORNING_HOUR)
+    }
+
+    /// The rest of the weekend, Saturday and Sunday, which hasn't ended
+    /// yet at `now`.
+    pub fn next_weekend(&self, now: i64) -> Range<i64> {
+        let today = self.date(now);
+        let days_since_saturday = (today.weekday() as i64 - Weekday::Saturday as i64).rem_euclid(7);
+        let saturday = if days_since_saturday < 2 {
+            today.add_days(-days_since_saturday)
+        } else {
+            today.add_days(7 - days_since_saturday)
+        };
+
+        self.midnight(saturday).max(now)..self.midnight(saturday.add_days(2))
+    }
+
+    /// Business hours, from 09:00 to 17:00, on the local day `date`.
+    pub fn business_hours(&self, date: LocalDate) -> Range<i64> {
+        self.at(date, BUSINESS_HOURS.start)..self.at(date, BUSINESS_HOURS.end)
+    }
+
+    // the start of the local day `date`
+    fn midnight(&self, date: LocalDate) -> i64 {
+        let fixed = date.days() * SECONDS_PER_DAY - self.offset_seconds;
+
+        // a known midnight is within a DST shift of the fixed one
+        self.midnights.iter()
+            .find(|midnight| (**midnight - fixed).abs() < SECONDS_PER_DAY / 2)
+            .cloned()
+            .unwrap_or(fixed)
+    }
+
+    // the start of the known day containing `time`, if any
+    fn day_start(&self, time: i64) -> Option<i64> {
+        let index = self.midnights.partition_point(|midnight| *midnight <= time);
+        let midnight = *self.midnights.get(index.checked_sub(1)?)?;
+
+        if index < self.midnights.len() || time < midnight + SECONDS_PER_DAY {
+            Some(midnight)
+        } else {
+            None
+        }
+    }
+
+    fn fixed_date(&self, time: i64) -> LocalDate {
+        LocalDate::from_civil(CivilDateTime::from_unix(time, self.offset_seconds))
+    }
+}
+
+impl ApiResponse {
+    /// The local calendar at this response's location. See
+    /// `LocalCalendar::from_response`.
+    pub fn local_calendar(&self) -> LocalCalendar {
+        LocalCalendar::from_response(self)
+    }
+}
+
+impl DataBlock {
+    /// Group the points of this block by the local date they fall on,
+    /// e.g. to bucket an hourly forecast into days. Points are grouped
+    /// in order, so a block sorted by time yields each date once.
+    pub fn group_by_day<'a>(&'a self, calendar: &'a LocalCalendar) -> DayGroups<'a> {
+        DayGroups { calendar, data: &self.data }
+    }
+
+    /// The points of this block which start within `range`, e.g. one
+    /// resolved by `LocalCalendar::tonight`. The block must be sorted by
+    /// time.
+    pub fn within(&self, range: Range<i64>) -> &[DataPoint] {
+        let start = self.data.partition_point(|point| point.time < range.start);
+        let end = self.data.partition_point(|point| point.time < range.end);
+
+        &self.data[start..end.max(start)]
+    }
+}
+
+/// An iterator over the points of a block grouped by local date,
+/// returned by `DataBlock::group_by_day`.
+#[derive(Debug, Clone)]
+pub struct DayGroups<'a> {
+    calendar: &'a LocalCalendar,
+    data: &'a [DataPoint]
+}
+
+impl<'a> Iterator for DayGroups<'a> {
+    type Item = (LocalDate, &'a [DataPoint]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let date = self.calendar.date(self.data.first()?.time);
+        let len = self.data.iter()
+            .position(|point| self.calendar.date(point.time) != date)
+            .unwrap_or(self.data.len());
+        let (group, rest) = self.data.split_at(len);
+
+        self.data = rest;
+
+        Some((date, group))
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalCalendar, LocalDate, Weekday};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    // 2018-03-11 was the start of DST in New York: local midnight was
+    // 05:00 UTC before it and 04:00 UTC after
+    const MARCH_10: i64 = 1_520_658_000;
+    const MARCH_11: i64 = 1_520_744_400;
+    const MARCH_12: i64 = 1_520_827_200;
+
+    fn response() -> ApiResponse {
+        let hourly: Vec<_> = (0..72).map(|hour| json!({"time": MARCH_10 + hour * 3600})).collect();
+
+        testing::response(json!({
+            "latitude": 40.7, "longitude": -74.0, "timezone": "America/New_York", "offset": -4,
+            "hourly": {"data": hourly},
+            "daily": {"data": [{"time": MARCH_10}, {"time": MARCH_11}, {"time": MARCH_12}]}
+        }))
+    }
+
+    #[test]
+    fn test_local_calendar() {
+        let calendar = response().local_calendar();
+
+        assert_eq!(calendar.date(MARCH_10), LocalDate::new(2018, 3, 10));
+        assert_eq!(calendar.date(MARCH_11 - 1), LocalDate::new(2018, 3, 10));
+        assert_eq!(calendar.date(MARCH_11), LocalDate::new(2018, 3, 11));
+        assert_eq!(calendar.date(MARCH_12 - 1), LocalDate::new(2018, 3, 11));
+        assert_eq!(calendar.date(MARCH_12 + 86_399), LocalDate::new(2018, 3, 12));
+
+        // outside the daily block, the fixed offset applies
+        assert_eq!(calendar.date(MARCH_10 - 4 * 3600 - 1), LocalDate::new(2018, 3, 9));
+        assert_eq!(calendar.date(MARCH_12 + 86_400), LocalDate::new(2018, 3, 13));
+        assert_eq!(LocalCalendar::fixed(0).date(MARCH_10), LocalDate::new(2018, 3, 10));
+    }
+
+    #[test]
+    fn test_group_by_day() {
+        let response = response();
+        let calendar = response.local_calendar();
+        let groups: Vec<_> = response.hourly.as_ref().unwrap()
+            .group_by_day(&calendar)
+            .map(|(date, points)| (date.to_string(), points.len(), points[0].time))
+            .collect();
+
+        assert_eq!(groups, vec![
+            ("2018-03-10".to_string(), 24, MARCH_10),
+            ("2018-03-11".to_string(), 23, MARCH_11),
+            ("2018-03-12".to_string(), 24, MARCH_12),
+            ("2018-03-13".to_string(), 1, MARCH_12 + 86_400)
+        ]);
+    }
+
+    #[test]
+    fn test_local_date() {
+        let date = LocalDate::new(2018, 3, 10);
+
+        assert_eq!(date.weekday(), Weekday::Saturday);
+        assert_eq!(date.add_days(1).weekday(), Weekday::Sunday);
+        assert_eq!(date.add_days(22), LocalDate::new(2018, 4, 1));
+        assert_eq!(date.add_days(-10), LocalDate::new(2018, 2, 28));
+        assert_eq!(LocalDate::new(1970, 1, 1).weekday(), Weekday::Thursday);
+    }
+
+    #[test]
+    fn test_selectors() {
+        let calendar = response().local_calendar();
+        let hour = 3600;
+
+        // Friday 2018-03-09 at noon EST
+        let now = MARCH_10 - 12 * hour;
+
+        assert_eq!(calendar.today(now), now..MARCH_10);
+        assert_eq!(calendar.tomorrow(now), MARCH_10..MARCH_11);
+        assert_eq!(calendar.tonight(now), MARCH_10 - 6 * hour..MARCH_10 + 6 * hour);
+        assert_eq!(calendar.next_weekend(now), MARCH_10..MARCH_12);
+
+        // in the small hours, tonight is the night under way
+        assert_eq!(calendar.tonight(MARCH_10 + hour), MARCH_10 + hour..MARCH_10 + 6 * hour);
+
+        // during the weekend, the weekend is the one under way
+        assert_eq!(calendar.next_weekend(MARCH_11), MARCH_11..MARCH_12);
+        assert_eq!(calendar.next_weekend(MARCH_12).start, MARCH_12 + 5 * 86_400);
+
+        // the day DST starts is an hour short, after 2:00
+        assert_eq!(
+            calendar.business_hours(LocalDate::new(2018, 3, 11)),
+            MARCH_11 + 8 * hour..MARCH_11 + 16 * hour
+        );
+        assert_eq!(
+            calendar.business_hours(LocalDate::new(2018, 3, 12)),
+            MARCH_12 + 9 * hour..MARCH_12 + 17 * hour
+        );
+    }
+
+    #[test]
+    fn test_within() {
+        let response = response();
+        let calendar = response.local_calendar();
+        let hourly = response.hourly.as_ref().unwrap();
+        let tonight = hourly.within(calendar.tonight(MARCH_10 + 12 * 3600));
+
+        assert_eq!(tonight.len(), 11);
+        assert_eq!(tonight[0].time, MARCH_10 + 18 * 3600);
+        assert!(hourly.within(0..1).is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Cooperative cancellation for long-running subsystems.
+//!
+//! A `CancellationToken` is handed to a long-running loop such as
+//! `Scheduler::run_until_cancelled`. Calling `cancel` on any clone of
+//! the token stops the loop: pending sleeps and in-flight requests are
+//! abandoned and the loop returns promptly.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>
+}
+
+/// A cloneable handle used to request that a long-running task stop.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>
+}
+
+impl CancellationToken {
+    /// Construct a new token which has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel this token and every clone of it, waking any task waiting
+    /// on `cancelled`.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+
+        let wakers: Vec<Waker> = match self.inner.wakers.lock() {
+            Ok(mut wakers) => wakers.drain(..).collect(),
+            Err(poisoned) => poisoned.into_inner().drain(..).collect()
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Whether this token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return a future which completes once this token is cancelled.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled { token: self }
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`.
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        if let Ok(mut wakers) = self.token.inner.wakers.lock() {
+            if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+
+        // re-check in case `cancel` ran before the waker was registered
+        if self.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancelled_wakes_waiter() {
+        let token = CancellationToken::new();
+        let waiter = token.clone();
+
+        let handle = tokio::spawn(async move { waiter.cancelled().await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+
+        tokio::time::timeout(Duration::from_secs(1), handle).await.unwrap().unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_already_cancelled() {
+        let token = CancellationToken::new();
+
+        token.cancel();
+        token.cancelled().await;
+    }
+}
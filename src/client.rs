@@ -0,0 +1,1382 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The HTTP client for the Forecast and Time Machine APIs. Only
+//! available with the `client` feature, which is enabled by default.
+
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::slice;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+
+use reqwest::{Proxy, Request};
+
+use tracing::Instrument;
+
+use uuid::Uuid;
+
+use crate::error::{self, Error, ErrorKind, ParseError};
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use crate::http::Certificate;
+use crate::http::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING};
+use crate::http::{self, Client, Response, Url};
+use crate::rate_limit::RateLimiter;
+use crate::request::FORECAST_URL;
+use crate::sourced::Sourced;
+use crate::{ApiResponse, CurrentlyDataPoint, DailyDataPoint, DataBlock, ExcludeBlock,
+            ExtendBy, ForecastRequest, ForecastRequestBuilder, RequestSigner, RetryPolicy, Time,
+            TimeMachineRequest, TimeMachineRequestBuilder, UsageTracker};
+
+// constants
+
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// hours of the standard and extended hourly forecasts
+const FORECAST_HOURS: i64 = 48;
+const EXTENDED_HOURS: i64 = 168;
+
+// enough for the extended forecast and a week of Time Machine requests
+const DEFAULT_MAX_ESCALATIONS: usize = 8;
+
+static REQUEST_ID_HEADER: &str = "x-request-id";
+static API_CALLS_HEADER: &str = "x-forecast-api-calls";
+
+// configuration of ApiClient::shared
+static TIMEOUT_SECS_VAR: &str = "FORECAST_TIMEOUT_SECS";
+static MAX_RETRIES_VAR: &str = "FORECAST_MAX_RETRIES";
+const SHARED_INITIAL_BACKOFF_SECS: u64 = 1;
+
+// only the hourly block is needed to fill gaps in a forecast window
+const TIME_MACHINE_EXCLUDE: [ExcludeBlock; 5] = [
+    ExcludeBlock::Currently, ExcludeBlock::Minutely, ExcludeBlock::Daily, ExcludeBlock::Alerts,
+    ExcludeBlock::Flags
+];
+
+// api objects
+
+/// The ApiClient is a thin wrapper around an HTTP `Client` which sends
+/// requests to the Forecast and Time Machine APIs.
+#[derive(Debug)]
+pub struct ApiClient<'a> {
+    client: Cow<'a, Client>,
+    retry_policy: RetryPolicy,
+    warnings_as_errors: bool,
+    gzip: bool,
+    max_response_size: Option<usize>,
+    max_escalations: usize,
+    request_id_header: bool,
+    base_url: Option<Url>,
+    usage_tracker: Option<Arc<UsageTracker>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    // configuration of the clients built for requests with their own
+    // proxy, which are kept so that they pool connections
+    config: ApiClientBuilder,
+    proxy_clients: Mutex<HashMap<Url, reqwest::Client>>
+}
+
+// The parts of a ForecastRequest or TimeMachineRequest needed to send it.
+struct RequestTarget<'r> {
+    api_key: &'r str,
+    latitude: f64,
+    longitude: f64,
+    url: Url,
+    proxy: Option<&'r Url>
+}
+
+impl<'r> From<&'r ForecastRequest<'_>> for RequestTarget<'r> {
+    fn from(request: &'r ForecastRequest) -> RequestTarget<'r> {
+        RequestTarget {
+            api_key: request.api_key,
+            latitude: request.latitude,
+            longitude: request.longitude,
+            url: request.url(),
+            proxy: request.proxy()
+        }
+    }
+}
+
+impl<'r> From<&'r TimeMachineRequest<'_>> for RequestTarget<'r> {
+    fn from(request: &'r TimeMachineRequest) -> RequestTarget<'r> {
+        RequestTarget {
+            api_key: request.api_key,
+            latitude: request.latitude,
+            longitude: request.longitude,
+            url: request.url(),
+            proxy: request.proxy()
+        }
+    }
+}
+
+impl<'a> ApiClient<'a> {
+    /// Construct a new ApiClient.
+    pub fn new(client: &'a Client) -> ApiClient<'a> {
+        ApiClient::with_client(Cow::Borrowed(client))
+    }
+
+    fn with_client(client: Cow<'a, Client>) -> ApiClient<'a> {
+        ApiClient::with_config(client, ApiClientBuilder::default())
+    }
+
+    fn with_config(client: Cow<'a, Client>, config: ApiClientBuilder) -> ApiClient<'a> {
+        ApiClient {
+            client,
+            retry_policy: RetryPolicy::default(),
+            warnings_as_errors: false,
+            gzip: true,
+            max_response_size: None,
+            max_escalations: DEFAULT_MAX_ESCALATIONS,
+            request_id_header: false,
+            base_url: None,
+            usage_tracker: None,
+            signer: None,
+            rate_limiter: None,
+            config,
+            proxy_clients: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Set the policy used to retry failed requests made by the parsed
+    /// methods (`get_forecast_parsed` and `get_time_machine_parsed`).
+    /// By default, requests are not retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> ApiClient<'a> {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// When set, the parsed methods return `ErrorKind::Degraded`
+    /// instead of a response which carries `warnings`, e.g. so that the
+    /// caller can fall back to another data source. Degraded responses
+    /// are retryable.
+    pub fn warnings_as_errors(mut self, warnings_as_errors: bool) -> ApiClient<'a> {
+        self.warnings_as_errors = warnings_as_errors;
+        self
+    }
+
+    /// Whether the parsed methods ask the API for a gzip-compressed
+    /// response (enabled by default). Compressed responses are
+    /// decompressed by the client regardless of how the underlying
+    /// HTTP `Client` is configured; a response with any other
+    /// `Content-Encoding` fails with `ErrorKind::Decompress`.
+    pub fn gzip(mut self, gzip: bool) -> ApiClient<'a> {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Fail the parsed methods with `ErrorKind::ResponseTooLarge`
+    /// instead of reading a response body larger than `limit` bytes. The
+    /// limit applies both to the body as received and, if it's
+    /// compressed, to the decompressed body, so neither an oversized
+    /// response nor a gzip bomb is ever fully buffered. By default
+    /// there's no limit.
+    pub fn max_response_size(mut self, limit: usize) -> ApiClient<'a> {
+        self.max_response_size = Some(limit);
+        self
+    }
+
+    /// Limit the billed requests `get_forecast_window` sends beyond the
+    /// standard forecast, i.e. the extended forecast and the Time
+    /// Machine requests, to `max`. A window which needs more fails with
+    /// `ErrorKind::EscalationLimit`. Defaults to 8, enough for the
+    /// extended forecast and a week of Time Machine requests.
+    pub fn max_escalations(mut self, max: usize) -> ApiClient<'a> {
+        self.max_escalations = max;
+        self
+    }
+
+    /// Send each request's ID as an `X-Request-Id` header, so that it can
+    /// be correlated with the logs of proxies and other middleware.
+    /// Every request is given a random ID regardless, which is recorded
+    /// on the `forecast_request` tracing span and attached to errors
+    /// returned by the parsed methods. Disabled by default.
+    pub fn request_id_header(mut self, request_id_header: bool) -> ApiClient<'a> {
+        self.request_id_header = request_id_header;
+        self
+    }
+
+    /// Send requests to a Dark Sky compatible API at `base_url`, e.g.
+    /// `https://api.pirateweather.net/forecast`, instead of Dark Sky.
+    /// Requests keep their path below the base URL and their query.
+    pub fn with_base_url(mut self, base_url: Url) -> ApiClient<'a> {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Record every request sent by this client, including retries, in
+    /// `usage_tracker`.
+    pub fn with_usage_tracker(mut self, usage_tracker: Arc<UsageTracker>) -> ApiClient<'a> {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
+    /// Sign every request sent by this client with `signer`.
+    pub fn with_signer(mut self, signer: Arc<dyn RequestSigner>) -> ApiClient<'a> {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Wait for `rate_limiter` before every request sent by the parsed
+    /// methods, including retries. Share the limiter between clients so
+    /// that they draw from the same budget. A request for which the
+    /// daily quota is spent fails with `ErrorKind::QuotaExhausted`.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> ApiClient<'a> {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Send a [Forecast API](https://darksky.net/dev/docs/forecast)
+    /// request, returns the corresponding Response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErrorKind::Http` if the request can't be sent. A
+    /// response with a non-success status is returned as it is.
+    pub async fn get_forecast<'b, T>(&self, request: T) -> error::Result<Response>
+        where T : Borrow<ForecastRequest<'b>> + Sized {
+        self.send(&request.borrow().into(), None, Uuid::new_v4()).await.map(Response::new)
+    }
+
+    /// Send a [Time Machine
+    /// API](https://darksky.net/dev/docs/time-machine) request,
+    /// returns the corresponding Response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as `get_forecast`.
+    pub async fn get_time_machine<'b, T>(&self, request: T) -> error::Result<Response>
+        where T : Borrow<TimeMachineRequest<'b>> + Sized {
+        self.send(&request.borrow().into(), None, Uuid::new_v4()).await.map(Response::new)
+    }
+
+    /// Send a [Forecast API](https://darksky.net/dev/docs/forecast)
+    /// request, returns the deserialized response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the API responds with a
+    /// non-success status, or the response can't be deserialized.
+    /// Retryable errors are retried according to the client's
+    /// `RetryPolicy`.
+    pub async fn get_forecast_parsed<'b, T>(&self, request: T) -> error::Result<ApiResponse>
+        where T : Borrow<ForecastRequest<'b>> + Sized {
+        self.execute(&request.borrow().into()).await.map(Sourced::into_inner)
+    }
+
+    /// Like `get_forecast_parsed`, but records where and when the
+    /// response was fetched.
+    pub async fn get_forecast_sourced<'b, T>(&self, request: T)
+        -> error::Result<Sourced<ApiResponse>> where T : Borrow<ForecastRequest<'b>> + Sized {
+        self.execute(&request.borrow().into()).await
+    }
+
+    /// Send a [Time Machine
+    /// API](https://darksky.net/dev/docs/time-machine) request,
+    /// returns the deserialized response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// `get_forecast_parsed`.
+    pub async fn get_time_machine_parsed<'b, T>(&self, request: T) -> error::Result<ApiResponse>
+        where T : Borrow<TimeMachineRequest<'b>> + Sized {
+        self.execute(&request.borrow().into()).await.map(Sourced::into_inner)
+    }
+
+    /// Like `get_time_machine_parsed`, but records where and when the
+    /// response was fetched.
+    pub async fn get_time_machine_sourced<'b, T>(&self, request: T)
+        -> error::Result<Sourced<ApiResponse>> where T : Borrow<TimeMachineRequest<'b>> + Sized {
+        self.execute(&request.borrow().into()).await
+    }
+
+    /// Fetch an hourly timeline for a location covering `window`
+    /// (seconds since the UNIX epoch), escalating as needed: first the
+    /// standard 48-hour forecast is fetched; if its hourly block doesn't
+    /// cover the window, the forecast is re-fetched extended to 168
+    /// hours; any part of the window still uncovered is filled in with
+    /// one Time Machine request per day. A window which ended before the
+    /// current hour is fetched with Time Machine requests alone. The
+    /// hourly points of every response are merged, in time order, into
+    /// the hourly block of the returned response.
+    ///
+    /// Every escalation is a separate billed API call, so at most
+    /// `max_escalations` are sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// `get_forecast_parsed`, or `ErrorKind::EscalationLimit` if
+    /// covering the window would take more than `max_escalations`
+    /// requests. The limit is checked before the requests are sent.
+    pub async fn get_forecast_window(
+        &self,
+        api_key: &str,
+        latitude: f64,
+        longitude: f64,
+        window: Range<i64>
+    ) -> error::Result<ApiResponse> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        // the hourly forecast starts at the current hour
+        let forecast_start = now - now.rem_euclid(SECONDS_PER_HOUR);
+
+        if window.start < window.end && window.end <= forecast_start {
+            self.check_escalations(time_machine_requests(slice::from_ref(&window)))?;
+
+            let (response, day_end) =
+                self.time_machine_day(api_key, latitude, longitude, window.start).await?;
+            let rest = day_end..window.end;
+
+            return self.fill_gaps(api_key, latitude, longitude, response, Some(rest), 1).await;
+        }
+
+        let standard = forecast_start..forecast_start + FORECAST_HOURS * SECONDS_PER_HOUR;
+        let extended = forecast_start..forecast_start + EXTENDED_HOURS * SECONDS_PER_HOUR;
+
+        if window.start < standard.start || window.end > standard.end {
+            self.check_escalations(1 + time_machine_requests(&gaps(&window, &extended)))?;
+        }
+
+        let request = ForecastRequestBuilder::new(api_key, latitude, longitude).build();
+        let response = self.get_forecast_parsed(request).await?;
+
+        if covers(&response, &window) {
+            return Ok(response);
+        }
+
+        let request = ForecastRequestBuilder::new(api_key, latitude, longitude)
+            .extend(ExtendBy::Hourly)
+            .build();
+        let response = self.get_forecast_parsed(request).await?;
+
+        if covers(&response, &window) {
+            return Ok(response);
+        }
+
+        let span = response.hourly.as_ref()
+            .and_then(DataBlock::span)
+            .unwrap_or(window.end..window.end);
+        let gaps = gaps(&window, &span);
+
+        self.check_escalations(1 + time_machine_requests(&gaps))?;
+
+        self.fill_gaps(api_key, latitude, longitude, response, gaps, 1).await
+    }
+
+    // Fill `gaps` in the hourly block of `response` with one Time Machine
+    // request per day, `escalations` requests having been sent already.
+    async fn fill_gaps(
+        &self,
+        api_key: &str,
+        latitude: f64,
+        longitude: f64,
+        mut response: ApiResponse,
+        gaps: impl IntoIterator<Item = Range<i64>>,
+        mut escalations: usize
+    ) -> error::Result<ApiResponse> {
+        for gap in gaps.into_iter().filter(|gap| gap.start < gap.end) {
+            let mut time = gap.start;
+
+            while time < gap.end {
+                // days needn't start on a UTC day boundary, so the
+                // estimate checked beforehand may be one short
+                escalations += 1;
+                self.check_escalations(escalations)?;
+
+                let (day, day_end) =
+                    self.time_machine_day(api_key, latitude, longitude, time).await?;
+
+                merge_hourly(&mut response, day);
+                time = day_end;
+            }
+        }
+
+        Ok(response)
+    }
+
+    // The hourly block of the day containing `time`, and when it ends.
+    async fn time_machine_day(&self, api_key: &str, latitude: f64, longitude: f64, time: i64)
+        -> error::Result<(ApiResponse, i64)> {
+        let request = TimeMachineRequestBuilder::new(api_key, latitude, longitude, time)
+            .exclude(TIME_MACHINE_EXCLUDE.iter())
+            .build();
+        let day = self.get_time_machine_parsed(request).await?;
+        let day_end = day.hourly.as_ref().and_then(DataBlock::span).map(|span| span.end);
+
+        // a day's hourly block runs from local midnight to midnight
+        Ok((day, day_end.filter(|end| *end > time).unwrap_or(time + SECONDS_PER_DAY)))
+    }
+
+    fn check_escalations(&self, needed: usize) -> error::Result<()> {
+        if needed > self.max_escalations {
+            return Err(ErrorKind::EscalationLimit { needed, limit: self.max_escalations }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fetch just the current conditions for a location. Every block
+    /// but `currently` is excluded, so the response is a fraction of a
+    /// full forecast's size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// `get_forecast_parsed`, or a parse error if the response has no
+    /// `currently` block.
+    pub async fn get_currently(&self, api_key: &str, latitude: f64, longitude: f64)
+        -> error::Result<CurrentlyDataPoint> {
+        let request = ForecastRequestBuilder::new(api_key, latitude, longitude)
+            .currently_only()
+            .build();
+
+        self.get_forecast_parsed(request).await.and_then(currently)
+    }
+
+    /// Fetch the daily forecast for the next `days` days for a location,
+    /// starting today. Every block but `daily` is excluded. The API
+    /// forecasts eight days including today.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// `get_forecast_parsed`, or a parse error if the response has fewer
+    /// than `days` daily points.
+    pub async fn get_daily(&self, api_key: &str, latitude: f64, longitude: f64, days: usize)
+        -> error::Result<Vec<DailyDataPoint>> {
+        let request = ForecastRequestBuilder::new(api_key, latitude, longitude)
+            .daily_only()
+            .build();
+
+        self.get_forecast_parsed(request).await.and_then(|response| daily(response, days))
+    }
+
+    async fn execute(&self, target: &RequestTarget<'_>) -> error::Result<Sourced<ApiResponse>> {
+        let mut attempt = 0;
+
+        loop {
+            match self.execute_once(target).await {
+                Err(e) if self.retry_policy.should_retry(&e, attempt) => {
+                    tokio::time::sleep(self.retry_policy.delay(&e, attempt)).await;
+                    attempt += 1;
+                },
+                result => return result
+            }
+        }
+    }
+
+    async fn execute_once(
+        &self,
+        target: &RequestTarget<'_>
+    ) -> error::Result<Sourced<ApiResponse>> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(target.api_key).await.map_err(ErrorKind::QuotaExhausted)?;
+        }
+
+        let request_id = Uuid::new_v4();
+
+        self.execute_request(target, request_id)
+            .instrument(request_span(target, request_id))
+            .await
+            .map_err(|error| {
+                tracing::debug!(%request_id, %error, "forecast request failed");
+                error.with_request_id(request_id)
+            })
+    }
+
+    async fn execute_request(
+        &self,
+        target: &RequestTarget<'_>,
+        request_id: Uuid
+    ) -> error::Result<Sourced<ApiResponse>> {
+        let accept_encoding = if self.gzip { "gzip" } else { "identity" };
+        let response = self.send(target, Some(accept_encoding), request_id).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        tracing::debug!(%status, "received forecast response");
+
+        let content_encoding = response.headers()
+            .get(CONTENT_ENCODING)
+            .map(|value| value.to_str().unwrap_or("").trim().to_ascii_lowercase());
+        let body = read_body(response, self.max_response_size).await?;
+        let body = decode_body(content_encoding.as_deref(), &body, self.max_response_size)?;
+
+        if !status.is_success() {
+            return Err(Error::from_http_response(status, &headers, &body));
+        }
+
+        let api_response: ApiResponse = ParseError::from_slice(&body)?;
+
+        if self.warnings_as_errors {
+            let warnings = api_response.warnings();
+
+            if !warnings.is_empty() {
+                return Err(ErrorKind::Degraded(warnings).into());
+            }
+        }
+
+        let url = self.url(target);
+        let mut sourced = Sourced::new(api_response, &url, target.api_key, fetched_at);
+        sourced.api_calls = headers.get(API_CALLS_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+
+        Ok(sourced)
+    }
+
+    async fn send(
+        &self,
+        target: &RequestTarget<'_>,
+        accept_encoding: Option<&str>,
+        request_id: Uuid
+    ) -> error::Result<reqwest::Response> {
+        if let Some(usage_tracker) = &self.usage_tracker {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            usage_tracker.record(target.api_key, target.latitude, target.longitude, now);
+        }
+
+        let request = self.prepare(target, accept_encoding, request_id).map_err(http::Error::new)?;
+        let response = match target.proxy {
+            Some(proxy) => self.proxy_client(proxy).map_err(http::Error::new)?.execute(request),
+            None => self.client.0.execute(request)
+        };
+
+        Ok(response.instrument(request_span(target, request_id)).await.map_err(http::Error::new)?)
+    }
+
+    // The URL `target` is sent to, on the base URL if one is set.
+    fn url(&self, target: &RequestTarget<'_>) -> Url {
+        let base_url = match &self.base_url {
+            Some(base_url) => base_url,
+            None => return target.url.clone()
+        };
+
+        let path = &target.url.as_str()[FORECAST_URL.len()..];
+        let url = format!("{}{}", base_url.as_str().trim_end_matches('/'), path);
+
+        Url::parse(&url).unwrap_or_else(|_| target.url.clone())
+    }
+
+    // The client used for requests sent through `proxy`, configured like
+    // this client's own if it was built by an ApiClientBuilder.
+    fn proxy_client(&self, proxy: &Url) -> reqwest::Result<reqwest::Client> {
+        let mut clients = self.proxy_clients.lock().unwrap_or_else(|p| p.into_inner());
+
+        if let Some(client) = clients.get(proxy) {
+            return Ok(client.clone());
+        }
+
+        let client = self.config.clone().proxy(proxy.clone()).build_client()?;
+        clients.insert(proxy.clone(), client.clone());
+
+        Ok(client)
+    }
+
+    fn prepare(
+        &self,
+        target: &RequestTarget<'_>,
+        accept_encoding: Option<&str>,
+        request_id: Uuid
+    ) -> reqwest::Result<Request> {
+        let mut request = self.client.0.get(self.url(target));
+
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.header(ACCEPT_ENCODING, accept_encoding);
+        }
+
+        if self.request_id_header {
+            let value = HeaderValue::from_str(&request_id.to_string()).unwrap();
+
+            request = request.header(REQUEST_ID_HEADER, value);
+        }
+
+        let mut request = request.build()?;
+
+        if let Some(signer) = &self.signer {
+            let url = request.url().clone();
+
+            signer.sign(&url, request.headers_mut());
+        }
+
+        Ok(request)
+    }
+}
+
+// The span covering one request. The URL isn't recorded, since it
+// contains the API key.
+fn request_span(target: &RequestTarget<'_>, request_id: Uuid) -> tracing::Span {
+    tracing::debug_span!(
+        "forecast_request",
+        %request_id,
+        latitude = target.latitude,
+        longitude = target.longitude
+    )
+}
+
+// Whether the hourly block of `response` covers `window`.
+fn covers(response: &ApiResponse, window: &Range<i64>) -> bool {
+    response.hourly.as_ref().is_some_and(|hourly| hourly.covers(window.clone()))
+}
+
+// The parts of `window` before and after `span`.
+fn gaps(window: &Range<i64>, span: &Range<i64>) -> Vec<Range<i64>> {
+    vec![window.start..span.start.min(window.end), span.end.max(window.start)..window.end]
+}
+
+// The number of Time Machine requests needed to fill `gaps`, assuming
+// days start on UTC day boundaries.
+fn time_machine_requests(gaps: &[Range<i64>]) -> usize {
+    gaps.iter()
+        .filter(|gap| gap.start < gap.end)
+        .map(|gap| {
+            let first = gap.start.div_euclid(SECONDS_PER_DAY);
+            let last = (gap.end - 1).div_euclid(SECONDS_PER_DAY);
+
+            (last - first + 1) as usize
+        })
+        .sum()
+}
+
+// Merge the hourly points of `other` into the hourly block of
+// `response`, keeping the points of `response` where both have a point
+// for the same time.
+fn merge_hourly(response: &mut ApiResponse, other: ApiResponse) {
+    let points = match other.hourly {
+        Some(hourly) => hourly.data,
+        None => return
+    };
+
+    let hourly = response.hourly.get_or_insert_with(|| {
+        DataBlock { data: Vec::new(), summary: None, icon: None }
+    });
+
+    hourly.data.extend(points);
+    hourly.data.sort_by_key(|point| point.time);
+    hourly.data.dedup_by_key(|point| point.time);
+}
+
+// Read a response body chunk by chunk, giving up as soon as it exceeds
+// `limit` bytes. A Content-Length over the limit fails before any of the
+// body is read.
+async fn read_body(mut response: reqwest::Response, limit: Option<usize>)
+    -> error::Result<Vec<u8>> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(response.bytes().await.map_err(http::Error::new)?.to_vec())
+    };
+
+    if response.content_length().is_some_and(|length| length > limit as u64) {
+        return Err(ErrorKind::ResponseTooLarge { limit }.into());
+    }
+
+    let mut body = Vec::new();
+
+    while let Some(chunk) = response.chunk().await.map_err(http::Error::new)? {
+        check_size(body.len() + chunk.len(), Some(limit))?;
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+fn check_size(size: usize, limit: Option<usize>) -> error::Result<()> {
+    match limit {
+        Some(limit) if size > limit => Err(ErrorKind::ResponseTooLarge { limit }.into()),
+        _ => Ok(())
+    }
+}
+
+// Decode a response body according to its Content-Encoding. A body which
+// the HTTP client already decompressed arrives without the header.
+// Decompression stops one byte past `limit` so that the size can be
+// checked without inflating the whole body.
+fn decode_body(
+    content_encoding: Option<&str>,
+    body: &[u8],
+    limit: Option<usize>
+) -> error::Result<Vec<u8>> {
+    match content_encoding {
+        None | Some("") | Some("identity") => {
+            check_size(body.len(), limit)?;
+
+            Ok(body.to_vec())
+        },
+        Some("gzip") | Some("x-gzip") => {
+            let mut decoded = Vec::new();
+            let max = limit.map_or(u64::MAX, |limit| limit as u64 + 1);
+
+            GzDecoder::new(body).take(max)
+                .read_to_end(&mut decoded)
+                .map_err(ErrorKind::Decompress)?;
+            check_size(decoded.len(), limit)?;
+
+            Ok(decoded)
+        },
+        Some(other) => Err(ErrorKind::Decompress(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported Content-Encoding: {}", other)
+        )).into())
+    }
+}
+
+impl ApiClient<'static> {
+    /// Construct a new ApiClient which owns `client`, so that it
+    /// doesn't need to be kept alive alongside the ApiClient. Cloning a
+    /// `Client` is cheap, so clients can still share a pool.
+    pub fn from_client(client: Client) -> ApiClient<'static> {
+        ApiClient::with_client(Cow::Owned(client))
+    }
+
+    /// Return the process-wide shared ApiClient, constructing it on
+    /// first use. API keys are given per request, so one client serves
+    /// every key. The client is configured from the environment:
+    ///
+    ///   * `FORECAST_TIMEOUT_SECS`: the request timeout in seconds
+    ///     (default: none).
+    ///   * `FORECAST_MAX_RETRIES`: how many times the parsed methods
+    ///     retry a failed request, waiting one second before the first
+    ///     retry (default: 0).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the client can't be constructed, in which case the next
+    /// call tries again.
+    pub fn shared() -> error::Result<Arc<ApiClient<'static>>> {
+        static SHARED: Mutex<Option<Arc<ApiClient<'static>>>> = Mutex::new(None);
+
+        let mut shared = SHARED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(client) = shared.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let mut builder = ApiClientBuilder::new();
+
+        if let Some(secs) = env_var::<u64>(TIMEOUT_SECS_VAR) {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+
+        let mut client = builder.build()?;
+
+        if let Some(max_retries) = env_var::<u32>(MAX_RETRIES_VAR) {
+            client = client.with_retry_policy(
+                RetryPolicy::new(max_retries, Duration::from_secs(SHARED_INITIAL_BACKOFF_SECS))
+            );
+        }
+
+        let client = Arc::new(client);
+        *shared = Some(client.clone());
+
+        Ok(client)
+    }
+}
+
+impl Default for ApiClient<'static> {
+    /// Construct an ApiClient which owns a default `Client`.
+    fn default() -> ApiClient<'static> {
+        ApiClient::from_client(Client::new())
+    }
+}
+
+// Read an environment variable, ignoring it if it's unset or malformed.
+fn env_var<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|value| value.trim().parse().ok())
+}
+
+/// Builder object used to construct an `ApiClient` which owns an HTTP
+/// `Client` tuned for talking to the API. Use it instead of
+/// `ApiClient::new` to control connection pooling.
+#[derive(Debug, Clone, Default)]
+pub struct ApiClientBuilder {
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_prior_knowledge: bool,
+    timeout: Option<Duration>,
+    proxy: Option<Url>,
+    resolve: Vec<(String, Vec<SocketAddr>)>,
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    root_certificates: Vec<Certificate>,
+    #[cfg(feature = "rustls-tls")]
+    pinned_certificates: Vec<[u8; 32]>
+}
+
+impl ApiClientBuilder {
+    /// Construct a new ApiClientBuilder with the HTTP client's default
+    /// pool settings.
+    pub fn new() -> ApiClientBuilder {
+        ApiClientBuilder::default()
+    }
+
+    /// Maximum number of idle connections to keep open to the API host.
+    /// Set to 0 to disable connection reuse.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> ApiClientBuilder {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle pooled connection is kept open.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> ApiClientBuilder {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keep-alive probes on open connections at this interval.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> ApiClientBuilder {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Speak HTTP/2 without negotiating it first, multiplexing
+    /// concurrent requests over a single connection.
+    pub fn http2_prior_knowledge(mut self, http2: bool) -> ApiClientBuilder {
+        self.http2_prior_knowledge = http2;
+        self
+    }
+
+    /// Fail requests which haven't completed within `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> ApiClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request through the proxy at `proxy`, instead of the
+    /// proxies given by the `HTTP_PROXY` and `HTTPS_PROXY` environment
+    /// variables. `http`, `https` and `socks5` proxy URLs are supported,
+    /// with credentials given in the URL. A `socks5` proxy's host name is
+    /// resolved when the client is built. A request built with its own
+    /// proxy overrides this one.
+    pub fn proxy(mut self, proxy: Url) -> ApiClientBuilder {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Connect to `addr` for requests to `domain` instead of looking it
+    /// up in DNS, e.g. to skip the lookup for `api.darksky.net` on every
+    /// new connection, or to reach a split-horizon or test deployment.
+    /// The host name is still used for TLS, so certificates are verified
+    /// as usual. A port of 0 means the URL's port.
+    pub fn resolve(self, domain: &str, addr: SocketAddr) -> ApiClientBuilder {
+        self.resolve_to_addrs(domain, &[addr])
+    }
+
+    /// Like `resolve`, but try each of `addrs` in turn. To pin the API
+    /// host to the addresses it resolves to now, pass the result of
+    /// `("api.darksky.net", 443).to_socket_addrs()`.
+    pub fn resolve_to_addrs(mut self, domain: &str, addrs: &[SocketAddr]) -> ApiClientBuilder {
+        self.resolve.push((domain.to_string(), addrs.to_vec()));
+        self
+    }
+
+    /// Trust `certificate` as a root in addition to the system's roots,
+    /// e.g. for a corporate TLS-intercepting proxy or a compatible
+    /// endpoint with a private CA. Requires a TLS backend feature.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> ApiClientBuilder {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Pin the API host's certificate by the SHA-256 fingerprint of its
+    /// DER encoding. Once any certificate is pinned, a server is trusted
+    /// if and only if it presents a pinned certificate: root
+    /// certificates, the host name and the validity period are no longer
+    /// checked, so rotate pins before the certificate is replaced. Only
+    /// available with the `rustls-tls` feature.
+    #[cfg(feature = "rustls-tls")]
+    pub fn pin_certificate_sha256(mut self, fingerprint: [u8; 32]) -> ApiClientBuilder {
+        self.pinned_certificates.push(fingerprint);
+        self
+    }
+
+    /// Build the ApiClient.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErrorKind::Http` if the underlying HTTP client can't be
+    /// constructed, e.g. because TLS can't be initialized.
+    pub fn build(self) -> error::Result<ApiClient<'static>> {
+        let client = self.clone().build_client().map_err(http::Error::new)?;
+
+        Ok(ApiClient::with_config(Cow::Owned(Client(client)), self))
+    }
+
+    fn build_client(self) -> reqwest::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+
+        for (domain, addrs) in &self.resolve {
+            builder = builder.resolve_to_addrs(domain, addrs);
+        }
+
+        #[cfg(feature = "rustls-tls")]
+        {
+            builder = builder.use_rustls_tls();
+
+            if !self.pinned_certificates.is_empty() {
+                let verifier = crate::tls::PinnedCertVerifier::new(self.pinned_certificates);
+
+                builder = builder.use_preconfigured_tls(
+                    verifier.client_config(self.http2_prior_knowledge)
+                );
+            }
+        }
+
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate.0);
+        }
+
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        builder.build()
+    }
+}
+
+fn currently(response: ApiResponse) -> error::Result<CurrentlyDataPoint> {
+    response.currently
+        .ok_or_else(|| Error::from(<serde_json::Error as serde::de::Error>::missing_field("currently")))
+}
+
+fn daily(response: ApiResponse, days: usize) -> error::Result<Vec<DailyDataPoint>> {
+    let mut data = response.daily.map(|daily| daily.data).unwrap_or_default();
+
+    if data.len() < days {
+        let expected = format!("{} daily data points", days);
+
+        let error: serde_json::Error = serde::de::Error::invalid_length(data.len(), &expected.as_str());
+
+        return Err(error.into());
+    }
+
+    data.truncate(days);
+
+    Ok(data)
+}
+
+// one-shot convenience functions
+
+/// Fetch the forecast for a location using a default client. For more
+/// than the occasional call, construct an `ApiClient` and reuse it.
+pub async fn get_current(api_key: &str, latitude: f64, longitude: f64)
+    -> error::Result<ApiResponse> {
+    let client = Client::new();
+    let request = ForecastRequestBuilder::new(api_key, latitude, longitude).build();
+
+    ApiClient::new(&client).get_forecast_parsed(request).await
+}
+
+/// Fetch the observed or forecast weather for a location at `time`
+/// using a default client. `time` is anything `TimeMachineRequestBuilder`
+/// accepts, e.g. seconds since the UNIX epoch, negative before 1970.
+pub async fn get_on(api_key: &str, latitude: f64, longitude: f64, time: impl Into<Time>)
+    -> error::Result<ApiResponse> {
+    let client = Client::new();
+    let request = TimeMachineRequestBuilder::new(api_key, latitude, longitude, time).build();
+
+    ApiClient::new(&client).get_time_machine_parsed(request).await
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiClient, ApiClientBuilder, currently, daily, decode_body, merge_hourly,
+                time_machine_requests, SECONDS_PER_DAY, SECONDS_PER_HOUR};
+
+    use crate::http::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING};
+    use crate::http::{Client, Url};
+    use crate::testing;
+    use crate::{ApiResponse, ErrorKind, ForecastRequestBuilder, RequestSigner, Units};
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::slice;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use uuid::Uuid;
+
+    // constants
+
+    const LAT: f64 = 6.66;
+    const LONG: f64 = 66.6;
+
+    static API_KEY: &str = "some_api_key";
+
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    static TEST_CERTIFICATE: &[u8] = include_bytes!("../resources/tests/certificate.pem");
+
+    #[test]
+    fn test_decode_body() {
+        let body = b"{\"latitude\":1.0}";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(decode_body(Some("gzip"), &compressed, None).unwrap(), body.to_vec());
+        assert_eq!(decode_body(None, body, None).unwrap(), body.to_vec());
+        assert_eq!(decode_body(Some("identity"), body, None).unwrap(), body.to_vec());
+        assert!(matches!(
+            decode_body(Some("gzip"), body, None).unwrap_err().kind(),
+            ErrorKind::Decompress(_)
+        ));
+        assert!(matches!(
+            decode_body(Some("br"), body, None).unwrap_err().kind(),
+            ErrorKind::Decompress(_)
+        ));
+    }
+
+    #[test]
+    fn test_decode_body_limit() {
+        let body = vec![b' '; 10_000];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert!(compressed.len() < 1_000);
+        assert!(matches!(
+            decode_body(Some("gzip"), &compressed, Some(1_000)).unwrap_err().kind(),
+            ErrorKind::ResponseTooLarge { limit: 1_000 }
+        ));
+        assert!(matches!(
+            decode_body(None, &body, Some(9_999)).unwrap_err().kind(),
+            ErrorKind::ResponseTooLarge { limit: 9_999 }
+        ));
+        assert_eq!(decode_body(Some("gzip"), &compressed, Some(10_000)).unwrap(), body);
+        assert_eq!(decode_body(None, &body, Some(10_000)).unwrap(), body);
+    }
+
+    #[test]
+    fn test_api_client_builder() {
+        ApiClientBuilder::new()
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(30))
+            .http2_prior_knowledge(true)
+            .build()
+            .unwrap()
+            .gzip(false);
+    }
+
+    #[test]
+    fn test_proxy_clients() {
+        let socks = Url::parse("socks5://127.0.0.1:1080").unwrap();
+        let http = Url::parse("http://127.0.0.1:3128").unwrap();
+        let api_client = ApiClientBuilder::new()
+            .proxy(http.clone())
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+
+        api_client.proxy_client(&socks).unwrap();
+        api_client.proxy_client(&socks).unwrap();
+        api_client.proxy_client(&http).unwrap();
+
+        assert_eq!(api_client.proxy_clients.lock().unwrap().len(), 2);
+        assert_eq!(api_client.config.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    fn test_api_client_builder_tls() {
+        let certificate = crate::http::Certificate::from_pem(TEST_CERTIFICATE).unwrap();
+        let builder = ApiClientBuilder::new().add_root_certificate(certificate);
+
+        #[cfg(feature = "rustls-tls")]
+        let builder = builder.pin_certificate_sha256([0; 32]);
+
+        builder.build().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+
+            stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").unwrap();
+
+            String::from_utf8_lossy(&request[..read]).into_owned()
+        });
+
+        let api_client = ApiClientBuilder::new()
+            .resolve("weather.invalid", addr)
+            .build()
+            .unwrap();
+        let url = format!("http://weather.invalid:{}/forecast", addr.port());
+        let response = api_client.client.0.get(url).send().await.unwrap();
+
+        assert!(response.status().is_success());
+        assert!(server.join().unwrap().contains("host: weather.invalid"));
+    }
+
+    #[derive(Debug)]
+    struct QuerySigner;
+
+    impl RequestSigner for QuerySigner {
+        fn sign(&self, url: &Url, headers: &mut HeaderMap) {
+            let signature = format!("signed:{}", url.query().unwrap_or(""));
+            headers.insert("x-signature", HeaderValue::from_str(&signature).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_signer() {
+        let client = Client::new();
+        let api_client = ApiClient::new(&client).with_signer(Arc::new(QuerySigner));
+        let forecast_request = ForecastRequestBuilder::new(API_KEY, LAT, LONG)
+            .units(Units::SI)
+            .build();
+
+        let request = api_client
+            .prepare(&(&forecast_request).into(), Some("gzip"), Uuid::new_v4())
+            .unwrap();
+
+        assert_eq!(request.headers()["x-signature"], "signed:units=si");
+        assert_eq!(request.headers()[ACCEPT_ENCODING], "gzip");
+        assert!(!request.headers().contains_key("x-request-id"));
+    }
+
+    #[test]
+    fn test_base_url() {
+        let base_url = Url::parse("https://api.pirateweather.net/forecast/").unwrap();
+        let api_client = ApiClient::default().with_base_url(base_url);
+        let forecast_request = ForecastRequestBuilder::new(API_KEY, LAT, LONG)
+            .units(Units::SI)
+            .build();
+
+        let request = api_client.prepare(&(&forecast_request).into(), None, Uuid::new_v4()).unwrap();
+        let url = request.url().as_str();
+
+        assert!(url.starts_with("https://api.pirateweather.net/forecast/some_api_key/6.66"));
+        assert!(url.ends_with("?units=si"));
+    }
+
+    #[test]
+    fn test_currently() {
+        let response = testing::response(serde_json::json!({
+            "currently": {"time": 0, "temperature": 1.5}
+        }));
+
+        assert_eq!(currently(response.clone()).unwrap().temperature, Some(1.5));
+
+        let mut response = response;
+        response.currently = None;
+
+        assert!(currently(response).unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_daily() {
+        let data: Vec<_> = (0..8).map(|day| serde_json::json!({"time": day * 86_400})).collect();
+        let response = testing::response(serde_json::json!({"daily": {"data": data}}));
+
+        let days = daily(response.clone(), 7).unwrap();
+
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[6].time, 6 * 86_400);
+        assert_eq!(daily(response.clone(), 8).unwrap().len(), 8);
+        assert!(daily(response, 9).unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_request_id_header() {
+        let api_client = ApiClient::default().request_id_header(true);
+        let forecast_request = ForecastRequestBuilder::new(API_KEY, LAT, LONG).build();
+        let request_id = Uuid::new_v4();
+
+        let request = api_client.prepare(&(&forecast_request).into(), None, request_id).unwrap();
+
+        assert_eq!(request.headers()["x-request-id"], request_id.to_string().as_str());
+    }
+
+    #[test]
+    fn test_merge_hourly() {
+        let response = |times: &[i64], temperature: f64| -> ApiResponse {
+            let data: Vec<_> = times.iter()
+                .map(|t| serde_json::json!({"time": t, "temperature": temperature}))
+                .collect();
+
+            testing::response(serde_json::json!({"hourly": {"data": data}}))
+        };
+
+        let mut forecast = response(&[7200, 10800], 1.0);
+
+        merge_hourly(&mut forecast, response(&[0, 3600, 7200], 2.0));
+
+        let hourly = forecast.hourly.unwrap();
+        let merged: Vec<_> = hourly.iter().map(|p| (p.time, p.temperature.unwrap())).collect();
+
+        assert_eq!(merged, vec![(0, 2.0), (3600, 2.0), (7200, 1.0), (10800, 1.0)]);
+    }
+
+    #[test]
+    fn test_time_machine_requests() {
+        assert_eq!(time_machine_requests(&[]), 0);
+        assert_eq!(time_machine_requests(&[0..SECONDS_PER_DAY, 5..5]), 1);
+        assert_eq!(time_machine_requests(slice::from_ref(&(-1..1))), 2);
+        assert_eq!(time_machine_requests(slice::from_ref(&(0..365 * SECONDS_PER_DAY))), 365);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_window_limit() {
+        // nothing listens on port 1, so a request which was sent would
+        // fail with an HTTP error instead
+        let api_client = ApiClient::default()
+            .with_base_url(Url::parse("http://127.0.0.1:1/forecast").unwrap())
+            .max_escalations(3);
+
+        let past = -10 * SECONDS_PER_DAY..0;
+        let error = api_client.get_forecast_window(API_KEY, LAT, LONG, past).await.unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::EscalationLimit { needed: 10, limit: 3 }));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let future = now..now + 30 * SECONDS_PER_DAY;
+        let error = api_client.get_forecast_window(API_KEY, LAT, LONG, future).await.unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::EscalationLimit { limit: 3, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_forecast_window_past() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let forecasts = Arc::new(AtomicUsize::new(0));
+
+        // answers every Time Machine request with the hours of its UTC day
+        let (sent, fetched) = (requests.clone(), forecasts.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 1024];
+                let read = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).into_owned();
+                let path = request.split(' ').nth(1).unwrap().split('?').next().unwrap();
+                let time = path.rsplit('/').next().unwrap().split(',').nth(2);
+
+                sent.fetch_add(1, Ordering::SeqCst);
+
+                let day = match time {
+                    Some(time) => time.parse::<i64>().unwrap().div_euclid(SECONDS_PER_DAY),
+                    None => {
+                        fetched.fetch_add(1, Ordering::SeqCst);
+                        0
+                    }
+                };
+                let data: Vec<_> = (0..24)
+                    .map(|hour| serde_json::json!({
+                        "time": day * SECONDS_PER_DAY + hour * SECONDS_PER_HOUR
+                    }))
+                    .collect();
+                let body = testing::response_json(serde_json::json!({"hourly": {"data": data}}))
+                    .to_string();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                ).unwrap();
+            }
+        });
+
+        let api_client = ApiClient::default()
+            .with_base_url(Url::parse(&format!("http://{}/forecast", addr)).unwrap())
+            .max_escalations(2);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let start = (now.div_euclid(SECONDS_PER_DAY) - 10) * SECONDS_PER_DAY + SECONDS_PER_HOUR;
+        let window = start..start + SECONDS_PER_DAY;
+
+        let response = api_client.get_forecast_window(API_KEY, LAT, LONG, window.clone())
+            .await
+            .unwrap();
+
+        assert!(response.hourly.unwrap().covers(window));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(forecasts.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_shared() {
+        let first = ApiClient::shared().unwrap();
+        let second = ApiClient::shared().unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_owned_client() {
+        struct Service {
+            api_client: ApiClient<'static>
+        }
+
+        let service = Service { api_client: ApiClient::from_client(Client::new()).gzip(false) };
+
+        assert!(!service.api_client.gzip);
+        assert!(ApiClient::default().gzip);
+    }
+}
@@ -0,0 +1,272 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! A memory-efficient representation of `DataPoint`.
+//!
+//! `CompactDataPoint` stores measurements as `f32` and tracks which
+//! fields are present in a bitset rather than in 46 separate `Option`s,
+//! using about a third of the memory of a `DataPoint`. It is intended
+//! for holding long histories of hourly data in memory.
+//!
+//! Measurements keep only `f32` precision, about 7 significant digits.
+//! Converting a `DataPoint` to a `CompactDataPoint` and back returns the
+//! same values for anything the API reports, which has at most 6
+//! significant digits, e.g. `1010.34` or `0.0089`; values with more
+//! digits come back rounded to 7 significant digits.
+
+use crate::{DataPoint, Icon, PrecipType};
+
+// constants
+
+// the decimal digits an f32 holds, to which measurements are rounded
+const SIGNIFICANT_DIGITS: i32 = 7;
+
+macro_rules! compact_fields {
+    (floats: [$($float:ident),*], times: [$($time:ident),*]) => {
+        #[allow(non_camel_case_types)]
+        enum FloatField { $($float),* }
+
+        #[allow(non_camel_case_types)]
+        enum TimeField { $($time),* }
+
+        const FLOAT_FIELDS: usize = [$(FloatField::$float),*].len();
+        const TIME_FIELDS: usize = [$(TimeField::$time),*].len();
+
+        // every field needs a bit of `CompactDataPoint::present`
+        const _: () = assert!(FLOAT_FIELDS + TIME_FIELDS <= u64::BITS as usize);
+
+        impl CompactDataPoint {
+            $(
+                #[doc = concat!("The `", stringify!($float), "` field of the `DataPoint`.")]
+                pub fn $float(&self) -> Option<f64> {
+                    self.float(FloatField::$float as usize)
+                }
+            )*
+
+            $(
+                #[doc = concat!("The `", stringify!($time), "` field of the `DataPoint`.")]
+                pub fn $time(&self) -> Option<i64> {
+                    self.timestamp(TimeField::$time as usize)
+                }
+            )*
+
+            /// Convert back to a `DataPoint`.
+            #[allow(deprecated)]
+            pub fn to_data_point(&self) -> DataPoint {
+                DataPoint {
+                    $($float: self.$float(),)*
+                    $($time: self.$time(),)*
+                    icon: self.icon.clone(),
+                    precip_type: self.precip_type.clone(),
+                    summary: self.summary.as_ref().map(|s| s.to_string()),
+                    time: self.time
+                }
+            }
+        }
+
+        impl From<&DataPoint> for CompactDataPoint {
+            #[allow(deprecated)]
+            fn from(point: &DataPoint) -> CompactDataPoint {
+                let mut compact = CompactDataPoint {
+                    time: point.time,
+                    present: 0,
+                    floats: [0.0; FLOAT_FIELDS],
+                    times: [0; TIME_FIELDS],
+                    icon: point.icon.clone(),
+                    precip_type: point.precip_type.clone(),
+                    summary: point.summary.as_ref().map(|s| s.clone().into_boxed_str())
+                };
+
+                $(compact.set_float(FloatField::$float as usize, point.$float);)*
+                $(compact.set_timestamp(TimeField::$time as usize, point.$time);)*
+
+                compact
+            }
+        }
+    }
+}
+
+compact_fields! {
+    floats: [
+        apparent_temperature, apparent_temperature_high, apparent_temperature_low,
+        apparent_temperature_max, apparent_temperature_min, cape, cloud_cover, dew_point,
+        humidity, moon_phase, nearest_storm_bearing, nearest_storm_distance, ozone,
+        precip_accumulation, precip_intensity, precip_intensity_max, precip_probability,
+        pressure, swell_direction, swell_height, swell_period, temperature, temperature_high,
+        temperature_low, temperature_max, temperature_min, uv_index, visibility, wave_height,
+        wave_period, wind_bearing, wind_gust, wind_speed
+    ],
+    times: [
+        apparent_temperature_high_time, apparent_temperature_low_time,
+        apparent_temperature_max_time, apparent_temperature_min_time,
+        precip_intensity_max_time, sunrise_time, sunset_time, temperature_high_time,
+        temperature_low_time, temperature_max_time, temperature_min_time, uv_index_time,
+        wind_gust_time
+    ]
+}
+
+/// A `DataPoint` stored in roughly a third of the memory. Each field of
+/// `DataPoint` is available through the method of the same name.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CompactDataPoint {
+    time: i64,
+    present: u64,
+    floats: [f32; FLOAT_FIELDS],
+    times: [i64; TIME_FIELDS],
+    icon: Option<Icon>,
+    precip_type: Option<PrecipType>,
+    summary: Option<Box<str>>
+}
+
+impl CompactDataPoint {
+    /// The time at which this data point begins.
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+
+    /// A machine-readable summary of this data point.
+    pub fn icon(&self) -> Option<&Icon> {
+        self.icon.as_ref()
+    }
+
+    /// The type of precipitation occurring at this time.
+    pub fn precip_type(&self) -> Option<&PrecipType> {
+        self.precip_type.as_ref()
+    }
+
+    /// A human-readable summary of this data point.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    fn float(&self, index: usize) -> Option<f64> {
+        if self.present & (1 << index) == 0 {
+            return None;
+        }
+
+        Some(round_significant(self.floats[index] as f64))
+    }
+
+    fn set_float(&mut self, index: usize, value: Option<f64>) {
+        if let Some(value) = value {
+            self.present |= 1 << index;
+            self.floats[index] = value as f32;
+        }
+    }
+
+    fn timestamp(&self, index: usize) -> Option<i64> {
+        if self.present & (1 << (FLOAT_FIELDS + index)) == 0 {
+            return None;
+        }
+
+        Some(self.times[index])
+    }
+
+    fn set_timestamp(&mut self, index: usize, value: Option<i64>) {
+        if let Some(value) = value {
+            self.present |= 1 << (FLOAT_FIELDS + index);
+            self.times[index] = value;
+        }
+    }
+}
+
+// Round `value` to SIGNIFICANT_DIGITS, so that e.g. 0.1f32 widens to 0.1
+// rather than 0.10000000149011612.
+fn round_significant(value: f64) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+
+    let exponent = SIGNIFICANT_DIGITS - 1 - value.abs().log10().floor() as i32;
+
+    if exponent >= 0 {
+        let scale = 10f64.powi(exponent);
+        (value * scale).round() / scale
+    } else {
+        let scale = 10f64.powi(-exponent);
+        (value / scale).round() * scale
+    }
+}
+
+impl From<DataPoint> for CompactDataPoint {
+    fn from(point: DataPoint) -> CompactDataPoint {
+        CompactDataPoint::from(&point)
+    }
+}
+
+impl From<CompactDataPoint> for DataPoint {
+    fn from(compact: CompactDataPoint) -> DataPoint {
+        compact.to_data_point()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{round_significant, CompactDataPoint};
+
+    use crate::{DataPoint, Icon};
+
+    use std::mem::size_of;
+
+    #[test]
+    fn test_round_trip() {
+        let point: DataPoint = serde_json::from_value(serde_json::json!({
+            "time": 1509993277,
+            "summary": "Drizzle",
+            "icon": "rain",
+            "precipIntensity": 0.0089,
+            "precipProbability": 0.9,
+            "precipType": "rain",
+            "temperature": 66.1,
+            "apparentTemperature": 66.31,
+            "dewPoint": 60.77,
+            "humidity": 0.83,
+            "pressure": 1010.34,
+            "windSpeed": 5.59,
+            "windBearing": 246,
+            "cloudCover": 0.7,
+            "uvIndex": 1,
+            "visibility": 9.84,
+            "ozone": 267.44,
+            "sunriseTime": 1509967519,
+            "temperatureHighTime": 1510002000
+        })).unwrap();
+
+        let compact = CompactDataPoint::from(&point);
+
+        assert_eq!(compact.temperature(), Some(66.1));
+        assert_eq!(compact.sunrise_time(), Some(1509967519));
+        assert_eq!(compact.wind_gust(), None);
+        assert_eq!(compact.sunset_time(), None);
+        assert_eq!(compact.icon(), Some(&Icon::Rain));
+        assert_eq!(compact.to_data_point(), point);
+    }
+
+    #[test]
+    fn test_round_significant() {
+        assert_eq!(round_significant(0.1f32 as f64), 0.1);
+        assert_eq!(round_significant(1010.34f32 as f64), 1010.34);
+        assert_eq!(round_significant(-0.0089f32 as f64), -0.0089);
+        assert_eq!(round_significant(123_456_789.0), 123_456_800.0);
+        assert_eq!(round_significant(0.0), 0.0);
+        assert!(round_significant(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_size() {
+        assert!(size_of::<CompactDataPoint>() * 2 < size_of::<DataPoint>());
+    }
+}
@@ -0,0 +1,64 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Conversions of response values into SI units, for calculations which
+// need physical quantities whatever units the response was requested
+// in. Pressure is in hectopascals (millibars) in every unit system.
+
+use crate::{ApiResponse, Units};
+
+const MPS_PER_MPH: f64 = 0.447_04;
+const MPS_PER_KPH: f64 = 1.0 / 3.6;
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+const CENTIMETERS_PER_INCH: f64 = 2.54;
+
+/// The units of `response`'s values, which are US units unless the
+/// response's flags say otherwise.
+pub(crate) fn response_units(response: &ApiResponse) -> &Units {
+    response.flags.as_ref().map_or(&Units::Imperial, |flags| &flags.units)
+}
+
+/// A temperature in degrees Celsius.
+pub(crate) fn celsius(temperature: f64, units: &Units) -> f64 {
+    match units {
+        Units::Imperial => (temperature - 32.0) * 5.0 / 9.0,
+        _ => temperature
+    }
+}
+
+/// A speed in meters per second.
+pub(crate) fn meters_per_second(speed: f64, units: &Units) -> f64 {
+    match units {
+        Units::SI => speed,
+        Units::CA => speed * MPS_PER_KPH,
+        _ => speed * MPS_PER_MPH
+    }
+}
+
+/// A precipitation intensity in millimeters per hour, or an accumulation
+/// in millimeters.
+pub(crate) fn millimeters(precipitation: f64, units: &Units) -> f64 {
+    match units {
+        Units::Imperial => precipitation * MILLIMETERS_PER_INCH,
+        _ => precipitation
+    }
+}
+
+/// A snow accumulation in centimeters.
+pub(crate) fn centimeters(accumulation: f64, units: &Units) -> f64 {
+    match units {
+        Units::Imperial => accumulation * CENTIMETERS_PER_INCH,
+        _ => accumulation
+    }
+}
@@ -0,0 +1,166 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Minimal calendar arithmetic for the UNIX timestamps used throughout
+// the API, so that renderers don't need a date/time dependency.
+
+use alloc::format;
+use alloc::string::String;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A proleptic Gregorian calendar date and time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CivilDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32
+}
+
+impl CivilDateTime {
+    /// Convert seconds since the UNIX epoch into a calendar date and
+    /// time, `offset_seconds` east of UTC.
+    pub fn from_unix(time: i64, offset_seconds: i64) -> CivilDateTime {
+        let local = time + offset_seconds;
+        let days = local.div_euclid(SECONDS_PER_DAY);
+        let seconds = local.rem_euclid(SECONDS_PER_DAY);
+
+        let (year, month, day) = civil_from_days(days);
+
+        CivilDateTime {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600) as u32,
+            minute: (seconds % 3600 / 60) as u32,
+            second: (seconds % 60) as u32
+        }
+    }
+}
+
+/// Format seconds since the UNIX epoch as an RFC 3339 timestamp,
+/// expressed in local time `offset_seconds` east of UTC.
+pub(crate) fn to_rfc3339(time: i64, offset_seconds: i64) -> String {
+    let t = CivilDateTime::from_unix(time, offset_seconds);
+    let sign = if offset_seconds < 0 { '-' } else { '+' };
+    let offset_minutes = offset_seconds.abs() / 60;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        t.year, t.month, t.day, t.hour, t.minute, t.second,
+        sign, offset_minutes / 60, offset_minutes % 60
+    )
+}
+
+/// Parse an HTTP date in the preferred IMF-fixdate format, e.g.
+/// `Wed, 21 Oct 2015 07:28:00 GMT`, into seconds since the UNIX epoch.
+#[cfg(feature = "client")]
+pub(crate) fn parse_http_date(date: &str) -> Option<i64> {
+    static MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"
+    ];
+
+    let parts: Vec<&str> = date.split_whitespace().collect();
+
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+
+    let day: u32 = parts[1].parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == parts[2])? as u32 + 1;
+    let year: i64 = parts[3].parse().ok()?;
+    let time: Vec<i64> = parts[4].split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+
+    if time.len() != 3 || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let seconds = time[0] * 3600 + time[1] * 60 + time[2];
+
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY + seconds)
+}
+
+// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+#[cfg(feature = "std")]
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{to_rfc3339, CivilDateTime};
+
+    #[test]
+    fn test_from_unix() {
+        assert_eq!(
+            CivilDateTime::from_unix(1_516_579_200 + 3_723, 0),
+            CivilDateTime { year: 2018, month: 1, day: 22, hour: 1, minute: 2, second: 3 }
+        );
+
+        assert_eq!(
+            CivilDateTime::from_unix(1_516_579_200, -5 * 3600),
+            CivilDateTime { year: 2018, month: 1, day: 21, hour: 19, minute: 0, second: 0 }
+        );
+
+        assert_eq!(
+            CivilDateTime::from_unix(-1, 0),
+            CivilDateTime { year: 1969, month: 12, day: 31, hour: 23, minute: 59, second: 59 }
+        );
+    }
+
+    #[test]
+    fn test_to_rfc3339() {
+        assert_eq!(to_rfc3339(1_516_579_200, 0), "2018-01-22T00:00:00+00:00");
+        assert_eq!(to_rfc3339(1_516_579_200, -5 * 3600), "2018-01-21T19:00:00-05:00");
+        assert_eq!(to_rfc3339(1_516_579_200, 5 * 3600 + 1800), "2018-01-22T05:30:00+05:30");
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_parse_http_date() {
+        use super::parse_http_date;
+
+        assert_eq!(parse_http_date("Mon, 22 Jan 2018 01:02:03 GMT"), Some(1_516_579_200 + 3_723));
+        assert_eq!(parse_http_date("Wed, 31 Dec 1969 23:59:59 GMT"), Some(-1));
+        assert_eq!(parse_http_date("Monday, 22-Jan-18 01:02:03 GMT"), None);
+        assert_eq!(parse_http_date("120"), None);
+    }
+}
@@ -0,0 +1,631 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The error type returned by the parsed `ApiClient` methods.
+//!
+//! An `Error` is an `ErrorKind` saying what went wrong, with the ID of
+//! the request it occurred in if it occurred once a request was under
+//! way. Match on `Error::kind()` to tell errors apart:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use forecast::{Error, ErrorKind};
+//!
+//! fn describe(error: &Error) -> String {
+//!     match error.kind() {
+//!         ErrorKind::BadRequest { message, .. } => format!("fix the request: {}", message),
+//!         ErrorKind::RateLimited { retry_after } => format!("back off for {:?}", retry_after),
+//!         _ => error.to_string()
+//!     }
+//! }
+//!
+//! let error = Error::from(ErrorKind::RateLimited { retry_after: Some(Duration::from_secs(30)) });
+//!
+//! assert_eq!(describe(&error), "back off for Some(30s)");
+//! assert_eq!(error.retry_after(), Some(Duration::from_secs(30)));
+//! ```
+
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+
+use uuid::Uuid;
+
+use crate::datetime;
+use crate::http::header::{HeaderMap, RETRY_AFTER};
+use crate::http::{self, StatusCode};
+use crate::rate_limit::QuotaExhausted;
+use crate::Warning;
+
+// constants
+
+const SNIPPET_CHARS: usize = 80;
+
+/// A `Result` alias where the `Err` case is `forecast::Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors which can occur while requesting and parsing an API response.
+/// See the module documentation.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    request_id: Option<Uuid>
+}
+
+/// What went wrong, as reported by `Error::kind()`.
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// The request couldn't be sent, or the response body couldn't be
+    /// read.
+    Http(http::Error),
+
+    /// The API responded with a non-success status.
+    Status(StatusCode),
+
+    /// The API responded with `429 Too Many Requests`. `retry_after` is
+    /// how long the API asked us to wait before sending another request,
+    /// taken from the `Retry-After` header if one was present.
+    RateLimited {
+        retry_after: Option<Duration>
+    },
+
+    /// The API rejected the request as invalid, e.g. because the
+    /// location or time is malformed. `message` is the explanation
+    /// given by the API.
+    BadRequest {
+        code: u16,
+        message: String
+    },
+
+    /// The response body couldn't be deserialized. The `ParseError`
+    /// says where in the body deserialization failed.
+    Parse(ParseError),
+
+    /// The response body couldn't be decompressed, or was compressed
+    /// with an unsupported encoding.
+    Decompress(io::Error),
+
+    /// The response carried warnings indicating that its data is
+    /// degraded. Only returned when the client is configured with
+    /// `warnings_as_errors`.
+    Degraded(Vec<Warning>),
+
+    /// The response body, after decompression, exceeded the limit of
+    /// `limit` bytes set with `ApiClient::max_response_size`. Reading
+    /// stops as soon as the limit is passed.
+    ResponseTooLarge {
+        limit: usize
+    },
+
+    /// The client's `RateLimiter` has no daily quota left for the key,
+    /// so the request wasn't sent.
+    QuotaExhausted(QuotaExhausted),
+
+    /// `ApiClient::get_forecast_window` needs at least `needed` billed
+    /// requests beyond the standard forecast to cover the window, more
+    /// than the client's `max_escalations` of `limit`, so they weren't
+    /// sent.
+    EscalationLimit {
+        needed: usize,
+        limit: usize
+    }
+}
+
+/// A deserialization error, with the JSON path of the value which
+/// couldn't be deserialized and a snippet of the payload around it, e.g.
+///
+/// ```text
+/// at hourly.data[1].time: invalid type: string "noon", expected i64 at line 1 column 103 (near `…{"time":0},{"time":"noon","summary":"Dry"}]}}`)
+/// ```
+#[derive(Debug)]
+pub struct ParseError {
+    path: String,
+    snippet: String,
+    error: serde_json::Error
+}
+
+impl ParseError {
+    /// Deserialize a `T` from `body`, recording where deserialization
+    /// failed if it does.
+    pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> std::result::Result<T, ParseError> {
+        let deserializer = &mut serde_json::Deserializer::from_slice(body);
+
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            let error = e.into_inner();
+            let snippet = snippet(body, error.line(), error.column());
+
+            ParseError { path, snippet, error }
+        })
+    }
+
+    /// The path of the value which couldn't be deserialized, e.g.
+    /// `hourly.data[3].time`, or `.` for the payload as a whole. Empty if
+    /// the path isn't known.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Up to 80 characters of the payload around where deserialization
+    /// failed, with `…` marking where it was cut off. Empty if the
+    /// payload isn't known.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// The underlying `serde_json` error, which has the line and column
+    /// of the failure.
+    pub fn inner(&self) -> &serde_json::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() && self.path != "." {
+            write!(f, "at {}: ", self.path)?;
+        }
+
+        write!(f, "{}", self.error)?;
+
+        if !self.snippet.is_empty() {
+            write!(f, " (near `{}`)", self.snippet)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(error: serde_json::Error) -> ParseError {
+        ParseError { path: String::new(), snippet: String::new(), error }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: u16,
+    error: String
+}
+
+impl Error {
+    /// Construct the error corresponding to a non-success response with
+    /// the given `status` and `body`. A 400 response whose body has the
+    /// form `{"code":400,"error":"..."}` becomes a `BadRequest`, a 429
+    /// response becomes `RateLimited`, and any other response becomes a
+    /// `Status`.
+    pub fn from_response(status: StatusCode, body: &[u8]) -> Error {
+        if status == StatusCode::BAD_REQUEST {
+            if let Ok(body) = serde_json::from_slice::<ErrorBody>(body) {
+                return ErrorKind::BadRequest { code: body.code, message: body.error }.into();
+            }
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return ErrorKind::RateLimited { retry_after: None }.into();
+        }
+
+        ErrorKind::Status(status).into()
+    }
+
+    /// Like `from_response`, but a `RateLimited` error also carries the
+    /// delay requested by the response's `Retry-After` header, which may
+    /// be given either in seconds or as an HTTP date.
+    pub fn from_http_response(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Error {
+        let mut error = Error::from_response(status, body);
+
+        if let ErrorKind::RateLimited { retry_after } = &mut error.kind {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+
+            *retry_after = headers.get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, now));
+        }
+
+        error
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// What went wrong, without the request ID.
+    pub fn into_kind(self) -> ErrorKind {
+        self.kind
+    }
+
+    /// How long the API asked us to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.kind {
+            ErrorKind::RateLimited { retry_after } => retry_after,
+            _ => None
+        }
+    }
+
+    /// The ID of the request which failed, which is also sent as the
+    /// `X-Request-Id` header if the client is configured to, so that the
+    /// failure can be found in logs. Set by the parsed `ApiClient`
+    /// methods for every error which occurs once a request is under way.
+    pub fn request_id(&self) -> Option<Uuid> {
+        self.request_id
+    }
+
+    // Record the request the error occurred in, unless it already has one.
+    pub(crate) fn with_request_id(mut self, request_id: Uuid) -> Error {
+        self.request_id.get_or_insert(request_id);
+        self
+    }
+
+    /// Whether the request may succeed if it is sent again: transport
+    /// failures such as timeouts and connection errors, server errors,
+    /// and rate limiting.
+    pub fn is_retryable(&self) -> bool {
+        match &self.kind {
+            ErrorKind::Http(e) => e.is_timeout() || e.is_connect() || e.is_body(),
+            ErrorKind::Status(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            },
+            ErrorKind::RateLimited { .. } | ErrorKind::Degraded(_) => true,
+            ErrorKind::BadRequest { .. }
+            | ErrorKind::Parse(_)
+            | ErrorKind::Decompress(_)
+            | ErrorKind::ResponseTooLarge { .. }
+            | ErrorKind::QuotaExhausted(_)
+            | ErrorKind::EscalationLimit { .. } => false
+        }
+    }
+
+    /// Whether the request was rejected because a rate limit or usage
+    /// quota was exceeded.
+    pub fn is_quota(&self) -> bool {
+        match &self.kind {
+            ErrorKind::Status(status) => *status == StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::RateLimited { .. } | ErrorKind::QuotaExhausted(_) => true,
+            _ => false
+        }
+    }
+
+    /// Whether the request was rejected because the API key is missing
+    /// or invalid.
+    pub fn is_auth(&self) -> bool {
+        match &self.kind {
+            ErrorKind::Status(status) => {
+                *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN
+            },
+            _ => false
+        }
+    }
+
+    /// Whether the response couldn't be deserialized.
+    pub fn is_parse(&self) -> bool {
+        match &self.kind {
+            ErrorKind::Http(e) => e.is_decode(),
+            ErrorKind::Parse(_) => true,
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some(request_id) = self.request_id {
+            write!(f, " (request {})", request_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Http(e) => Some(e),
+            ErrorKind::Status(_)
+            | ErrorKind::RateLimited { .. }
+            | ErrorKind::BadRequest { .. }
+            | ErrorKind::ResponseTooLarge { .. }
+            | ErrorKind::Degraded(_)
+            | ErrorKind::EscalationLimit { .. } => None,
+            ErrorKind::Parse(e) => Some(e),
+            ErrorKind::Decompress(e) => Some(e),
+            ErrorKind::QuotaExhausted(e) => Some(e)
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Http(e) => write!(f, "HTTP error: {}", e),
+            ErrorKind::Status(status) => write!(f, "API responded with status {}", status),
+            ErrorKind::RateLimited { retry_after: Some(delay) } => {
+                write!(f, "rate limited by API, retry after {}s", delay.as_secs())
+            },
+            ErrorKind::RateLimited { retry_after: None } => write!(f, "rate limited by API"),
+            ErrorKind::BadRequest { code, message } => {
+                write!(f, "bad request ({}): {}", code, message)
+            },
+            ErrorKind::Parse(e) => write!(f, "couldn't parse API response: {}", e),
+            ErrorKind::Decompress(e) => write!(f, "couldn't decompress API response: {}", e),
+            ErrorKind::Degraded(warnings) => {
+                let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+                write!(f, "degraded API response: {}", warnings.join(", "))
+            },
+            ErrorKind::ResponseTooLarge { limit } => {
+                write!(f, "API response exceeded the limit of {} bytes", limit)
+            },
+            ErrorKind::QuotaExhausted(e) => write!(f, "{}", e),
+            ErrorKind::EscalationLimit { needed, limit } => write!(
+                f,
+                "covering the window needs at least {} more requests, over the limit of {}",
+                needed,
+                limit
+            )
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error { kind, request_id: None }
+    }
+}
+
+impl From<http::Error> for Error {
+    fn from(error: http::Error) -> Error {
+        ErrorKind::Http(error).into()
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        ErrorKind::Parse(error.into()).into()
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Error {
+        ErrorKind::Parse(error).into()
+    }
+}
+
+// The text of `body` within SNIPPET_CHARS / 2 characters of the given
+// 1-based line and column, on a single line.
+fn snippet(body: &[u8], line: usize, column: usize) -> String {
+    let body = String::from_utf8_lossy(body);
+    let offset = body.split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>() + column.saturating_sub(1);
+    let offset = (0..=offset.min(body.len())).rev()
+        .find(|&i| body.is_char_boundary(i))
+        .unwrap_or(0);
+
+    let before: Vec<char> = body[..offset].chars().rev().take(SNIPPET_CHARS / 2 + 1).collect();
+    let after: Vec<char> = body[offset..].chars().take(SNIPPET_CHARS / 2 + 1).collect();
+
+    let mut snippet = String::new();
+
+    if before.len() > SNIPPET_CHARS / 2 {
+        snippet.push('…');
+    }
+
+    snippet.extend(before.iter().rev().skip(before.len().saturating_sub(SNIPPET_CHARS / 2)));
+    snippet.extend(after.iter().take(SNIPPET_CHARS / 2));
+
+    if after.len() > SNIPPET_CHARS / 2 {
+        snippet.push('…');
+    }
+
+    snippet.chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect()
+}
+
+// A Retry-After value is either a number of seconds or an HTTP date;
+// dates in the past mean we may retry immediately.
+fn parse_retry_after(value: &str, now: i64) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    datetime::parse_http_date(value).map(|at| Duration::from_secs((at - now).max(0) as u64))
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_retry_after, snippet, Error, ErrorKind, ParseError};
+
+    use crate::http::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use crate::http::StatusCode;
+    use crate::rate_limit::QuotaExhausted;
+    use crate::ApiResponse;
+
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    #[test]
+    fn test_status_classification() {
+        let forbidden = Error::from(ErrorKind::Status(StatusCode::FORBIDDEN));
+
+        assert!(forbidden.is_auth());
+        assert!(!forbidden.is_retryable());
+        assert!(!forbidden.is_quota());
+
+        let rate_limited = Error::from(ErrorKind::Status(StatusCode::TOO_MANY_REQUESTS));
+
+        assert!(rate_limited.is_quota());
+        assert!(rate_limited.is_retryable());
+        assert!(!rate_limited.is_auth());
+
+        let unavailable = Error::from(ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE));
+
+        assert!(unavailable.is_retryable());
+        assert!(!unavailable.is_parse());
+    }
+
+    #[test]
+    fn test_parse_classification() {
+        let error: Error = serde_json::from_str::<u64>("nope").unwrap_err().into();
+
+        assert!(error.is_parse());
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_from_response() {
+        let body = b"{\"code\":400,\"error\":\"The given location is invalid.\"}";
+
+        match Error::from_response(StatusCode::BAD_REQUEST, body).into_kind() {
+            ErrorKind::BadRequest { code, message } => {
+                assert_eq!(code, 400);
+                assert_eq!(message, "The given location is invalid.");
+            },
+            other => panic!("expected BadRequest, got {:?}", other)
+        }
+
+        let error = Error::from_response(StatusCode::BAD_REQUEST, b"<html></html>");
+
+        assert!(matches!(error.kind(), ErrorKind::Status(StatusCode::BAD_REQUEST)));
+
+        let error = Error::from_response(StatusCode::FORBIDDEN, body);
+
+        assert!(matches!(error.kind(), ErrorKind::Status(StatusCode::FORBIDDEN)));
+        assert!(!Error::from_response(StatusCode::BAD_REQUEST, body).is_retryable());
+    }
+
+    #[test]
+    fn test_from_http_response_rate_limited() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+
+        let error = Error::from_http_response(StatusCode::TOO_MANY_REQUESTS, &headers, b"");
+
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(120)));
+        assert!(error.is_quota());
+        assert!(error.is_retryable());
+
+        let empty = HeaderMap::new();
+        let error = Error::from_http_response(StatusCode::TOO_MANY_REQUESTS, &empty, b"");
+
+        assert!(matches!(error.kind(), ErrorKind::RateLimited { retry_after: None }));
+
+        let error = Error::from_http_response(StatusCode::SERVICE_UNAVAILABLE, &headers, b"");
+
+        assert!(matches!(error.kind(), ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE)));
+        assert_eq!(error.retry_after(), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = 1_516_579_200;
+
+        assert_eq!(parse_retry_after(" 30 ", now), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_retry_after("Mon, 22 Jan 2018 00:01:30 GMT", now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 21 Jan 2018 00:00:00 GMT", now),
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_request_id() {
+        let request_id = Uuid::new_v4();
+        let error = Error::from(ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE))
+            .with_request_id(request_id);
+
+        assert_eq!(error.request_id(), Some(request_id));
+        assert!(matches!(error.kind(), ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(error.is_retryable());
+        assert!(error.to_string().ends_with(&format!("(request {})", request_id)));
+
+        let error = error.with_request_id(Uuid::new_v4());
+
+        assert_eq!(error.request_id(), Some(request_id));
+        assert_eq!(Error::from(ErrorKind::Status(StatusCode::FORBIDDEN)).request_id(), None);
+
+        let body = b"{\"code\":400,\"error\":\"The given location is invalid.\"}";
+        let error = Error::from_response(StatusCode::BAD_REQUEST, body).with_request_id(request_id);
+
+        assert!(matches!(error.kind(), ErrorKind::BadRequest { code: 400, .. }));
+        assert!(!error.is_retryable());
+
+        let retry_after = Some(Duration::from_secs(5));
+        let error = Error::from(ErrorKind::RateLimited { retry_after }).with_request_id(request_id);
+
+        assert!(matches!(error.kind(), ErrorKind::RateLimited { .. }));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(5)));
+        assert!(error.is_quota());
+    }
+
+    #[test]
+    fn test_quota_exhausted_classification() {
+        let error = Error::from(ErrorKind::QuotaExhausted(QuotaExhausted { resets_at: 86_400 }));
+
+        assert!(error.is_quota());
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_parse_error_path() {
+        let body = b"{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+                     \"hourly\":{\"data\":[{\"time\":0},{\"time\":\"noon\",\"summary\":\"Dry\"}]}}";
+        let error = ParseError::from_slice::<ApiResponse>(body).unwrap_err();
+
+        assert_eq!(error.path(), "hourly.data[1].time");
+        assert!(error.snippet().contains("{\"time\":\"noon\""), "{}", error.snippet());
+        assert!(error.to_string().starts_with("at hourly.data[1].time: invalid type"), "{}", error);
+
+        let error: Error = error.into();
+
+        assert!(error.is_parse());
+        assert!(error.to_string().contains("(near `"));
+    }
+
+    #[test]
+    fn test_snippet() {
+        let long = format!("{{\"a\":\n{}!{}}}", "x".repeat(60), "y".repeat(60));
+        let cut = snippet(long.as_bytes(), 2, 61);
+
+        assert_eq!(cut.chars().count(), 82);
+        assert!(cut.starts_with("…x"));
+        assert!(cut.contains("x!y"));
+        assert!(cut.ends_with("y…"));
+
+        assert_eq!(snippet(b"{\n  \"a\": tru }", 2, 9), "{   \"a\": tru }");
+        assert_eq!(snippet(b"", 1, 1), "");
+    }
+}
@@ -0,0 +1,145 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Render responses as [GeoJSON](https://tools.ietf.org/html/rfc7946)
+//! Features, ready to be dropped onto a Leaflet or Mapbox map.
+//!
+//! The API identifies the regions an `Alert` applies to by name only,
+//! so every Feature is located at the `Point` the response was
+//! requested for. The region names are carried in the `regions`
+//! property.
+
+use serde_json::{json, Value};
+
+use crate::{Alert, ApiResponse};
+
+/// Render the `currently` block of a response as a Feature whose
+/// properties are the fields of the `DataPoint` (named as in the API
+/// response) plus the response `timezone`. Returns `None` if the
+/// response has no `currently` block.
+pub fn conditions_feature(response: &ApiResponse) -> Option<Value> {
+    let currently = response.currently.as_ref()?;
+
+    let mut properties = match serde_json::to_value(currently) {
+        Ok(Value::Object(map)) => map,
+        _ => return None
+    };
+
+    properties.retain(|_, v| !v.is_null());
+    properties.insert("kind".to_string(), json!("conditions"));
+    properties.insert("timezone".to_string(), json!(response.timezone));
+
+    Some(feature(response, Value::Object(properties)))
+}
+
+/// Render each `Alert` in a response as a Feature.
+pub fn alert_features(response: &ApiResponse) -> Vec<Value> {
+    response.alerts.iter()
+        .flatten()
+        .map(|alert| feature(response, alert_properties(alert)))
+        .collect()
+}
+
+/// Render the current conditions and every alert in a response as a
+/// FeatureCollection.
+pub fn feature_collection(response: &ApiResponse) -> Value {
+    let features: Vec<Value> = conditions_feature(response).into_iter()
+        .chain(alert_features(response))
+        .collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features
+    })
+}
+
+fn alert_properties(alert: &Alert) -> Value {
+    json!({
+        "kind": "alert",
+        "title": alert.title,
+        "description": alert.description,
+        "severity": alert.severity,
+        "regions": alert.regions,
+        "time": alert.time,
+        "expires": alert.expires,
+        "uri": alert.uri
+    })
+}
+
+fn feature(response: &ApiResponse, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": [response.longitude, response.latitude]
+        },
+        "properties": properties
+    })
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{alert_features, conditions_feature, feature_collection};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.5, "longitude": -71.25, "timezone": "America/New_York", "offset": -5,
+            "currently": {"time": 100, "temperature": 3.5},
+            "alerts": [{
+                "description": "Snow.",
+                "expires": 200,
+                "regions": ["Middlesex"],
+                "severity": "warning",
+                "time": 100,
+                "title": "Winter Storm Warning",
+                "uri": "https://alerts.weather.gov"
+            }]
+        }))
+    }
+
+    #[test]
+    fn test_conditions_feature() {
+        let feature = conditions_feature(&response()).unwrap();
+
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["coordinates"], json!([-71.25, 42.5]));
+        assert_eq!(feature["properties"]["temperature"], 3.5);
+        assert_eq!(feature["properties"]["timezone"], "America/New_York");
+        assert!(feature["properties"].get("humidity").is_none());
+    }
+
+    #[test]
+    fn test_alert_features() {
+        let features = alert_features(&response());
+
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["properties"]["severity"], "warning");
+        assert_eq!(features[0]["properties"]["regions"], json!(["Middlesex"]));
+    }
+
+    #[test]
+    fn test_feature_collection() {
+        let collection = feature_collection(&response());
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["features"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,198 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Map responses onto the attributes expected by [Home
+//! Assistant](https://developers.home-assistant.io/docs/core/entity/weather/)
+//! weather entities.
+//!
+//! Units are passed through unchanged, so the response should be
+//! requested in the units the entity is configured for. Fractions
+//! (humidity, cloud cover, precipitation probability) are converted to
+//! percentages, and forecast `datetime`s are rendered as RFC 3339
+//! timestamps in the response's local time.
+
+use serde_json::{json, Map, Value};
+
+use crate::datetime::to_rfc3339;
+use crate::{ApiResponse, DataPoint, Icon};
+
+/// Return the Home Assistant condition string corresponding to an
+/// `Icon`.
+pub fn condition(icon: &Icon) -> &'static str {
+    match icon {
+        Icon::ClearDay => "sunny",
+        Icon::ClearNight => "clear-night",
+        Icon::Rain => "rainy",
+        Icon::Snow => "snowy",
+        Icon::Sleet => "snowy-rainy",
+        Icon::Wind => "windy",
+        Icon::Fog => "fog",
+        Icon::Cloudy => "cloudy",
+        Icon::PartlyCloudyDay | Icon::PartlyCloudyNight => "partlycloudy",
+        Icon::Hail => "hail",
+        Icon::Thunderstorm => "lightning-rainy",
+        Icon::Tornado => "exceptional"
+    }
+}
+
+/// Return the state attributes of a weather entity: the current
+/// conditions plus a `forecast` array built from the `daily` block.
+/// Attributes the response doesn't report are omitted.
+pub fn attributes(response: &ApiResponse) -> Value {
+    let mut attributes = Map::new();
+
+    if let Some(currently) = &response.currently {
+        attributes.insert("condition".to_string(), json!(condition(&currently.inferred_icon())));
+
+        insert(&mut attributes, "temperature", currently.temperature);
+        insert(&mut attributes, "apparent_temperature", currently.apparent_temperature);
+        insert(&mut attributes, "dew_point", currently.dew_point);
+        insert(&mut attributes, "humidity", percent(currently.humidity));
+        insert(&mut attributes, "pressure", currently.pressure);
+        insert(&mut attributes, "wind_speed", currently.wind_speed);
+        insert(&mut attributes, "wind_gust_speed", currently.wind_gust);
+        insert(&mut attributes, "wind_bearing", currently.wind_bearing);
+        insert(&mut attributes, "visibility", currently.visibility);
+        insert(&mut attributes, "ozone", currently.ozone);
+        insert(&mut attributes, "uv_index", currently.uv_index);
+        insert(&mut attributes, "cloud_coverage", percent(currently.cloud_cover));
+    }
+
+    attributes.insert("forecast".to_string(), Value::Array(daily_forecast(response)));
+
+    Value::Object(attributes)
+}
+
+/// Return the `daily` block as Home Assistant forecast entries, with
+/// `temperature` and `templow` taken from the day's high and low.
+pub fn daily_forecast(response: &ApiResponse) -> Vec<Value> {
+    response.daily.iter()
+        .flat_map(|block| block.data.iter())
+        .map(|day| {
+            let mut entry = forecast_entry(response, day, 24.0);
+
+            insert(&mut entry, "temperature", day.temperature_high);
+            insert(&mut entry, "templow", day.temperature_low);
+
+            Value::Object(entry)
+        })
+        .collect()
+}
+
+/// Return the `hourly` block as Home Assistant forecast entries.
+pub fn hourly_forecast(response: &ApiResponse) -> Vec<Value> {
+    response.hourly.iter()
+        .flat_map(|block| block.data.iter())
+        .map(|hour| {
+            let mut entry = forecast_entry(response, hour, 1.0);
+
+            insert(&mut entry, "temperature", hour.temperature);
+            insert(&mut entry, "apparent_temperature", hour.apparent_temperature);
+
+            Value::Object(entry)
+        })
+        .collect()
+}
+
+#[allow(deprecated)]
+fn forecast_entry(response: &ApiResponse, point: &DataPoint, hours: f64) -> Map<String, Value> {
+    let mut entry = Map::new();
+
+    entry.insert("datetime".to_string(), json!(to_rfc3339(point.time, response.offset * 3600)));
+    entry.insert("condition".to_string(), json!(condition(&point.inferred_icon())));
+
+    insert(&mut entry, "precipitation", point.precip_intensity.map(|i| i * hours));
+    insert(&mut entry, "precipitation_probability", percent(point.precip_probability));
+    insert(&mut entry, "humidity", percent(point.humidity));
+    insert(&mut entry, "wind_speed", point.wind_speed);
+    insert(&mut entry, "wind_gust_speed", point.wind_gust);
+    insert(&mut entry, "wind_bearing", point.wind_bearing);
+    insert(&mut entry, "uv_index", point.uv_index);
+    insert(&mut entry, "cloud_coverage", percent(point.cloud_cover));
+
+    entry
+}
+
+fn insert(map: &mut Map<String, Value>, key: &str, value: Option<f64>) {
+    if let Some(value) = value {
+        map.insert(key.to_string(), json!(value));
+    }
+}
+
+fn percent(fraction: Option<f64>) -> Option<f64> {
+    fraction.map(|f| (f * 100.0).round())
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{attributes, condition, hourly_forecast};
+
+    use crate::testing;
+    use crate::{ApiResponse, Icon};
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.5, "longitude": -71.25, "timezone": "America/New_York", "offset": -5,
+            "currently": {"time": 100, "temperature": 3.5, "humidity": 0.81, "icon": "sleet"},
+            "hourly": {"data": [{"time": 1_516_597_200u64, "temperature": 2.0, "icon": "snow"}]},
+            "daily": {
+                "data": [{
+                    "time": 1_516_597_200u64,
+                    "icon": "rain",
+                    "temperatureHigh": 8.4,
+                    "temperatureLow": 1.2,
+                    "precipIntensity": 0.1,
+                    "precipProbability": 0.8
+                }]
+            }
+        }))
+    }
+
+    #[test]
+    fn test_condition() {
+        assert_eq!(condition(&Icon::ClearDay), "sunny");
+        assert_eq!(condition(&Icon::PartlyCloudyNight), "partlycloudy");
+    }
+
+    #[test]
+    fn test_attributes() {
+        let attributes = attributes(&response());
+
+        assert_eq!(attributes["condition"], "snowy-rainy");
+        assert_eq!(attributes["temperature"], 3.5);
+        assert_eq!(attributes["humidity"], 81.0);
+        assert!(attributes.get("pressure").is_none());
+
+        let forecast = &attributes["forecast"][0];
+
+        assert_eq!(forecast["datetime"], "2018-01-22T00:00:00-05:00");
+        assert_eq!(forecast["condition"], "rainy");
+        assert_eq!(forecast["temperature"], 8.4);
+        assert_eq!(forecast["templow"], 1.2);
+        assert_eq!(forecast["precipitation_probability"], 80.0);
+    }
+
+    #[test]
+    fn test_hourly_forecast() {
+        let forecast = hourly_forecast(&response());
+
+        assert_eq!(forecast.len(), 1);
+        assert_eq!(forecast[0]["condition"], "snowy");
+        assert_eq!(forecast[0]["temperature"], 2.0);
+    }
+}
@@ -0,0 +1,223 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Render the `daily` block of a response as an
+//! [iCalendar](https://tools.ietf.org/html/rfc5545) feed which calendar
+//! apps can subscribe to.
+//!
+//! Each day becomes an all-day `VEVENT` whose summary carries the high
+//! and low temperature and the chance of precipitation. Sunrise and
+//! sunset may optionally be included as timed events.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::datetime::CivilDateTime;
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+static PRODID: &str = "-//forecast-rs//forecast-rs//EN";
+static CRLF: &str = "\r\n";
+
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Render the `daily` block of `response` as a `VCALENDAR`. When
+/// `sun_events` is set, sunrise and sunset are included as timed events
+/// alongside each day. Returns `None` if the response has no `daily`
+/// block.
+#[allow(deprecated)]
+pub fn daily_calendar(response: &ApiResponse, sun_events: bool) -> Option<String> {
+    let daily = response.daily.as_ref()?;
+    let offset = response.offset * 3600;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-TIMEZONE:{}", escape(&response.timezone))
+    ];
+
+    for day in &daily.data {
+        lines.extend(day_event(response, day, offset, stamp));
+
+        if sun_events {
+            if let Some(sunrise) = day.sunrise_time {
+                lines.extend(sun_event(response, "Sunrise", sunrise, stamp));
+            }
+
+            if let Some(sunset) = day.sunset_time {
+                lines.extend(sun_event(response, "Sunset", sunset, stamp));
+            }
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    Some(lines.iter().map(|line| fold(line) + CRLF).collect())
+}
+
+fn day_event(response: &ApiResponse, day: &DataPoint, offset: i64, stamp: i64) -> Vec<String> {
+    let start = CivilDateTime::from_unix(day.time, offset);
+    let end = CivilDateTime::from_unix(day.time + 86_400, offset);
+
+    let mut summary = Vec::new();
+
+    if let Some(high) = day.temperature_high {
+        summary.push(format!("High {:.0}°", high));
+    }
+
+    if let Some(low) = day.temperature_low {
+        summary.push(format!("Low {:.0}°", low));
+    }
+
+    if let Some(probability) = day.precip_probability {
+        summary.push(format!("{:.0}% chance of precipitation", probability * 100.0));
+    }
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(response, "day", day.time)),
+        format!("DTSTAMP:{}", format_utc(stamp)),
+        format!("DTSTART;VALUE=DATE:{}", format_date(&start)),
+        format!("DTEND;VALUE=DATE:{}", format_date(&end)),
+        format!("SUMMARY:{}", escape(&summary.join(", "))),
+        "TRANSP:TRANSPARENT".to_string()
+    ];
+
+    if let Some(description) = &day.summary {
+        lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+
+    lines.push("END:VEVENT".to_string());
+
+    lines
+}
+
+fn sun_event(response: &ApiResponse, name: &str, time: i64, stamp: i64) -> Vec<String> {
+    vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(response, &name.to_lowercase(), time)),
+        format!("DTSTAMP:{}", format_utc(stamp)),
+        format!("DTSTART:{}", format_utc(time)),
+        format!("SUMMARY:{}", name),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string()
+    ]
+}
+
+fn uid(response: &ApiResponse, kind: &str, time: i64) -> String {
+    format!("{}-{}-{:.4},{:.4}@forecast-rs", kind, time, response.latitude, response.longitude)
+}
+
+fn format_date(date: &CivilDateTime) -> String {
+    format!("{:04}{:02}{:02}", date.year, date.month, date.day)
+}
+
+fn format_utc(time: i64) -> String {
+    let t = CivilDateTime::from_unix(time, 0);
+
+    format!("{}T{:02}{:02}{:02}Z", format_date(&t), t.hour, t.minute, t.second)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 octets are folded onto continuation lines which
+// begin with a single space, taking care not to split a UTF-8 sequence.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str(CRLF);
+            folded.push(' ');
+            octets = 1;
+        }
+
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{daily_calendar, fold};
+
+    use crate::testing;
+    use crate::ApiResponse;
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.5, "longitude": -71.25, "timezone": "America/New_York", "offset": -5,
+            "daily": {
+                "data": [{
+                    "time": 1_516_597_200u64,
+                    "summary": "Rain, heavy at times.",
+                    "temperatureHigh": 8.4,
+                    "temperatureLow": 1.2,
+                    "precipProbability": 0.8,
+                    "sunriseTime": 1_516_623_000u64,
+                    "sunsetTime": 1_516_658_700u64
+                }]
+            }
+        }))
+    }
+
+    #[test]
+    fn test_daily_calendar() {
+        let calendar = daily_calendar(&response(), false).unwrap();
+        let lines: Vec<&str> = calendar.split("\r\n").collect();
+
+        assert_eq!(lines[0], "BEGIN:VCALENDAR");
+        assert!(lines.contains(&"DTSTART;VALUE=DATE:20180122"));
+        assert!(lines.contains(&"DTEND;VALUE=DATE:20180123"));
+        assert!(lines.contains(&"SUMMARY:High 8°\\, Low 1°\\, 80% chance of precipitation"));
+        assert!(lines.contains(&"DESCRIPTION:Rain\\, heavy at times."));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_daily_calendar_sun_events() {
+        let calendar = daily_calendar(&response(), true).unwrap();
+
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 3);
+        assert!(calendar.contains("DTSTART:20180122T121000Z\r\nSUMMARY:Sunrise"));
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = "X".repeat(100);
+        let folded = fold(&line);
+
+        assert_eq!(folded, format!("{}\r\n {}", "X".repeat(75), "X".repeat(25)));
+        assert_eq!(fold("short"), "short");
+    }
+}
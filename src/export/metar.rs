@@ -0,0 +1,269 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Render the `currently` block of a response as a METAR-style
+//! observation, e.g.
+//!
+//! ```text
+//! KBOS 221500Z 27015G25KT 9999 -RA BKN/// 03/M01 Q1013
+//! ```
+//!
+//! Groups follow the METAR conventions: wind in knots, visibility in
+//! meters (statute miles for responses in US units, which also report
+//! pressure as an altimeter setting in inches of mercury), temperature
+//! and dew point in whole degrees Celsius. Cloud heights are unknown to
+//! the API, so cloud groups carry `///` for the height. Groups whose
+//! fields are missing from the response are omitted.
+//!
+//! The units of the response are read from its `flags`; a response
+//! without flags is assumed to be in US units, the API's default.
+
+use crate::datetime::CivilDateTime;
+use crate::{ApiResponse, DataPoint, Icon, PrecipType, Units};
+
+// constants
+
+const KNOTS_PER_MPH: f64 = 0.868_976;
+const KNOTS_PER_MPS: f64 = 1.943_844;
+const KNOTS_PER_KPH: f64 = 0.539_957;
+const METERS_PER_KM: f64 = 1000.0;
+const METERS_PER_MILE: f64 = 1609.344;
+const MM_PER_INCH: f64 = 25.4;
+const INHG_PER_HPA: f64 = 0.029_53;
+
+// Rain rates in mm/h separating light, moderate and heavy precipitation.
+const LIGHT_PRECIP_INTENSITY: f64 = 2.5;
+const HEAVY_PRECIP_INTENSITY: f64 = 7.6;
+
+// Visibility at and above which METAR reports 9999.
+const UNLIMITED_VISIBILITY_METERS: f64 = 10_000.0;
+
+/// Render the current conditions of `response` as observed at
+/// `station`, a four-letter ICAO identifier. Returns `None` if the
+/// response has no `currently` block.
+pub fn metar(response: &ApiResponse, station: &str) -> Option<String> {
+    let currently = response.currently.as_ref()?;
+    let units = response.flags.as_ref().map_or(&Units::Imperial, |flags| &flags.units);
+    let us = *units == Units::Imperial;
+
+    let groups = [
+        Some(station.to_string()),
+        Some(time_group(currently.time)),
+        wind_group(currently, units),
+        currently.visibility.map(|v| visibility_group(v, units)),
+        weather_group(currently, units),
+        currently.cloud_cover.map(cloud_group),
+        temperature_group(currently, units),
+        currently.pressure.map(|p| pressure_group(p, us))
+    ];
+
+    Some(groups.iter().flatten().cloned().collect::<Vec<String>>().join(" "))
+}
+
+fn time_group(time: i64) -> String {
+    let t = CivilDateTime::from_unix(time, 0);
+
+    format!("{:02}{:02}{:02}Z", t.day, t.hour, t.minute)
+}
+
+fn wind_group(point: &DataPoint, units: &Units) -> Option<String> {
+    let speed = knots(point.wind_speed?, units);
+    let bearing = match point.wind_bearing {
+        Some(bearing) if speed >= 1.0 => format!("{:03}", ((bearing / 10.0).round() as i64 * 10) % 360),
+        Some(_) => "000".to_string(),
+        None => "VRB".to_string()
+    };
+
+    let gust = point.wind_gust
+        .map(|gust| knots(gust, units))
+        .filter(|gust| gust.round() > speed.round())
+        .map_or(String::new(), |gust| format!("G{:02.0}", gust));
+
+    Some(format!("{}{:02.0}{}KT", bearing, speed, gust))
+}
+
+fn visibility_group(visibility: f64, units: &Units) -> String {
+    match units {
+        Units::Imperial => {
+            if visibility >= 10.0 {
+                "10SM".to_string()
+            } else if visibility >= 1.0 {
+                format!("{:.0}SM", visibility)
+            } else {
+                match (visibility * 4.0).round() as i64 {
+                    0 => "M1/4SM".to_string(),
+                    1 => "1/4SM".to_string(),
+                    2 => "1/2SM".to_string(),
+                    _ => "3/4SM".to_string()
+                }
+            }
+        },
+        _ => {
+            let meters = match units {
+                Units::UK => visibility * METERS_PER_MILE,
+                _ => visibility * METERS_PER_KM
+            };
+
+            if meters >= UNLIMITED_VISIBILITY_METERS {
+                "9999".to_string()
+            } else if meters >= 5000.0 {
+                format!("{:04}", (meters / 1000.0).floor() as i64 * 1000)
+            } else {
+                format!("{:04}", (meters / 100.0).floor() as i64 * 100)
+            }
+        }
+    }
+}
+
+fn weather_group(point: &DataPoint, units: &Units) -> Option<String> {
+    let thunder = point.icon == Some(Icon::Thunderstorm);
+    let intensity = point.precip_intensity.filter(|i| *i > 0.0).map(|i| match units {
+        Units::Imperial => i * MM_PER_INCH,
+        _ => i
+    });
+
+    match intensity {
+        Some(intensity) => {
+            let qualifier = if intensity < LIGHT_PRECIP_INTENSITY {
+                "-"
+            } else if intensity > HEAVY_PRECIP_INTENSITY {
+                "+"
+            } else {
+                ""
+            };
+
+            let precip = match point.precip_type {
+                Some(PrecipType::Rain) => "RA",
+                Some(PrecipType::Snow) => "SN",
+                Some(PrecipType::Sleet) => "PL",
+                None => "UP"
+            };
+
+            Some(format!("{}{}{}", qualifier, if thunder { "TS" } else { "" }, precip))
+        },
+        None if thunder => Some("TS".to_string()),
+        None if point.icon == Some(Icon::Fog) => Some("FG".to_string()),
+        None => None
+    }
+}
+
+fn cloud_group(cloud_cover: f64) -> String {
+    let oktas = (cloud_cover * 8.0).round() as i64;
+
+    let cover = match oktas {
+        i64::MIN..=0 => return "SKC".to_string(),
+        1..=2 => "FEW",
+        3..=4 => "SCT",
+        5..=7 => "BKN",
+        _ => "OVC"
+    };
+
+    format!("{}///", cover)
+}
+
+fn temperature_group(point: &DataPoint, units: &Units) -> Option<String> {
+    let temperature = celsius(point.temperature?, units);
+    let dew_point = point.dew_point.map_or("//".to_string(), |d| format_celsius(celsius(d, units)));
+
+    Some(format!("{}/{}", format_celsius(temperature), dew_point))
+}
+
+fn pressure_group(pressure: f64, us: bool) -> String {
+    if us {
+        format!("A{:04.0}", pressure * INHG_PER_HPA * 100.0)
+    } else {
+        format!("Q{:04.0}", pressure)
+    }
+}
+
+fn knots(speed: f64, units: &Units) -> f64 {
+    match units {
+        Units::SI => speed * KNOTS_PER_MPS,
+        Units::CA => speed * KNOTS_PER_KPH,
+        _ => speed * KNOTS_PER_MPH
+    }
+}
+
+fn celsius(temperature: f64, units: &Units) -> f64 {
+    match units {
+        Units::Imperial => (temperature - 32.0) * 5.0 / 9.0,
+        _ => temperature
+    }
+}
+
+// Whole degrees, with negative values prefixed by `M`.
+fn format_celsius(temperature: f64) -> String {
+    let rounded = temperature.round() as i64;
+
+    if rounded < 0 {
+        format!("M{:02}", -rounded)
+    } else {
+        format!("{:02}", rounded)
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::metar;
+
+    use crate::testing;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_metar_si() {
+        let response = testing::response(json!({
+            "latitude": 51.5, "longitude": -0.1, "timezone": "Europe/London",
+            "currently": {
+                "time": 1516633200,
+                "windSpeed": 7.7,
+                "windGust": 12.9,
+                "windBearing": 268,
+                "visibility": 16.09,
+                "precipIntensity": 0.8,
+                "precipType": "rain",
+                "cloudCover": 0.7,
+                "temperature": 3.4,
+                "dewPoint": -0.6,
+                "pressure": 1013.2
+            },
+            "flags": {"sources": [], "units": "si"}
+        }));
+
+        assert_eq!(metar(&response, "EGLL").unwrap(),
+                   "EGLL 221500Z 27015G25KT 9999 -RA BKN/// 03/M01 Q1013");
+    }
+
+    #[test]
+    fn test_metar_us() {
+        let response = testing::response(json!({
+            "latitude": 42.4, "longitude": -71.0, "timezone": "America/New_York", "offset": -5,
+            "currently": {
+                "time": 1516633200,
+                "windSpeed": 4.0,
+                "visibility": 0.5,
+                "icon": "fog",
+                "cloudCover": 1.0,
+                "temperature": 41.0,
+                "pressure": 1013.2
+            }
+        }));
+
+        assert_eq!(metar(&response, "KBOS").unwrap(),
+                   "KBOS 221500Z VRB03KT 1/2SM FG OVC/// 05/// A2992");
+    }
+}
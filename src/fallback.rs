@@ -0,0 +1,287 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Graceful degradation across several sources of forecasts.
+//!
+//! A `FallbackProvider` tries a list of `ForecastProvider`s in order,
+//! e.g. Dark Sky, then a compatible API such as Pirate Weather (see
+//! `ApiClient::with_base_url`), then whatever a `ForecastCache` last
+//! held, until one of them serves a forecast. Each step may have its own
+//! timeout. The result records which provider served the forecast and
+//! why every earlier provider didn't.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::ForecastCache;
+use crate::error::Error;
+use crate::portfolio::Site;
+use crate::sourced::Sourced;
+use crate::{ApiClient, ApiResponse};
+
+/// The future returned by `ForecastProvider::fetch`.
+pub type ProviderFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Sourced<ApiResponse>, Failure>> + Send + 'a>>;
+
+/// A source of forecasts.
+pub trait ForecastProvider: fmt::Debug + Send + Sync {
+    /// The name under which the provider is reported.
+    fn name(&self) -> &str;
+
+    /// Fetch the forecast for `site`.
+    fn fetch<'a>(&'a self, site: &'a Site) -> ProviderFuture<'a>;
+}
+
+/// Why a provider didn't serve a forecast.
+#[derive(Debug)]
+pub enum Failure {
+    /// The provider's request failed.
+    Error(Error),
+
+    /// The provider didn't respond within its step's timeout.
+    TimedOut,
+
+    /// The provider had nothing to serve, e.g. an empty cache.
+    Unavailable
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Error(e) => write!(f, "{}", e),
+            Failure::TimedOut => write!(f, "timed out"),
+            Failure::Unavailable => write!(f, "no forecast available")
+        }
+    }
+}
+
+/// A provider which didn't serve a forecast, and why.
+#[derive(Debug)]
+pub struct Attempt {
+    pub provider: String,
+
+    pub failure: Failure
+}
+
+/// A forecast served by a `FallbackProvider`.
+#[derive(Debug)]
+pub struct Served {
+    pub response: Sourced<ApiResponse>,
+
+    /// The name of the provider which served the forecast.
+    pub provider: String,
+
+    /// The providers tried before it, in order.
+    pub attempts: Vec<Attempt>
+}
+
+/// Every provider of a `FallbackProvider` failed.
+#[derive(Debug)]
+pub struct FallbackError {
+    pub attempts: Vec<Attempt>
+}
+
+impl fmt::Display for FallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attempts: Vec<String> = self.attempts.iter()
+            .map(|attempt| format!("{}: {}", attempt.provider, attempt.failure))
+            .collect();
+
+        write!(f, "every forecast provider failed ({})", attempts.join("; "))
+    }
+}
+
+impl std::error::Error for FallbackError {}
+
+/// Fetches forecasts from an API with one key.
+#[derive(Debug)]
+pub struct ApiProvider {
+    name: String,
+    client: Arc<ApiClient<'static>>,
+    api_key: String
+}
+
+impl ApiProvider {
+    /// A provider named `name` which fetches forecasts with `client`
+    /// using `api_key`.
+    pub fn new(name: &str, client: Arc<ApiClient<'static>>, api_key: &str) -> ApiProvider {
+        ApiProvider { name: name.to_string(), client, api_key: api_key.to_string() }
+    }
+}
+
+impl ForecastProvider for ApiProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn fetch<'a>(&'a self, site: &'a Site) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let request = site.request(&self.api_key).build();
+
+            self.client.get_forecast_sourced(&request).await.map_err(Failure::Error)
+        })
+    }
+}
+
+/// Serves whatever forecast a `ForecastCache` holds for a site, however
+/// stale, without contacting the API.
+#[derive(Debug)]
+pub struct CacheProvider {
+    cache: ForecastCache
+}
+
+impl CacheProvider {
+    pub fn new(cache: ForecastCache) -> CacheProvider {
+        CacheProvider { cache }
+    }
+}
+
+impl ForecastProvider for CacheProvider {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn fetch<'a>(&'a self, site: &'a Site) -> ProviderFuture<'a> {
+        let cached = self.cache.cached(site);
+
+        Box::pin(async move { cached.ok_or(Failure::Unavailable) })
+    }
+}
+
+#[derive(Debug)]
+struct Step {
+    provider: Box<dyn ForecastProvider>,
+    timeout: Option<Duration>
+}
+
+/// Tries providers in order until one serves a forecast.
+#[derive(Debug, Default)]
+pub struct FallbackProvider {
+    steps: Vec<Step>
+}
+
+impl FallbackProvider {
+    /// Construct a FallbackProvider with no providers.
+    pub fn new() -> FallbackProvider {
+        FallbackProvider::default()
+    }
+
+    /// Try `provider` next, for as long as it takes.
+    pub fn then<P>(mut self, provider: P) -> FallbackProvider where P : ForecastProvider + 'static {
+        self.steps.push(Step { provider: Box::new(provider), timeout: None });
+        self
+    }
+
+    /// Try `provider` next, giving up on it after `timeout`.
+    pub fn then_with_timeout<P>(mut self, provider: P, timeout: Duration) -> FallbackProvider
+        where P : ForecastProvider + 'static {
+        self.steps.push(Step { provider: Box::new(provider), timeout: Some(timeout) });
+        self
+    }
+
+    /// Fetch the forecast for `site` from the first provider which
+    /// serves one.
+    pub async fn fetch(&self, site: &Site) -> Result<Served, FallbackError> {
+        let mut attempts = Vec::new();
+
+        for step in &self.steps {
+            let provider = step.provider.name().to_string();
+            let result = match step.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, step.provider.fetch(site))
+                    .await
+                    .unwrap_or(Err(Failure::TimedOut)),
+                None => step.provider.fetch(site).await
+            };
+
+            match result {
+                Ok(response) => return Ok(Served { response, provider, attempts }),
+                Err(failure) => attempts.push(Attempt { provider, failure })
+            }
+        }
+
+        Err(FallbackError { attempts })
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheProvider, Failure, FallbackProvider, ForecastProvider, ProviderFuture};
+
+    use crate::cache::ForecastCache;
+    use crate::portfolio::Site;
+    use crate::sourced::{CacheStatus, Sourced};
+    use crate::testing;
+    use crate::ApiClient;
+
+    use serde_json::json;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    static API_KEY: &str = "some_api_key";
+
+    #[derive(Debug)]
+    struct Slow;
+
+    impl ForecastProvider for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn fetch<'a>(&'a self, _site: &'a Site) -> ProviderFuture<'a> {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Err(Failure::Unavailable)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider() {
+        let site = Site::new(1.0, 2.0);
+        let cache = ForecastCache::new(Arc::new(ApiClient::default()), API_KEY);
+        let empty = ForecastCache::new(Arc::new(ApiClient::default()), API_KEY);
+        let url = site.request(API_KEY).build().url();
+
+        cache.insert(&site, Sourced::new(testing::response(json!({})), &url, API_KEY, 1_000));
+
+        let fallback = FallbackProvider::new()
+            .then_with_timeout(Slow, Duration::from_millis(10))
+            .then(CacheProvider::new(empty))
+            .then(CacheProvider::new(cache));
+
+        let served = fallback.fetch(&site).await.unwrap();
+
+        assert_eq!(served.provider, "cache");
+        assert_eq!(served.response.cache_status, CacheStatus::Hit);
+        assert_eq!(served.attempts.len(), 2);
+        assert_eq!(served.attempts[0].provider, "slow");
+        assert!(matches!(served.attempts[0].failure, Failure::TimedOut));
+        assert!(matches!(served.attempts[1].failure, Failure::Unavailable));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider_exhausted() {
+        let fallback = FallbackProvider::new().then_with_timeout(Slow, Duration::from_millis(10));
+        let error = fallback.fetch(&Site::new(1.0, 2.0)).await.unwrap_err();
+
+        assert_eq!(error.attempts.len(), 1);
+        assert_eq!(error.to_string(), "every forecast provider failed (slow: timed out)");
+    }
+}
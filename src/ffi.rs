@@ -0,0 +1,273 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! C bindings for the parsed client, available with the `ffi` feature.
+//!
+//! Every function blocks until the request completes, using the shared
+//! client (see `ApiClient::shared`) on a background runtime,
+//! and returns a `ForecastStatus`. The C header is generated into
+//! `include/forecast.h` by running `cbindgen --output include/forecast.h`
+//! in the project root.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::blocking;
+use crate::{ApiClient, ApiResponse, DataPoint, Error, ErrorKind, ForecastRequestBuilder};
+
+/// The outcome of an FFI call.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ForecastStatus {
+    Ok = 0,
+
+    /// A pointer argument was null.
+    NullArgument = 1,
+
+    /// A string argument wasn't valid UTF-8.
+    InvalidUtf8 = 2,
+
+    /// The request couldn't be sent, or the response couldn't be read.
+    Http = 3,
+
+    /// The API responded with a non-success status.
+    Status = 4,
+
+    /// The API rejected the request as invalid.
+    BadRequest = 5,
+
+    /// The response couldn't be parsed.
+    Parse = 6,
+
+    /// The response was degraded.
+    Degraded = 7,
+
+    /// The client or runtime couldn't be constructed.
+    Internal = 8,
+
+    /// The response has no current conditions.
+    NoData = 9,
+
+    /// The daily quota of the client's rate limiter is spent.
+    QuotaExhausted = 10,
+
+    /// The API rejected the request with `429 Too Many Requests`.
+    RateLimited = 11,
+
+    /// The response exceeded the client's maximum response size.
+    ResponseTooLarge = 12,
+
+    /// Covering a time window would take more requests than allowed.
+    EscalationLimit = 13
+}
+
+/// Current conditions. Measurements the API didn't report are NaN.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ForecastCurrent {
+    pub time: i64,
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub dew_point: f64,
+    pub humidity: f64,
+    pub pressure: f64,
+    pub wind_speed: f64,
+    pub wind_bearing: f64,
+    pub cloud_cover: f64,
+    pub precip_intensity: f64,
+    pub precip_probability: f64,
+    pub visibility: f64
+}
+
+impl From<&DataPoint> for ForecastCurrent {
+    fn from(point: &DataPoint) -> ForecastCurrent {
+        let nan = |value: Option<f64>| value.unwrap_or(f64::NAN);
+
+        ForecastCurrent {
+            time: point.time,
+            temperature: nan(point.temperature),
+            apparent_temperature: nan(point.apparent_temperature),
+            dew_point: nan(point.dew_point),
+            humidity: nan(point.humidity),
+            pressure: nan(point.pressure),
+            wind_speed: nan(point.wind_speed),
+            wind_bearing: nan(point.wind_bearing),
+            cloud_cover: nan(point.cloud_cover),
+            precip_intensity: nan(point.precip_intensity),
+            precip_probability: nan(point.precip_probability),
+            visibility: nan(point.visibility)
+        }
+    }
+}
+
+impl From<&Error> for ForecastStatus {
+    fn from(error: &Error) -> ForecastStatus {
+        match error.kind() {
+            ErrorKind::Http(_) => ForecastStatus::Http,
+            ErrorKind::Status(_) => ForecastStatus::Status,
+            ErrorKind::BadRequest { .. } => ForecastStatus::BadRequest,
+            ErrorKind::Parse(_) | ErrorKind::Decompress(_) => ForecastStatus::Parse,
+            ErrorKind::Degraded(_) => ForecastStatus::Degraded,
+            ErrorKind::QuotaExhausted(_) => ForecastStatus::QuotaExhausted,
+            ErrorKind::RateLimited { .. } => ForecastStatus::RateLimited,
+            ErrorKind::ResponseTooLarge { .. } => ForecastStatus::ResponseTooLarge,
+            ErrorKind::EscalationLimit { .. } => ForecastStatus::EscalationLimit
+        }
+    }
+}
+
+/// Fetch the current conditions at a location into `out`.
+///
+/// # Safety
+///
+/// `api_key` must be a valid null-terminated string and `out` must
+/// point to writable memory for a `ForecastCurrent`.
+#[no_mangle]
+pub unsafe extern "C" fn forecast_get_current(
+    api_key: *const c_char,
+    latitude: f64,
+    longitude: f64,
+    out: *mut ForecastCurrent
+) -> ForecastStatus {
+    if out.is_null() {
+        return ForecastStatus::NullArgument;
+    }
+
+    let response = match unsafe { fetch(api_key, latitude, longitude) } {
+        Ok(response) => response,
+        Err(status) => return status
+    };
+
+    match &response.currently {
+        Some(currently) => {
+            unsafe { out.write(ForecastCurrent::from(currently)) };
+            ForecastStatus::Ok
+        },
+        None => ForecastStatus::NoData
+    }
+}
+
+/// Fetch the forecast for a location as a JSON string, stored in
+/// `out_json`. The string must be freed with `forecast_string_free`.
+///
+/// # Safety
+///
+/// `api_key` must be a valid null-terminated string and `out_json` must
+/// point to writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn forecast_get_forecast_json(
+    api_key: *const c_char,
+    latitude: f64,
+    longitude: f64,
+    out_json: *mut *mut c_char
+) -> ForecastStatus {
+    if out_json.is_null() {
+        return ForecastStatus::NullArgument;
+    }
+
+    unsafe { out_json.write(ptr::null_mut()) };
+
+    let response = match unsafe { fetch(api_key, latitude, longitude) } {
+        Ok(response) => response,
+        Err(status) => return status
+    };
+
+    // serialized JSON never contains an interior NUL
+    match crate::wire::to_string(&response).map(CString::new) {
+        Ok(Ok(json)) => {
+            unsafe { out_json.write(json.into_raw()) };
+            ForecastStatus::Ok
+        },
+        _ => ForecastStatus::Parse
+    }
+}
+
+/// Free a string returned by this library. Does nothing if `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library which hasn't
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn forecast_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+// Fetch the forecast for a location, blocking the calling thread.
+unsafe fn fetch(api_key: *const c_char, latitude: f64, longitude: f64)
+    -> Result<ApiResponse, ForecastStatus> {
+    if api_key.is_null() {
+        return Err(ForecastStatus::NullArgument);
+    }
+
+    let api_key = unsafe { CStr::from_ptr(api_key) }
+        .to_str()
+        .map_err(|_| ForecastStatus::InvalidUtf8)?
+        .to_string();
+
+    let client = ApiClient::shared().map_err(|_| ForecastStatus::Internal)?;
+
+    blocking::block_on(async move {
+        let request = ForecastRequestBuilder::new(&api_key, latitude, longitude).build();
+        client.get_forecast_parsed(request).await
+    })
+    .ok_or(ForecastStatus::Internal)?
+    .map_err(|e| ForecastStatus::from(&e))
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{forecast_get_current, forecast_get_forecast_json, forecast_string_free,
+                ForecastCurrent, ForecastStatus};
+
+    use crate::DataPoint;
+
+    use std::ffi::CString;
+    use std::ptr;
+
+    #[test]
+    fn test_null_arguments() {
+        let api_key = CString::new("some_api_key").unwrap();
+        let mut json = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                forecast_get_current(api_key.as_ptr(), 1.0, 2.0, ptr::null_mut()),
+                ForecastStatus::NullArgument
+            );
+            assert_eq!(
+                forecast_get_forecast_json(ptr::null(), 1.0, 2.0, &mut json),
+                ForecastStatus::NullArgument
+            );
+            assert!(json.is_null());
+
+            forecast_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_current_from_data_point() {
+        let point: DataPoint = serde_json::from_str("{\"time\":1,\"temperature\":20.5}").unwrap();
+        let current = ForecastCurrent::from(&point);
+
+        assert_eq!(current.time, 1);
+        assert_eq!(current.temperature, 20.5);
+        assert!(current.humidity.is_nan());
+    }
+}
@@ -0,0 +1,217 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Dynamic access to the numeric fields of a `DataPoint`, e.g. to
+//! select the columns of a table or configure generic analytics at
+//! runtime:
+//!
+//! ```
+//! use forecast::DataPoint;
+//! use forecast::field::Field;
+//!
+//! let point = DataPoint::builder().time(3600).with_humidity(0.8).build();
+//! let field: Field = "humidity".parse().unwrap();
+//!
+//! assert_eq!(point.get(field), Some(0.8));
+//! assert_eq!(point.get(Field::Time), Some(3600.0));
+//! assert_eq!(Field::iter().filter(|field| point.get(*field).is_some()).count(), 2);
+//! ```
+//!
+//! Fields are named as in `DataPoint`, in snake case. Timestamps are
+//! returned as seconds since the UNIX epoch.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::DataPoint;
+
+macro_rules! fields {
+    (measurements: [$($float_variant:ident => $float:ident),*],
+     times: [$($time_variant:ident => $time:ident),*]) => {
+        /// A numeric field of a `DataPoint`.
+        #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Field {
+            $($float_variant,)*
+            $($time_variant,)*
+            Time
+        }
+
+        static FIELDS: &[Field] = &[$(Field::$float_variant,)* $(Field::$time_variant,)* Field::Time];
+
+        impl Field {
+            /// The name of the field in `DataPoint`, e.g. `"wind_gust"`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Field::$float_variant => stringify!($float),)*
+                    $(Field::$time_variant => stringify!($time),)*
+                    Field::Time => "time"
+                }
+            }
+
+            /// Whether the field is a time, in seconds since the UNIX
+            /// epoch, rather than a measurement.
+            pub fn is_time(&self) -> bool {
+                match self {
+                    $(Field::$float_variant => false,)*
+                    $(Field::$time_variant => true,)*
+                    Field::Time => true
+                }
+            }
+        }
+
+        impl DataPoint {
+            /// The value of `field`, or `None` if the API omitted it.
+            #[allow(deprecated)]
+            pub fn get(&self, field: Field) -> Option<f64> {
+                match field {
+                    $(Field::$float_variant => self.$float,)*
+                    $(Field::$time_variant => self.$time.map(|time| time as f64),)*
+                    Field::Time => Some(self.time as f64)
+                }
+            }
+
+            // The measurement `field`, or `None` for a time.
+            #[allow(deprecated)]
+            pub(crate) fn measurement_mut(&mut self, field: Field) -> Option<&mut Option<f64>> {
+                match field {
+                    $(Field::$float_variant => Some(&mut self.$float),)*
+                    _ => None
+                }
+            }
+        }
+    }
+}
+
+fields! {
+    measurements: [
+        ApparentTemperature => apparent_temperature,
+        ApparentTemperatureHigh => apparent_temperature_high,
+        ApparentTemperatureLow => apparent_temperature_low,
+        ApparentTemperatureMax => apparent_temperature_max,
+        ApparentTemperatureMin => apparent_temperature_min,
+        Cape => cape,
+        CloudCover => cloud_cover,
+        DewPoint => dew_point,
+        Humidity => humidity,
+        MoonPhase => moon_phase,
+        NearestStormBearing => nearest_storm_bearing,
+        NearestStormDistance => nearest_storm_distance,
+        Ozone => ozone,
+        PrecipAccumulation => precip_accumulation,
+        PrecipIntensity => precip_intensity,
+        PrecipIntensityMax => precip_intensity_max,
+        PrecipProbability => precip_probability,
+        Pressure => pressure,
+        SwellDirection => swell_direction,
+        SwellHeight => swell_height,
+        SwellPeriod => swell_period,
+        Temperature => temperature,
+        TemperatureHigh => temperature_high,
+        TemperatureLow => temperature_low,
+        TemperatureMax => temperature_max,
+        TemperatureMin => temperature_min,
+        UvIndex => uv_index,
+        Visibility => visibility,
+        WaveHeight => wave_height,
+        WavePeriod => wave_period,
+        WindBearing => wind_bearing,
+        WindGust => wind_gust,
+        WindSpeed => wind_speed
+    ],
+    times: [
+        ApparentTemperatureHighTime => apparent_temperature_high_time,
+        ApparentTemperatureLowTime => apparent_temperature_low_time,
+        ApparentTemperatureMaxTime => apparent_temperature_max_time,
+        ApparentTemperatureMinTime => apparent_temperature_min_time,
+        PrecipIntensityMaxTime => precip_intensity_max_time,
+        SunriseTime => sunrise_time,
+        SunsetTime => sunset_time,
+        TemperatureHighTime => temperature_high_time,
+        TemperatureLowTime => temperature_low_time,
+        TemperatureMaxTime => temperature_max_time,
+        TemperatureMinTime => temperature_min_time,
+        UvIndexTime => uv_index_time,
+        WindGustTime => wind_gust_time
+    ]
+}
+
+impl Field {
+    /// Every field: the measurements, then the times, then `Time`.
+    pub fn iter() -> impl Iterator<Item = Field> {
+        FIELDS.iter().cloned()
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = UnknownField;
+
+    fn from_str(name: &str) -> Result<Field, UnknownField> {
+        Field::iter()
+            .find(|field| field.name() == name)
+            .ok_or_else(|| UnknownField(name.to_string()))
+    }
+}
+
+/// The error returned when parsing the name of a field which doesn't
+/// exist or isn't numeric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField(pub String);
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown field: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownField {}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, UnknownField};
+
+    use crate::DataPoint;
+
+    #[test]
+    fn test_get() {
+        let point = DataPoint::builder()
+            .time(60)
+            .with_wind_gust(12.5)
+            .with_sunset_time(120)
+            .build();
+
+        assert_eq!(point.get(Field::WindGust), Some(12.5));
+        assert_eq!(point.get(Field::SunsetTime), Some(120.0));
+        assert_eq!(point.get(Field::Time), Some(60.0));
+        assert_eq!(point.get(Field::Humidity), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Field::iter().count(), 47);
+        assert!(Field::iter().all(|field| field.name().parse() == Ok(field)));
+        assert_eq!("precip_type".parse::<Field>(), Err(UnknownField("precip_type".to_string())));
+        assert_eq!(serde_json::to_string(&Field::UvIndexTime).unwrap(), "\"uv_index_time\"");
+        assert!(Field::Time.is_time());
+        assert!(!Field::Temperature.is_time());
+    }
+}
@@ -0,0 +1,223 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Golden-file fixtures: raw API responses saved as test data and
+//! listed in a `manifest.json` alongside them. `Manifest::record`
+//! scrubs the API key and rounds the coordinates of each response with
+//! `anonymize` before writing it.
+//!
+//! The crate's own fixtures live in `resources/tests/`; those which
+//! predate the manifest were saved by hand and are left as they are. To
+//! record fresh ones from the live API, run
+//!
+//! ```text
+//! FORECAST_API_KEY=$YOUR_FORECAST_API_KEY cargo test --features record-fixtures
+//! ```
+//!
+//! and review the diff before committing it. Every fixture in the
+//! manifest is round-tripped through the models by the integration
+//! tests.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+// constants
+
+static MANIFEST: &str = "manifest.json";
+static REDACTED: &str = "REDACTED";
+
+// about 11 km, enough to not pinpoint whoever recorded the fixture
+const COORDINATE_DECIMALS: i32 = 1;
+
+/// A recorded fixture, as listed in the manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Fixture {
+    /// The file name of the fixture, relative to the manifest.
+    pub file: String,
+
+    /// What the fixture was recorded from, e.g. `"forecast"` or
+    /// `"time_machine"`.
+    pub kind: String,
+
+    /// When the fixture was recorded, in seconds since the UNIX epoch,
+    /// if known. Fixtures which predate the manifest leave it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recorded_at: Option<i64>
+}
+
+/// The list of fixtures in a directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    pub fixtures: Vec<Fixture>
+}
+
+impl Manifest {
+    /// Load the manifest of `dir`, or an empty one if there isn't one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest can't be read or parsed.
+    pub fn load(dir: &Path) -> io::Result<Manifest> {
+        match fs::read(dir.join(MANIFEST)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::from),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Write the manifest into `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest can't be written.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+
+        fs::write(dir.join(MANIFEST), json)
+    }
+
+    /// Anonymize `raw_json` and write it into `dir` as `fixture.file`,
+    /// replacing any fixture of the same name in the manifest, then save
+    /// the manifest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `fixture.file` isn't a plain file name,
+    /// `raw_json` isn't valid JSON, or the fixture or manifest can't be
+    /// written.
+    pub fn record(&mut self, dir: &Path, fixture: Fixture, raw_json: &str, api_key: &str)
+        -> io::Result<()> {
+        if !is_file_name(&fixture.file) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("fixture file {:?} isn't a plain file name", fixture.file)
+            ));
+        }
+
+        fs::write(dir.join(&fixture.file), anonymize(raw_json, api_key)?)?;
+
+        self.fixtures.retain(|f| f.file != fixture.file);
+        self.fixtures.push(fixture);
+        self.fixtures.sort_by(|a, b| a.file.cmp(&b.file));
+
+        self.save(dir)
+    }
+}
+
+/// Replace every occurrence of `api_key` in `raw_json` and round the
+/// response's coordinates to one decimal place, returning pretty-printed
+/// JSON.
+///
+/// # Errors
+///
+/// Returns an error if `raw_json` isn't valid JSON.
+pub fn anonymize(raw_json: &str, api_key: &str) -> Result<String, serde_json::Error> {
+    let mut value: Value = serde_json::from_str(raw_json)?;
+
+    if !api_key.is_empty() {
+        scrub(&mut value, api_key);
+    }
+
+    for coordinate in &["latitude", "longitude"] {
+        if let Some(degrees) = value.get_mut(*coordinate) {
+            if let Some(rounded) = degrees.as_f64().map(round_coordinate) {
+                *degrees = Value::from(rounded);
+            }
+        }
+    }
+
+    let mut json = serde_json::to_string_pretty(&value)?;
+    json.push('\n');
+
+    Ok(json)
+}
+
+// Whether `file` names a file directly inside the fixture directory.
+fn is_file_name(file: &str) -> bool {
+    !file.is_empty() && !file.contains(['/', '\\']) && !file.contains("..")
+}
+
+fn scrub(value: &mut Value, api_key: &str) {
+    match value {
+        Value::String(s) if s.contains(api_key) => *s = s.replace(api_key, REDACTED),
+        Value::Array(values) => values.iter_mut().for_each(|v| scrub(v, api_key)),
+        Value::Object(object) => object.values_mut().for_each(|v| scrub(v, api_key)),
+        _ => ()
+    }
+}
+
+fn round_coordinate(degrees: f64) -> f64 {
+    let scale = 10f64.powi(COORDINATE_DECIMALS);
+
+    (degrees * scale).round() / scale
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{anonymize, Fixture, Manifest};
+
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_anonymize() {
+        let json = anonymize(
+            "{\"latitude\":42.3736,\"longitude\":-71.1097,\
+             \"alerts\":[{\"uri\":\"https://example.com/?key=s3cr3t\"}]}",
+            "s3cr3t"
+        ).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["latitude"], 42.4);
+        assert_eq!(value["longitude"], -71.1);
+        assert_eq!(value["alerts"][0]["uri"], "https://example.com/?key=REDACTED");
+        assert!(anonymize("{", "s3cr3t").is_err());
+    }
+
+    #[test]
+    fn test_record() {
+        let dir = env::temp_dir().join(format!("forecast-fixtures-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let fixture = |recorded_at| Fixture {
+            file: "forecast.json".to_string(),
+            kind: "forecast".to_string(),
+            recorded_at: Some(recorded_at)
+        };
+
+        let mut manifest = Manifest::load(&dir).unwrap();
+
+        manifest.record(&dir, fixture(1), "{\"latitude\":1.23}", "key").unwrap();
+        manifest.record(&dir, fixture(2), "{\"latitude\":1.23}", "key").unwrap();
+
+        assert_eq!(Manifest::load(&dir).unwrap().fixtures, vec![fixture(2)]);
+        assert!(fs::read_to_string(dir.join("forecast.json")).unwrap().contains("1.2"));
+
+        for file in &["../forecast.json", "nested/forecast.json", "..", ""] {
+            let fixture = Fixture { file: file.to_string(), ..fixture(3) };
+
+            assert!(manifest.record(&dir, fixture, "{}", "key").is_err());
+        }
+
+        assert_eq!(Manifest::load(&dir).unwrap().fixtures, vec![fixture(2)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
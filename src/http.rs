@@ -0,0 +1,158 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The HTTP types which appear in the client's API.
+//!
+//! `Client`, `Response`, `Error` and `Certificate` wrap the types of the
+//! HTTP library the client is built on, so that upgrading that library
+//! doesn't change this crate's API. Status codes, headers and URLs are
+//! the types of the `http` and `url` crates, re-exported here.
+//!
+//! ```
+//! use forecast::ApiClient;
+//! use forecast::http::Client;
+//!
+//! let client = Client::new();
+//! let api_client = ApiClient::new(&client);
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{self, ParseError};
+
+pub use ::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+pub use ::http::StatusCode;
+pub use url::Url;
+
+/// A pool of HTTP connections to send requests with. Cloning a `Client`
+/// is cheap, and the clones share the pool.
+#[derive(Debug, Clone, Default)]
+pub struct Client(pub(crate) reqwest::Client);
+
+impl Client {
+    /// Construct a Client with default settings. Use `ApiClientBuilder`
+    /// to control pooling, timeouts, proxies and TLS.
+    pub fn new() -> Client {
+        Client::default()
+    }
+}
+
+/// A response returned by `ApiClient::get_forecast` or
+/// `ApiClient::get_time_machine`, whose body hasn't been read yet.
+#[derive(Debug)]
+pub struct Response(reqwest::Response);
+
+impl Response {
+    pub(crate) fn new(response: reqwest::Response) -> Response {
+        Response(response)
+    }
+
+    /// The status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.0.headers()
+    }
+
+    /// The URL the response came from, after any redirects.
+    pub fn url(&self) -> &Url {
+        self.0.url()
+    }
+
+    /// Read the body of the response.
+    pub async fn bytes(self) -> error::Result<Vec<u8>> {
+        Ok(self.0.bytes().await.map_err(Error::new)?.to_vec())
+    }
+
+    /// Read the body of the response as UTF-8 text.
+    pub async fn text(self) -> error::Result<String> {
+        Ok(self.0.text().await.map_err(Error::new)?)
+    }
+
+    /// Read the body of the response and deserialize it from JSON, e.g.
+    /// into an `ApiResponse`.
+    pub async fn json<T: DeserializeOwned>(self) -> error::Result<T> {
+        let body = self.bytes().await?;
+
+        Ok(ParseError::from_slice(&body)?)
+    }
+}
+
+/// A request which couldn't be sent, or a response which couldn't be
+/// read, e.g. because the connection failed or timed out.
+#[derive(Debug)]
+pub struct Error(reqwest::Error);
+
+impl Error {
+    pub(crate) fn new(error: reqwest::Error) -> Error {
+        Error(error)
+    }
+
+    /// Whether the request timed out.
+    pub fn is_timeout(&self) -> bool {
+        self.0.is_timeout()
+    }
+
+    /// Whether the connection to the server failed.
+    pub fn is_connect(&self) -> bool {
+        self.0.is_connect()
+    }
+
+    /// Whether reading the response body failed.
+    pub fn is_body(&self) -> bool {
+        self.0.is_body()
+    }
+
+    /// Whether decoding the response body failed.
+    pub fn is_decode(&self) -> bool {
+        self.0.is_decode()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// A root certificate to trust, for
+/// `ApiClientBuilder::add_root_certificate`. Requires a TLS backend
+/// feature.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[derive(Debug, Clone)]
+pub struct Certificate(pub(crate) reqwest::Certificate);
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+impl Certificate {
+    /// Parse a PEM-encoded certificate.
+    pub fn from_pem(pem: &[u8]) -> error::Result<Certificate> {
+        Ok(Certificate(reqwest::Certificate::from_pem(pem).map_err(Error::new)?))
+    }
+
+    /// Parse a DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> error::Result<Certificate> {
+        Ok(Certificate(reqwest::Certificate::from_der(der).map_err(Error::new)?))
+    }
+}
@@ -0,0 +1,132 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Interning of strings which repeat across many responses, such as the
+// data sources listed in `Flags`. Interned strings are shared per
+// thread, so parsing a long archive of responses allocates each
+// distinct source once. Without the `std` feature there are no thread
+// locals, and strings aren't interned.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+use serde::de::{Deserializer, SeqAccess, Visitor};
+
+// bounds memory use if a provider sends unbounded distinct strings
+#[cfg(feature = "std")]
+const MAX_INTERNED: usize = 1024;
+
+#[cfg(feature = "std")]
+thread_local! {
+    static INTERNED: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn intern(s: &str) -> Arc<str> {
+    Arc::from(s)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn intern(s: &str) -> Arc<str> {
+    INTERNED.with(|interned| {
+        let mut interned = interned.borrow_mut();
+
+        if let Some(existing) = interned.get(s) {
+            return existing.clone();
+        }
+
+        let new: Arc<str> = Arc::from(s);
+
+        if interned.len() < MAX_INTERNED {
+            interned.insert(new.clone());
+        }
+
+        new
+    })
+}
+
+struct InternedStr(Arc<str>);
+
+impl<'de> serde::Deserialize<'de> for InternedStr {
+    fn deserialize<D>(deserializer: D) -> Result<InternedStr, D::Error>
+        where D : Deserializer<'de> {
+        struct StrVisitor;
+
+        impl Visitor<'_> for StrVisitor {
+            type Value = InternedStr;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<InternedStr, E> {
+                Ok(InternedStr(intern(s)))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+pub(crate) fn deserialize_strs<'de, D>(deserializer: D) -> Result<Vec<Arc<str>>, D::Error>
+    where D : Deserializer<'de> {
+    struct SeqVisitor;
+
+    impl<'de> Visitor<'de> for SeqVisitor {
+        type Value = Vec<Arc<str>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a sequence of strings")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Vec<Arc<str>>, A::Error>
+            where A : SeqAccess<'de> {
+            let mut strs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+            while let Some(InternedStr(s)) = seq.next_element()? {
+                strs.push(s);
+            }
+
+            Ok(strs)
+        }
+    }
+
+    deserializer.deserialize_seq(SeqVisitor)
+}
+
+// unit tests
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::Flags;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sources_are_interned() {
+        let json = "{\"sources\":[\"isd\",\"cmc\"],\"units\":\"us\"}";
+
+        let first: Flags = serde_json::from_str(json).unwrap();
+        let second: Flags = serde_json::from_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(&*first.sources[0], "isd");
+        assert!(Arc::ptr_eq(&first.sources[0], &second.sources[0]));
+        assert!(Arc::ptr_eq(&first.sources[1], &second.sources[1]));
+    }
+}
@@ -0,0 +1,140 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Streaming ingestion of newline-delimited JSON response archives.
+//!
+//! `ApiResponse::from_jsonl_reader` returns a `JsonlResponses` iterator
+//! which parses one response per line as it is read, so an archive of
+//! any size can be processed in constant memory. A line which can't be
+//! parsed yields a `JsonlError` carrying its line number, and iteration
+//! continues with the next line. Blank lines are skipped.
+
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::ApiResponse;
+
+/// Iterator over the responses in a JSONL archive.
+#[derive(Debug)]
+pub struct JsonlResponses<R> {
+    reader: R,
+    line: usize,
+    buffer: String,
+    finished: bool
+}
+
+/// An error encountered while reading one line of a JSONL archive.
+#[derive(Debug)]
+pub struct JsonlError {
+    /// The line number, counting from 1.
+    pub line: usize,
+
+    pub kind: JsonlErrorKind
+}
+
+/// The reason a line of a JSONL archive couldn't be read.
+#[derive(Debug)]
+pub enum JsonlErrorKind {
+    Io(io::Error),
+
+    Parse(serde_json::Error)
+}
+
+impl<R: BufRead> JsonlResponses<R> {
+    pub(crate) fn new(reader: R) -> JsonlResponses<R> {
+        JsonlResponses { reader, line: 0, buffer: String::new(), finished: false }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonlResponses<R> {
+    type Item = Result<ApiResponse, JsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.buffer.clear();
+            self.line += 1;
+
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    let line = self.buffer.trim();
+
+                    if line.is_empty() {
+                        continue;
+                    }
+
+                    return Some(serde_json::from_str(line).map_err(|e| JsonlError {
+                        line: self.line,
+                        kind: JsonlErrorKind::Parse(e)
+                    }));
+                },
+                Err(e) => {
+                    // invalid UTF-8 fails only this line, but any other
+                    // I/O error ends the stream
+                    self.finished = e.kind() != io::ErrorKind::InvalidData;
+
+                    return Some(Err(JsonlError { line: self.line, kind: JsonlErrorKind::Io(e) }));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for JsonlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            JsonlErrorKind::Io(e) => write!(f, "line {}: couldn't read: {}", self.line, e),
+            JsonlErrorKind::Parse(e) => write!(f, "line {}: couldn't parse: {}", self.line, e)
+        }
+    }
+}
+
+impl std::error::Error for JsonlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            JsonlErrorKind::Io(e) => Some(e),
+            JsonlErrorKind::Parse(e) => Some(e)
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::ApiResponse;
+
+    use super::JsonlErrorKind;
+
+    #[test]
+    fn test_from_jsonl_reader() {
+        let archive = "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0}\n\
+                       \n\
+                       {\"latitude\":\n\
+                       {\"latitude\":3.0,\"longitude\":4.0,\"timezone\":\"UTC\",\"offset\":0}\n";
+
+        let results: Vec<_> = ApiResponse::from_jsonl_reader(archive.as_bytes()).collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().latitude, 1.0);
+
+        let error = results[1].as_ref().unwrap_err();
+
+        assert_eq!(error.line, 3);
+        assert!(matches!(error.kind, JsonlErrorKind::Parse(_)));
+        assert_eq!(results[2].as_ref().unwrap().latitude, 3.0);
+    }
+}
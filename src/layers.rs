@@ -0,0 +1,470 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The client's retry, caching and rate limiting as tower `Layer`s,
+//! available with the `tower` feature, for stacks which need to order
+//! and configure them explicitly:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! use tower::ServiceBuilder;
+//!
+//! use forecast::layers::{ApiService, CacheLayer, RateLimitLayer, RetryLayer};
+//! use forecast::{ApiClient, RateLimiter, RetryPolicy};
+//!
+//! let service = ServiceBuilder::new()
+//!     .layer(CacheLayer::new(Duration::from_secs(600)))
+//!     .layer(RetryLayer::new(RetryPolicy::new(3, Duration::from_millis(500))))
+//!     .layer(RateLimitLayer::new(Arc::new(RateLimiter::new(10))))
+//!     .service(ApiService::new(Arc::new(ApiClient::default())));
+//! ```
+//!
+//! `ApiService` sends `ForecastRequest<'static>`s and
+//! `TimeMachineRequest<'static>`s with an `ApiClient`, responding with
+//! `Sourced<ApiResponse>`. Configure that client without a retry policy
+//! or rate limiter of its own, or requests are retried and limited
+//! twice.
+
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tower::{Layer, Service};
+
+use url::Url;
+
+use crate::error::{Error, ErrorKind};
+use crate::sourced::{CacheStatus, Sourced};
+use crate::{ApiClient, ApiResponse, ForecastRequest, RateLimiter, RetryPolicy, TimeMachineRequest};
+
+/// The future returned by the services of this module.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send>>;
+
+/// A request which can pass through the layers of this module.
+pub trait ApiRequest : Clone + Send + 'static {
+    /// The API key the request is sent with, which the rate limit is
+    /// applied to.
+    fn api_key(&self) -> &str;
+
+    /// The URL the request is sent to, which responses are cached by.
+    fn url(&self) -> Url;
+}
+
+impl ApiRequest for ForecastRequest<'static> {
+    fn api_key(&self) -> &str {
+        self.api_key
+    }
+
+    fn url(&self) -> Url {
+        ForecastRequest::url(self)
+    }
+}
+
+impl ApiRequest for TimeMachineRequest<'static> {
+    fn api_key(&self) -> &str {
+        self.api_key
+    }
+
+    fn url(&self) -> Url {
+        TimeMachineRequest::url(self)
+    }
+}
+
+/// Sends requests with an `ApiClient`.
+#[derive(Debug, Clone)]
+pub struct ApiService {
+    client: Arc<ApiClient<'static>>
+}
+
+impl ApiService {
+    pub fn new(client: Arc<ApiClient<'static>>) -> ApiService {
+        ApiService { client }
+    }
+}
+
+impl Service<ForecastRequest<'static>> for ApiService {
+    type Response = Sourced<ApiResponse>;
+    type Error = Error;
+    type Future = BoxFuture<Sourced<ApiResponse>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: ForecastRequest<'static>) -> Self::Future {
+        let client = self.client.clone();
+
+        Box::pin(async move { client.get_forecast_sourced(request).await })
+    }
+}
+
+impl Service<TimeMachineRequest<'static>> for ApiService {
+    type Response = Sourced<ApiResponse>;
+    type Error = Error;
+    type Future = BoxFuture<Sourced<ApiResponse>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: TimeMachineRequest<'static>) -> Self::Future {
+        let client = self.client.clone();
+
+        Box::pin(async move { client.get_time_machine_sourced(request).await })
+    }
+}
+
+/// Retries failed requests according to a `RetryPolicy`.
+#[derive(Debug, Clone)]
+pub struct RetryLayer {
+    policy: RetryPolicy
+}
+
+impl RetryLayer {
+    pub fn new(policy: RetryPolicy) -> RetryLayer {
+        RetryLayer { policy }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = Retry<S>;
+
+    fn layer(&self, inner: S) -> Retry<S> {
+        Retry { inner, policy: self.policy.clone() }
+    }
+}
+
+/// The service produced by `RetryLayer`.
+#[derive(Debug, Clone)]
+pub struct Retry<S> {
+    inner: S,
+    policy: RetryPolicy
+}
+
+impl<S, R> Service<R> for Retry<S>
+    where S : Service<R, Error = Error> + Clone + Send + 'static,
+          S::Response : Send,
+          S::Future : Send,
+          R : ApiRequest {
+    type Response = S::Response;
+    type Error = Error;
+    type Future = BoxFuture<S::Response>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let policy = self.policy.clone();
+
+        Box::pin(async move {
+            let mut attempt = 0;
+
+            loop {
+                poll_fn(|cx| inner.poll_ready(cx)).await?;
+
+                match inner.call(request.clone()).await {
+                    Err(e) if policy.should_retry(&e, attempt) => {
+                        tokio::time::sleep(policy.delay(&e, attempt)).await;
+                        attempt += 1;
+                    },
+                    result => return result
+                }
+            }
+        })
+    }
+}
+
+/// Delays requests to stay within the limits of a `RateLimiter`, and
+/// fails them with `ErrorKind::QuotaExhausted` once the daily quota of
+/// their API key is spent.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>
+}
+
+impl RateLimitLayer {
+    pub fn new(limiter: Arc<RateLimiter>) -> RateLimitLayer {
+        RateLimitLayer { limiter }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> RateLimit<S> {
+        RateLimit { inner, limiter: self.limiter.clone() }
+    }
+}
+
+/// The service produced by `RateLimitLayer`.
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>
+}
+
+impl<S, R> Service<R> for RateLimit<S>
+    where S : Service<R, Error = Error> + Clone + Send + 'static,
+          S::Response : Send,
+          S::Future : Send,
+          R : ApiRequest {
+    type Response = S::Response;
+    type Error = Error;
+    type Future = BoxFuture<S::Response>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let acquired = self.limiter.acquire(request.api_key());
+
+        Box::pin(async move {
+            acquired.await.map_err(ErrorKind::QuotaExhausted)?;
+            poll_fn(|cx| inner.poll_ready(cx)).await?;
+
+            inner.call(request).await
+        })
+    }
+}
+
+type Entries = Arc<Mutex<HashMap<String, Sourced<ApiResponse>>>>;
+
+/// Serves responses from memory while they are younger than `max_age`,
+/// keyed by request URL. Every service made by one layer shares its
+/// cache. Stale responses are dropped whenever a new one is cached, so
+/// the cache only holds responses fetched within the last `max_age`.
+#[derive(Debug, Clone)]
+pub struct CacheLayer {
+    max_age: Duration,
+    entries: Entries
+}
+
+impl CacheLayer {
+    pub fn new(max_age: Duration) -> CacheLayer {
+        CacheLayer { max_age, entries: Arc::default() }
+    }
+}
+
+impl<S> Layer<S> for CacheLayer {
+    type Service = Cache<S>;
+
+    fn layer(&self, inner: S) -> Cache<S> {
+        Cache { inner, max_age: self.max_age, entries: self.entries.clone() }
+    }
+}
+
+/// The service produced by `CacheLayer`.
+#[derive(Debug, Clone)]
+pub struct Cache<S> {
+    inner: S,
+    max_age: Duration,
+    entries: Entries
+}
+
+impl<S> Cache<S> {
+    fn cached(&self, key: &str) -> Option<Sourced<ApiResponse>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        entries.get(key)
+            .filter(|response| !response.is_stale(self.max_age))
+            .cloned()
+    }
+}
+
+fn insert(entries: &Entries, max_age: Duration, key: String, response: Sourced<ApiResponse>) {
+    let mut entries = entries.lock().unwrap_or_else(|e| e.into_inner());
+
+    entries.retain(|_, cached| !cached.is_stale(max_age));
+    entries.insert(key, response);
+}
+
+impl<S, R> Service<R> for Cache<S>
+    where S : Service<R, Response = Sourced<ApiResponse>, Error = Error> + Clone + Send + 'static,
+          S::Future : Send,
+          R : ApiRequest {
+    type Response = Sourced<ApiResponse>;
+    type Error = Error;
+    type Future = BoxFuture<Sourced<ApiResponse>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let key = request.url().to_string();
+
+        if let Some(mut response) = self.cached(&key) {
+            response.cache_status = CacheStatus::Hit;
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let mut inner = self.inner.clone();
+        let max_age = self.max_age;
+        let entries = self.entries.clone();
+
+        Box::pin(async move {
+            poll_fn(|cx| inner.poll_ready(cx)).await?;
+
+            let response = inner.call(request).await?;
+
+            insert(&entries, max_age, key, response.clone());
+
+            Ok(response)
+        })
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiRequest, BoxFuture, CacheLayer, RateLimitLayer, RetryLayer};
+
+    use crate::error::{Error, ErrorKind};
+    use crate::http::StatusCode;
+    use crate::sourced::{CacheStatus, Sourced};
+    use crate::{ApiResponse, ForecastRequest, ForecastRequestBuilder, RateLimiter, RetryPolicy};
+
+    use url::Url;
+
+    use std::future::poll_fn;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use tower::{Layer, Service};
+
+    static API_KEY: &str = "some_api_key";
+
+    // Fails with a 503 until it has been called `failures` times.
+    #[derive(Clone)]
+    struct Flaky {
+        calls: Arc<AtomicUsize>,
+        failures: usize
+    }
+
+    impl Flaky {
+        fn new(failures: usize) -> Flaky {
+            Flaky { calls: Arc::default(), failures }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Service<ForecastRequest<'static>> for Flaky {
+        type Response = Sourced<ApiResponse>;
+        type Error = Error;
+        type Future = BoxFuture<Sourced<ApiResponse>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: ForecastRequest<'static>) -> Self::Future {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let failures = self.failures;
+
+            Box::pin(async move {
+                if call < failures {
+                    return Err(ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE).into());
+                }
+
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+                Ok(Sourced::new(ApiResponse::default(), &request.url(), request.api_key(), now))
+            })
+        }
+    }
+
+    async fn send<S>(service: &mut S) -> Result<Sourced<ApiResponse>, Error>
+        where S : Service<ForecastRequest<'static>, Response = Sourced<ApiResponse>, Error = Error> {
+        poll_fn(|cx| service.poll_ready(cx)).await?;
+        service.call(ForecastRequestBuilder::new(API_KEY, 1.0, 2.0).build()).await
+    }
+
+    #[tokio::test]
+    async fn test_retry_layer() {
+        let flaky = Flaky::new(2);
+        let mut service = RetryLayer::new(RetryPolicy::new(2, Duration::from_millis(1)))
+            .layer(flaky.clone());
+
+        assert!(send(&mut service).await.is_ok());
+        assert_eq!(flaky.calls(), 3);
+
+        let flaky = Flaky::new(2);
+        let mut service = RetryLayer::new(RetryPolicy::new(1, Duration::from_millis(1)))
+            .layer(flaky.clone());
+
+        assert!(matches!(send(&mut service).await.unwrap_err().kind(), ErrorKind::Status(_)));
+        assert_eq!(flaky.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_layer() {
+        let flaky = Flaky::new(0);
+        let layer = CacheLayer::new(Duration::from_secs(60));
+        let mut service = layer.layer(flaky.clone());
+
+        assert_eq!(send(&mut service).await.unwrap().cache_status, CacheStatus::Miss);
+
+        let mut shared = layer.layer(flaky.clone());
+
+        assert_eq!(send(&mut shared).await.unwrap().cache_status, CacheStatus::Hit);
+        assert_eq!(flaky.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_layer_evicts_stale() {
+        let layer = CacheLayer::new(Duration::from_secs(60));
+        let url = Url::parse("https://api.darksky.net/forecast/some_api_key/3,4").unwrap();
+        let stale = Sourced::new(ApiResponse::default(), &url, API_KEY, 0);
+
+        layer.entries.lock().unwrap().insert(url.to_string(), stale);
+
+        let mut service = layer.layer(Flaky::new(0));
+
+        assert_eq!(send(&mut service).await.unwrap().cache_status, CacheStatus::Miss);
+
+        let entries = layer.entries.lock().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!(!entries.contains_key(url.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_layer() {
+        let flaky = Flaky::new(0);
+        let limiter = Arc::new(RateLimiter::new(10).daily_quota(1));
+        let mut service = RateLimitLayer::new(limiter).layer(flaky.clone());
+
+        assert!(send(&mut service).await.is_ok());
+        let error = send(&mut service).await.unwrap_err();
+
+        assert!(matches!(error.kind(), ErrorKind::QuotaExhausted(_)));
+        assert_eq!(flaky.calls(), 1);
+    }
+}
@@ -0,0 +1,950 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The data model objects of API responses, and the enums shared with
+//! requests. Everything here is re-exported at the crate root, and is
+//! available without the `std` feature.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::sync::Arc;
+use core::option::Option;
+use core::ops::{Index, Range};
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+use serde::de::{Deserialize, Deserializer, IntoDeserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::datetime;
+#[cfg(feature = "std")]
+use crate::jsonl::JsonlResponses;
+
+// constants
+
+// 49 hourly points: the current hour and the 48 after it
+const STANDARD_HOURLY_SPAN: i64 = 49 * 3600;
+
+// thresholds used by DataPoint::inferred_icon
+
+const INFERRED_PRECIP_PROBABILITY: f64 = 0.5;
+const INFERRED_CLOUDY_COVER: f64 = 0.75;
+const INFERRED_PARTLY_CLOUDY_COVER: f64 = 0.4;
+
+/// Model object representing an icon for display.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Icon {
+    #[serde(rename = "clear-day")]
+    ClearDay,
+
+    #[serde(rename = "clear-night")]
+    ClearNight,
+
+    #[serde(rename = "rain")]
+    Rain,
+
+    #[serde(rename = "snow")]
+    Snow,
+
+    #[serde(rename = "sleet")]
+    Sleet,
+
+    #[serde(rename = "wind")]
+    Wind,
+
+    #[serde(rename = "fog")]
+    Fog,
+
+    #[serde(rename = "cloudy")]
+    Cloudy,
+
+    #[serde(rename = "partly-cloudy-day")]
+    PartlyCloudyDay,
+
+    #[serde(rename = "partly-cloudy-night")]
+    PartlyCloudyNight,
+
+    #[serde(rename = "hail")]
+    Hail,
+
+    #[serde(rename = "thunderstorm")]
+    Thunderstorm,
+
+    #[serde(rename = "tornado")]
+    Tornado
+}
+
+/// Model object representing the kind of precipitation occurring at a particular
+/// time.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrecipType {
+    #[serde(rename = "rain")]
+    Rain,
+
+    #[serde(rename = "snow")]
+    Snow,
+
+    #[serde(rename = "sleet")]
+    Sleet
+}
+
+
+
+/// Model object representing a DataBlock to exclude from the response.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExcludeBlock {
+    #[serde(rename = "currently")]
+    Currently,
+
+    #[serde(rename = "minutely")]
+    Minutely,
+
+    #[serde(rename = "hourly")]
+    Hourly,
+
+    #[serde(rename = "daily")]
+    Daily,
+
+    #[serde(rename = "alerts")]
+    Alerts,
+
+    #[serde(rename = "flags")]
+    Flags
+}
+
+/// When present in a request, this feature causes response data to be reported
+/// for 168 hours into the future instead of 48 hours.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExtendBy {
+    #[serde(rename = "hourly")]
+    Hourly
+}
+
+/// Model object representing language.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(remote = "Lang")]
+pub enum Lang {
+    #[serde(rename = "ar")]
+    Arabic,
+
+    #[serde(rename = "az")]
+    Azerbaijani,
+
+    #[serde(rename = "be")]
+    Belarusian,
+
+    #[serde(rename = "bg")]
+    Bulgarian,
+
+    #[serde(rename = "bs")]
+    Bosnian,
+
+    #[serde(rename = "ca")]
+    Catalan,
+
+    #[serde(rename = "cz")]
+    Czech,
+
+    #[serde(rename = "da")]
+    Danish,
+
+    #[serde(rename = "de")]
+    German,
+
+    #[serde(rename = "el")]
+    Greek,
+
+    #[serde(rename = "en")]
+    English,
+
+    #[serde(rename = "es")]
+    Spanish,
+
+    #[serde(rename = "et")]
+    Estonian,
+
+    #[serde(rename = "fi")]
+    Finnish,
+
+    #[serde(rename = "fr")]
+    French,
+
+    #[serde(rename = "hr")]
+    Croatian,
+
+    #[serde(rename = "hu")]
+    Hungarian,
+
+    #[serde(rename = "id")]
+    Indonesian,
+
+    #[serde(rename = "is")]
+    Icelandic,
+
+    #[serde(rename = "it")]
+    Italian,
+
+    #[serde(rename = "ja")]
+    Japanese,
+
+    #[serde(rename = "ka")]
+    Georgian,
+
+    #[serde(rename = "ko")]
+    Korean,
+
+    #[serde(rename = "kw")]
+    Cornish,
+
+    // can also be deserialized from "no", but will always be serialized to "nb"
+    #[serde(rename = "nb")]
+    NorwegianBokmal,
+
+    #[serde(rename = "nl")]
+    Dutch,
+
+    #[serde(rename = "pl")]
+    Polish,
+
+    #[serde(rename = "pt")]
+    Portuguese,
+
+    #[serde(rename = "ro")]
+    Romanian,
+
+    #[serde(rename = "ru")]
+    Russian,
+
+    #[serde(rename = "sk")]
+    Slovak,
+
+    #[serde(rename = "sl")]
+    Slovenian,
+
+    #[serde(rename = "sr")]
+    Serbian,
+
+    #[serde(rename = "sv")]
+    Swedish,
+
+    #[serde(rename = "tet")]
+    Tetum,
+
+    #[serde(rename = "tr")]
+    Turkish,
+
+    #[serde(rename = "uk")]
+    Ukranian,
+
+    #[serde(rename = "x-pig-latin")]
+    IgpayAtinlay,
+
+    #[serde(rename = "zh")]
+    SimplifiedChinese,
+
+    #[serde(rename = "zh-tw")]
+    TraditionalChinese
+}
+
+// This is needed to take into account the aliasing of "nb" as "no".
+// See https://github.com/serde-rs/serde/issues/1174#issuecomment-372411280
+impl <'de> Deserialize<'de> for Lang {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let value = String::deserialize(deserializer)?;
+
+        if value == "no" {
+            Ok(Lang::NorwegianBokmal)
+        } else {
+            Lang::deserialize(value.into_deserializer())
+        }
+    }
+}
+
+// This is needed to take into account the aliasing of "nb" as "no".
+// See https://github.com/serde-rs/serde/issues/1174#issuecomment-372411280
+impl Serialize for Lang {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        Lang::serialize(self, serializer)
+    }
+}
+
+/// Model object representing measurement units. Defaults to `Imperial`,
+/// as does the API.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Units {
+    #[serde(rename = "auto")]
+    Auto,
+
+    #[serde(rename = "ca")]
+    CA,
+
+    #[serde(rename = "uk2")]
+    UK,
+
+    #[default]
+    #[serde(rename = "us")]
+    Imperial,
+
+    #[serde(rename = "si")]
+    SI
+}
+
+/// Model object representing an Alert's severity. Defaults to the least
+/// severe, `Advisory`.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Severity {
+    #[default]
+    #[serde(rename = "advisory")]
+    Advisory,
+
+    #[serde(rename = "watch")]
+    Watch,
+
+    #[serde(rename = "warning")]
+    Warning
+}
+
+/// The current conditions at a location, the `currently` block of a
+/// forecast.
+pub type CurrentlyDataPoint = DataPoint;
+
+/// The forecast for one day, a point of the `daily` block of a forecast.
+pub type DailyDataPoint = DataPoint;
+
+/// Model object containing various properties, each representing the average
+/// (unless otherwise specified) of a particular weather phenomenon occurring
+/// during a period of time.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct DataPoint {
+    #[serde(rename = "apparentTemperature")]
+    pub apparent_temperature: Option<f64>,
+
+    #[serde(rename = "apparentTemperatureHigh")]
+    pub apparent_temperature_high: Option<f64>,
+
+    #[serde(rename = "apparentTemperatureHighTime")]
+    pub apparent_temperature_high_time: Option<i64>,
+
+    #[serde(rename = "apparentTemperatureLow")]
+    pub apparent_temperature_low: Option<f64>,
+
+    #[serde(rename = "apparentTemperatureLowTime")]
+    pub apparent_temperature_low_time: Option<i64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "apparentTemperatureMax")]
+    pub apparent_temperature_max: Option<f64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "apparentTemperatureMaxTime")]
+    pub apparent_temperature_max_time: Option<i64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "apparentTemperatureMin")]
+    pub apparent_temperature_min: Option<f64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "apparentTemperatureMinTime")]
+    pub apparent_temperature_min_time: Option<i64>,
+
+    /// Convective available potential energy, in J/kg, a measure of the
+    /// instability which drives thunderstorms. Only reported by some
+    /// compatible providers.
+    pub cape: Option<f64>,
+
+    #[serde(rename = "cloudCover")]
+    pub cloud_cover: Option<f64>,
+
+    #[serde(rename = "dewPoint")]
+    pub dew_point: Option<f64>,
+
+    pub humidity: Option<f64>,
+
+    pub icon: Option<Icon>,
+
+    #[serde(rename = "moonPhase")]
+    pub moon_phase: Option<f64>,
+
+    #[serde(rename = "nearestStormBearing")]
+    pub nearest_storm_bearing: Option<f64>,
+
+    #[serde(rename = "nearestStormDistance")]
+    pub nearest_storm_distance: Option<f64>,
+
+    pub ozone: Option<f64>,
+
+    #[serde(rename = "precipAccumulation")]
+    pub precip_accumulation: Option<f64>,
+
+    #[serde(rename = "precipIntensity")]
+    pub precip_intensity: Option<f64>,
+
+    #[serde(rename = "precipIntensityMax")]
+    pub precip_intensity_max: Option<f64>,
+
+    #[serde(rename = "precipIntensityMaxTime")]
+    pub precip_intensity_max_time: Option<i64>,
+
+    #[serde(rename = "precipProbability")]
+    pub precip_probability: Option<f64>,
+
+    #[serde(rename = "precipType")]
+    pub precip_type: Option<PrecipType>,
+
+    pub pressure: Option<f64>,
+
+    pub summary: Option<String>,
+
+    #[serde(rename = "sunriseTime")]
+    pub sunrise_time: Option<i64>,
+
+    #[serde(rename = "sunsetTime")]
+    pub sunset_time: Option<i64>,
+
+    /// The direction the swell comes from, in degrees clockwise from
+    /// true north. Only reported by some compatible providers.
+    #[serde(rename = "swellDirection")]
+    pub swell_direction: Option<f64>,
+
+    /// In meters, or feet in US units. Only reported by some compatible
+    /// providers.
+    #[serde(rename = "swellHeight")]
+    pub swell_height: Option<f64>,
+
+    /// In seconds. Only reported by some compatible providers.
+    #[serde(rename = "swellPeriod")]
+    pub swell_period: Option<f64>,
+
+    pub temperature: Option<f64>,
+
+    #[serde(rename = "temperatureHigh")]
+    pub temperature_high: Option<f64>,
+
+    #[serde(rename = "temperatureHighTime")]
+    pub temperature_high_time: Option<i64>,
+
+    #[serde(rename = "temperatureLow")]
+    pub temperature_low: Option<f64>,
+
+    #[serde(rename = "temperatureLowTime")]
+    pub temperature_low_time: Option<i64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "temperatureMax")]
+    pub temperature_max: Option<f64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "temperatureMaxTime")]
+    pub temperature_max_time: Option<i64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "temperatureMin")]
+    pub temperature_min: Option<f64>,
+
+    #[deprecated(since = "1.0.0")]
+    #[serde(rename = "temperatureMinTime")]
+    pub temperature_min_time: Option<i64>,
+
+    pub time: i64,
+
+    #[serde(rename = "uvIndex")]
+    pub uv_index: Option<f64>,
+
+    #[serde(rename = "uvIndexTime")]
+    pub uv_index_time: Option<i64>,
+
+    pub visibility: Option<f64>,
+
+    /// The significant height of combined wind waves and swell, in
+    /// meters, or feet in US units. Only reported by some compatible
+    /// providers.
+    #[serde(rename = "waveHeight")]
+    pub wave_height: Option<f64>,
+
+    /// In seconds. Only reported by some compatible providers.
+    #[serde(rename = "wavePeriod")]
+    pub wave_period: Option<f64>,
+
+    #[serde(rename = "windBearing")]
+    pub wind_bearing: Option<f64>,
+
+    #[serde(rename = "windGust")]
+    pub wind_gust: Option<f64>,
+
+    #[serde(rename = "windGustTime")]
+    pub wind_gust_time: Option<i64>,
+
+    #[serde(rename = "windSpeed")]
+    pub wind_speed: Option<f64>
+}
+
+impl DataPoint {
+    /// Return the icon reported by the API or, when the API omitted
+    /// it, a best-effort `Icon` derived from the precipitation type
+    /// and intensity, cloud cover, and (when `sunrise_time` and
+    /// `sunset_time` are present) whether it is day or night.
+    pub fn inferred_icon(&self) -> Icon {
+        if let Some(icon) = &self.icon {
+            return *icon;
+        }
+
+        let precipitating = self.precip_intensity.is_some_and(|i| i > 0.0)
+            || self.precip_probability
+                .is_some_and(|p| p >= INFERRED_PRECIP_PROBABILITY);
+
+        if precipitating {
+            return match self.precip_type {
+                Some(PrecipType::Snow) => Icon::Snow,
+                Some(PrecipType::Sleet) => Icon::Sleet,
+                _ => Icon::Rain
+            };
+        }
+
+        let daytime = match (self.sunrise_time, self.sunset_time) {
+            (Some(sunrise), Some(sunset)) => self.time >= sunrise && self.time < sunset,
+            _ => true
+        };
+
+        match self.cloud_cover {
+            Some(c) if c >= INFERRED_CLOUDY_COVER => Icon::Cloudy,
+            Some(c) if c >= INFERRED_PARTLY_CLOUDY_COVER => if daytime {
+                Icon::PartlyCloudyDay
+            } else {
+                Icon::PartlyCloudyNight
+            },
+            _ => if daytime { Icon::ClearDay } else { Icon::ClearNight }
+        }
+    }
+}
+
+// e.g. "2018-01-22T09:00:00+00:00 3.2° rain"
+fn write_point(f: &mut core::fmt::Formatter, point: &DataPoint, offset_seconds: i64) -> core::fmt::Result {
+    f.write_str(&datetime::to_rfc3339(point.time, offset_seconds))?;
+
+    match (point.temperature, point.temperature_high, point.temperature_low) {
+        (Some(temperature), _, _) => write!(f, " {:.1}°", temperature)?,
+        (None, Some(high), Some(low)) => write!(f, " {:.1}°/{:.1}°", high, low)?,
+        _ => ()
+    }
+
+    if let Some(icon) = &point.icon {
+        write!(f, " {}", serde_json::to_string(icon).unwrap().trim_matches('"'))?;
+    }
+
+    Ok(())
+}
+
+impl core::fmt::Display for DataPoint {
+    /// A compact one-line summary for logging: the time in UTC, the
+    /// temperature (or the high and low of a day) and the icon, e.g.
+    /// `2018-01-22T09:00:00+00:00 3.2° rain`.
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write_point(f, self, 0)
+    }
+}
+
+/// Model object representing the various weather phenomena occurring over a
+/// period of time.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct DataBlock {
+    pub data: Vec<DataPoint>,
+
+    pub summary: Option<String>,
+
+    pub icon: Option<Icon>
+}
+
+impl DataBlock {
+    /// The number of DataPoints in this block.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether this block has no DataPoints.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The earliest DataPoint in this block.
+    pub fn first(&self) -> Option<&DataPoint> {
+        self.data.first()
+    }
+
+    /// The latest DataPoint in this block.
+    pub fn last(&self) -> Option<&DataPoint> {
+        self.data.last()
+    }
+
+    /// Iterate over the DataPoints in this block.
+    pub fn iter(&self) -> core::slice::Iter<'_, DataPoint> {
+        self.data.iter()
+    }
+
+    /// The time window covered by this block: from the start of its
+    /// first DataPoint to the end of its last, assuming the points are
+    /// evenly spaced. A block with a single point covers just that
+    /// point's start time.
+    pub fn span(&self) -> Option<Range<i64>> {
+        let first = self.data.first()?;
+        let last = self.data.last()?;
+        let step = self.data.get(1).map_or(0, |second| second.time - first.time);
+
+        Some(first.time..last.time + step)
+    }
+
+    /// Whether this block has data for the whole of `range`.
+    pub fn covers(&self, range: Range<i64>) -> bool {
+        self.span().is_some_and(|span| span.start <= range.start && range.end <= span.end)
+    }
+}
+
+impl IntoIterator for DataBlock {
+    type Item = DataPoint;
+    type IntoIter = alloc::vec::IntoIter<DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DataBlock {
+    type Item = &'a DataPoint;
+    type IntoIter = core::slice::Iter<'a, DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl Index<usize> for DataBlock {
+    type Output = DataPoint;
+
+    fn index(&self, index: usize) -> &DataPoint {
+        &self.data[index]
+    }
+}
+
+/// Model object representing a severe weather warning issued by a government
+/// authority for the requested location.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Alert {
+    pub description: String,
+
+    pub expires: i64,
+
+    pub regions: Vec<String>,
+
+    pub severity: Severity,
+
+    pub time: i64,
+
+    pub title: String,
+
+    pub uri: String
+}
+
+/// Model object representing a flag which contains miscellaneous metadata about
+/// a request.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Flags {
+    #[serde(rename = "darksky-unavailable")]
+    pub darksky_unavailable: Option<String>,
+
+    #[serde(deserialize_with = "crate::intern::deserialize_strs")]
+    pub sources: Vec<Arc<str>>,
+
+    pub units: Units
+}
+
+/// Model object representing a Forecast or Time Machine API response.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct ApiResponse {
+    pub latitude: f64,
+
+    pub longitude: f64,
+
+    pub timezone: String,
+
+    #[deprecated(since = "1.0.0")]
+    pub offset: i64,
+
+    pub currently: Option<DataPoint>,
+
+    pub minutely: Option<DataBlock>,
+
+    pub hourly: Option<DataBlock>,
+
+    pub daily: Option<DataBlock>,
+
+    pub alerts: Option<Vec<Alert>>,
+
+    pub flags: Option<Flags>
+}
+
+impl ApiResponse {
+    /// The time windows covered by the minutely, hourly and daily
+    /// blocks of this response, e.g. to detect that the hourly forecast
+    /// stops after 48 hours and should be re-requested with
+    /// `ExtendBy::Hourly`.
+    pub fn forecast_horizon(&self) -> ForecastHorizon {
+        ForecastHorizon {
+            minutely: self.minutely.as_ref().and_then(DataBlock::span),
+            hourly: self.hourly.as_ref().and_then(DataBlock::span),
+            daily: self.daily.as_ref().and_then(DataBlock::span)
+        }
+    }
+
+    /// Return any conditions reported by the API which indicate that the
+    /// data in this response is degraded.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.flags.iter()
+            .filter_map(|flags| flags.darksky_unavailable.clone())
+            .map(Warning::DarkSkyUnavailable)
+            .collect()
+    }
+
+    /// Stream-parse a newline-delimited JSON archive of responses, one
+    /// response per line. See the `jsonl` module.
+    #[cfg(feature = "std")]
+    pub fn from_jsonl_reader<R: BufRead>(reader: R) -> JsonlResponses<R> {
+        JsonlResponses::new(reader)
+    }
+}
+
+impl core::fmt::Display for ApiResponse {
+    /// A compact one-line summary for logging: the location and the
+    /// current conditions in local time, e.g. `42.3601,-71.0589
+    /// America/New_York 2018-01-22T04:00:00-05:00 3.2° rain`.
+    #[allow(deprecated)]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:.4},{:.4} {}", self.latitude, self.longitude, self.timezone)?;
+
+        match &self.currently {
+            Some(currently) => {
+                f.write_str(" ")?;
+                write_point(f, currently, self.offset * 3600)
+            },
+            None => f.write_str(" (no current conditions)")
+        }
+    }
+}
+
+/// The time windows covered by the blocks of an `ApiResponse`, as
+/// returned by `ApiResponse::forecast_horizon`. Each window is `None`
+/// if the block is absent or empty.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ForecastHorizon {
+    /// Normally 61 one-minute points, i.e. an hour.
+    pub minutely: Option<Range<i64>>,
+
+    /// Normally 49 hourly points (48 hours), or 169 (168 hours) when
+    /// extended with `ExtendBy::Hourly`.
+    pub hourly: Option<Range<i64>>,
+
+    /// Normally 8 daily points.
+    pub daily: Option<Range<i64>>
+}
+
+impl ForecastHorizon {
+    /// Whether the hourly block runs past the standard 48 hours, i.e.
+    /// the forecast was extended.
+    pub fn is_hourly_extended(&self) -> bool {
+        self.hourly.as_ref().is_some_and(|hourly| hourly.end - hourly.start > STANDARD_HOURLY_SPAN)
+    }
+}
+
+/// Model object representing a condition which degrades the quality of
+/// the data in a response.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Warning {
+    /// The Dark Sky data source is unavailable for the requested location,
+    /// as reported by the `darksky-unavailable` flag. Carries the value
+    /// of the flag.
+    DarkSkyUnavailable(String)
+}
+
+impl core::fmt::Display for Warning {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Warning::DarkSkyUnavailable(reason) => write!(f, "darksky-unavailable: {}", reason)
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{Alert, ApiResponse, DataBlock, DataPoint, Flags, Icon, Lang, Severity, Units, Warning};
+
+    use crate::testing;
+
+    use std::vec::Vec;
+
+    // Test that we can deserialize and serialize both variants "nb" and "no"
+    #[test]
+    fn test_norwegian_lang_serde() {
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct TestStruct {
+            no: Lang,
+            nb: Lang,
+            en: Lang
+        }
+
+        let test_json = "{\"nb\":\"nb\",\"no\":\"no\",\"en\":\"en\"}";
+
+        let test_struct: TestStruct = serde_json::from_str(test_json).unwrap();
+
+        assert_eq!(test_struct.nb, Lang::NorwegianBokmal);
+        assert_eq!(test_struct.no, Lang::NorwegianBokmal);
+        assert_eq!(test_struct.en, Lang::English);
+
+        let test_struct_serialized = serde_json::to_string(&test_struct).unwrap();
+
+        let test_struct_deserialized: TestStruct = serde_json::from_str(
+            test_struct_serialized.as_str()
+        ).unwrap();
+
+        assert_eq!(test_struct_deserialized.nb, Lang::NorwegianBokmal);
+        assert_eq!(test_struct_deserialized.no, Lang::NorwegianBokmal);
+        assert_eq!(test_struct_deserialized.en, Lang::English);
+    }
+
+    // tests for data model helpers
+
+    #[test]
+    fn test_inferred_icon() {
+        let reported: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"icon\":\"fog\",\"cloudCover\":1.0}"
+        ).unwrap();
+
+        assert_eq!(reported.inferred_icon(), Icon::Fog);
+
+        let snowing: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"precipIntensity\":0.1,\"precipType\":\"snow\"}"
+        ).unwrap();
+
+        assert_eq!(snowing.inferred_icon(), Icon::Snow);
+
+        let overcast: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"precipIntensity\":0.0,\"cloudCover\":0.9}"
+        ).unwrap();
+
+        assert_eq!(overcast.inferred_icon(), Icon::Cloudy);
+
+        let night: DataPoint = serde_json::from_str(
+            "{\"time\":100,\"sunriseTime\":10,\"sunsetTime\":50,\"cloudCover\":0.5}"
+        ).unwrap();
+
+        assert_eq!(night.inferred_icon(), Icon::PartlyCloudyNight);
+
+        let day: DataPoint = serde_json::from_str("{\"time\":0,\"cloudCover\":0.1}").unwrap();
+
+        assert_eq!(day.inferred_icon(), Icon::ClearDay);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let degraded = testing::response(serde_json::json!({
+            "flags": {"darksky-unavailable": "radar offline", "sources": [], "units": "us"}
+        }));
+
+        assert_eq!(degraded.warnings(), vec![Warning::DarkSkyUnavailable("radar offline".to_string())]);
+
+        let healthy = testing::response(serde_json::json!({}));
+
+        assert!(healthy.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_data_block_iteration() {
+        let block: DataBlock = serde_json::from_str(
+            "{\"data\":[{\"time\":1},{\"time\":2},{\"time\":3}]}"
+        ).unwrap();
+
+        assert_eq!(block.len(), 3);
+        assert!(!block.is_empty());
+        assert_eq!(block.first().map(|p| p.time), Some(1));
+        assert_eq!(block.last().map(|p| p.time), Some(3));
+        assert_eq!(block[1].time, 2);
+        assert_eq!((&block).into_iter().map(|p| p.time).sum::<i64>(), 6);
+
+        let times: Vec<i64> = block.into_iter().map(|p| p.time).collect();
+
+        assert_eq!(times, vec![1, 2, 3]);
+    }
+
+
+    #[test]
+    fn test_forecast_horizon() {
+        let hours = |n: i64| -> Vec<serde_json::Value> {
+            (0..n).map(|h| serde_json::json!({"time": h * 3600})).collect()
+        };
+
+        let standard = testing::response(serde_json::json!({"hourly": {"data": hours(49)}}));
+
+        let horizon = standard.forecast_horizon();
+
+        assert_eq!(horizon.hourly, Some(0..49 * 3600));
+        assert_eq!(horizon.minutely, None);
+        assert!(!horizon.is_hourly_extended());
+
+        let hourly = standard.hourly.as_ref().unwrap();
+
+        assert!(hourly.covers(3600..24 * 3600));
+        assert!(!hourly.covers(3600..72 * 3600));
+
+        let extended = testing::response(serde_json::json!({"hourly": {"data": hours(169)}}));
+
+        assert!(extended.forecast_horizon().is_hourly_extended());
+    }
+
+    #[test]
+    fn test_display() {
+        let response = testing::response(serde_json::json!({
+            "latitude": 42.36008, "longitude": -71.05888, "timezone": "America/New_York", "offset": -5,
+            "currently": {"time": 1516611600, "temperature": 3.24, "icon": "rain"}
+        }));
+
+        assert_eq!(
+            response.to_string(),
+            "42.3601,-71.0589 America/New_York 2018-01-22T04:00:00-05:00 3.2° rain"
+        );
+
+        let day: DataPoint = serde_json::from_str(
+            "{\"time\":1516579200,\"temperatureHigh\":6.4,\"temperatureLow\":1.0}"
+        ).unwrap();
+
+        assert_eq!(day.to_string(), "2018-01-22T00:00:00+00:00 6.4°/1.0°");
+    }
+
+    #[test]
+    fn test_defaults() {
+        let response = ApiResponse {
+            currently: Some(DataPoint { temperature: Some(3.2), ..DataPoint::default() }),
+            ..ApiResponse::default()
+        };
+
+        assert_eq!(response.currently.unwrap().time, 0);
+        assert!(response.hourly.is_none());
+        assert_eq!(Flags::default().units, Units::Imperial);
+        assert_eq!(Alert::default().severity, Severity::Advisory);
+        assert!(DataBlock::default().is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Parsing of responses from providers which report missing or broken
+//! measurements as `"NaN"`, `"Infinity"` or sentinel values such as
+//! `-9999` instead of leaving them out.
+//!
+//! Such values fail to parse, or parse as nonsense, with the plain
+//! models. `NumericOptions` maps them to `None` by default, or to NaN
+//! for pipelines which would rather propagate them, and can reject
+//! further values with a validation hook per field:
+//!
+//! ```
+//! use forecast::field::Field;
+//! use forecast::numeric::{InvalidValue, NumericOptions};
+//!
+//! let options = NumericOptions::new()
+//!     .sentinel(-9999.0)
+//!     .validate(Field::Humidity, |humidity| (0.0..=1.0).contains(&humidity));
+//!
+//! let response = options.parse(
+//!     "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+//!      \"currently\":{\"time\":0,\"temperature\":\"NaN\",\"humidity\":42,\"pressure\":-9999}}"
+//! ).unwrap();
+//! let currently = response.currently.unwrap();
+//!
+//! assert_eq!(currently.temperature, None);
+//! assert_eq!(currently.humidity, None);
+//! assert_eq!(currently.pressure, None);
+//!
+//! let response = options.invalid(InvalidValue::Nan).parse(
+//!     "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+//!      \"currently\":{\"time\":0,\"temperature\":\"-Infinity\"}}"
+//! ).unwrap();
+//!
+//! assert!(response.currently.unwrap().temperature.unwrap().is_nan());
+//! ```
+//!
+//! Only the measurements of `DataPoint`s, i.e. the non-time fields of
+//! `Field`, are checked; `null` is always `None`.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::field::Field;
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+static NON_FINITE: [&str; 7] = ["nan", "inf", "+inf", "-inf", "infinity", "+infinity", "-infinity"];
+static BLOCKS: [&str; 3] = ["minutely", "hourly", "daily"];
+
+/// What to do with a measurement which is non-finite, a sentinel or
+/// fails validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidValue {
+    /// Leave the measurement out, as if the API hadn't reported it.
+    #[default]
+    Omit,
+
+    /// Set the measurement to NaN.
+    Nan
+}
+
+type Validator = Box<dyn Fn(f64) -> bool + Send + Sync>;
+
+/// Options for parsing responses with invalid measurements. See the
+/// module documentation.
+#[derive(Default)]
+pub struct NumericOptions {
+    invalid: InvalidValue,
+    sentinels: Vec<f64>,
+    validators: HashMap<Field, Validator>
+}
+
+impl NumericOptions {
+    /// Options which omit non-finite measurements.
+    pub fn new() -> NumericOptions {
+        NumericOptions::default()
+    }
+
+    /// Set what to do with invalid measurements.
+    pub fn invalid(mut self, invalid: InvalidValue) -> NumericOptions {
+        self.invalid = invalid;
+        self
+    }
+
+    /// Treat `value` as invalid in every measurement.
+    pub fn sentinel(mut self, value: f64) -> NumericOptions {
+        self.sentinels.push(value);
+        self
+    }
+
+    /// Treat values of `field` for which `valid` returns false as
+    /// invalid, replacing any previous hook for the field. Hooks on time
+    /// fields are ignored.
+    pub fn validate<F>(mut self, field: Field, valid: F) -> NumericOptions
+        where F : Fn(f64) -> bool + Send + Sync + 'static {
+        self.validators.insert(field, Box::new(valid));
+        self
+    }
+
+    /// Parse a response from JSON, applying these options to the
+    /// measurements of its points.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `raw_json` can't be parsed once the invalid
+    /// measurements are removed.
+    pub fn parse(&self, raw_json: &str) -> Result<ApiResponse, serde_json::Error> {
+        let mut value: Map<String, Value> = serde_json::from_str(raw_json)?;
+        let mut currently = Vec::new();
+        let mut blocks: Vec<Vec<(usize, Field)>> = vec![Vec::new(); BLOCKS.len()];
+
+        if let Some(Value::Object(point)) = value.get_mut("currently") {
+            currently = self.scrub(point);
+        }
+
+        for (name, invalid) in BLOCKS.iter().zip(blocks.iter_mut()) {
+            let points = value.get_mut(*name)
+                .and_then(|block| block.get_mut("data"))
+                .and_then(Value::as_array_mut);
+
+            for (index, point) in points.into_iter().flatten().enumerate() {
+                if let Value::Object(point) = point {
+                    invalid.extend(self.scrub(point).into_iter().map(|field| (index, field)));
+                }
+            }
+        }
+
+        let mut response: ApiResponse = serde_json::from_value(Value::Object(value))?;
+
+        if self.invalid == InvalidValue::Nan {
+            if let Some(point) = response.currently.as_mut() {
+                currently.into_iter().for_each(|field| set_nan(point, field));
+            }
+
+            let data = [&mut response.minutely, &mut response.hourly, &mut response.daily];
+
+            for (block, invalid) in IntoIterator::into_iter(data).zip(blocks) {
+                if let Some(block) = block {
+                    for (index, field) in invalid {
+                        set_nan(&mut block.data[index], field);
+                    }
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    // Remove the invalid measurements of `point`, returning their fields.
+    fn scrub(&self, point: &mut Map<String, Value>) -> Vec<Field> {
+        let mut invalid = Vec::new();
+
+        for field in Field::iter().filter(|field| !field.is_time()) {
+            let key = json_name(field);
+
+            if point.get(&key).is_some_and(|value| self.is_invalid(field, value)) {
+                point.remove(&key);
+                invalid.push(field);
+            }
+        }
+
+        invalid
+    }
+
+    fn is_invalid(&self, field: Field, value: &Value) -> bool {
+        match value {
+            Value::String(s) => NON_FINITE.contains(&s.trim().to_ascii_lowercase().as_str()),
+            Value::Number(n) => n.as_f64().is_some_and(|n| {
+                self.sentinels.contains(&n)
+                    || self.validators.get(&field).is_some_and(|valid| !valid(n))
+            }),
+            _ => false
+        }
+    }
+}
+
+fn set_nan(point: &mut DataPoint, field: Field) {
+    if let Some(measurement) = point.measurement_mut(field) {
+        *measurement = Some(f64::NAN);
+    }
+}
+
+// The camelCase name of `field` in the API's JSON.
+fn json_name(field: Field) -> String {
+    let mut parts = field.name().split('_');
+    let mut name = parts.next().unwrap_or("").to_string();
+
+    for part in parts {
+        let mut chars = part.chars();
+
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    name
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{json_name, InvalidValue, NumericOptions};
+
+    use crate::field::Field;
+    use crate::testing;
+
+    use serde_json::json;
+
+    fn json() -> String {
+        testing::response_json(json!({
+            "hourly": {"data": [
+                {"time": 0, "temperature": 3.5, "windSpeed": "Infinity"},
+                {"time": 3600, "temperature": -9999, "humidity": null, "uvIndex": -1}
+            ]}
+        })).to_string()
+    }
+
+    #[test]
+    fn test_json_name() {
+        assert_eq!(json_name(Field::Cape), "cape");
+        assert_eq!(json_name(Field::PrecipIntensityMax), "precipIntensityMax");
+        assert_eq!(json_name(Field::UvIndex), "uvIndex");
+    }
+
+    #[test]
+    fn test_omit() {
+        let response = NumericOptions::new()
+            .sentinel(-9999.0)
+            .validate(Field::UvIndex, |uv_index| uv_index >= 0.0)
+            .parse(&json())
+            .unwrap();
+        let hourly = response.hourly.unwrap();
+
+        assert_eq!(hourly[0].temperature, Some(3.5));
+        assert_eq!(hourly[0].wind_speed, None);
+        assert_eq!(hourly[1].temperature, None);
+        assert_eq!(hourly[1].humidity, None);
+        assert_eq!(hourly[1].uv_index, None);
+    }
+
+    #[test]
+    fn test_nan() {
+        let response = NumericOptions::new()
+            .invalid(InvalidValue::Nan)
+            .parse(&json())
+            .unwrap();
+        let hourly = response.hourly.unwrap();
+
+        assert_eq!(hourly[0].temperature, Some(3.5));
+        assert!(hourly[0].wind_speed.unwrap().is_nan());
+        assert_eq!(hourly[1].temperature, Some(-9999.0));
+        assert_eq!(hourly[1].humidity, None);
+        assert_eq!(hourly[1].uv_index, Some(-1.0));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(NumericOptions::new().parse("{\"latitude\":1.0}").is_err());
+        assert!(NumericOptions::new()
+            .parse(&testing::response_json(json!({"currently": {"time": "NaN"}})).to_string())
+            .is_err());
+    }
+}
@@ -0,0 +1,179 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Lenient parsing of responses, where a malformed block is left out
+//! instead of failing the whole response, so that e.g. a bad field in
+//! the `daily` block doesn't blank out a dashboard's current conditions:
+//!
+//! ```
+//! use forecast::{ApiResponse, ExcludeBlock};
+//!
+//! let partial = ApiResponse::from_str_partial(
+//!     "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+//!      \"currently\":{\"time\":0,\"temperature\":21.5},\
+//!      \"daily\":{\"data\":[{\"time\":\"tomorrow\"}]}}"
+//! ).unwrap();
+//!
+//! assert_eq!(partial.response.currently.unwrap().temperature, Some(21.5));
+//! assert!(partial.response.daily.is_none());
+//! assert_eq!(partial.errors[0].block, ExcludeBlock::Daily);
+//! ```
+//!
+//! The location and time zone fields are still required; a response
+//! without them fails to parse.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{ApiResponse, ExcludeBlock};
+
+// constants
+
+static BLOCKS: [(ExcludeBlock, &str); 6] = [
+    (ExcludeBlock::Currently, "currently"),
+    (ExcludeBlock::Minutely, "minutely"),
+    (ExcludeBlock::Hourly, "hourly"),
+    (ExcludeBlock::Daily, "daily"),
+    (ExcludeBlock::Alerts, "alerts"),
+    (ExcludeBlock::Flags, "flags")
+];
+
+/// A block of a response which couldn't be parsed, and was left out.
+#[derive(Debug)]
+pub struct BlockParseError {
+    pub block: ExcludeBlock,
+
+    pub error: serde_json::Error
+}
+
+impl fmt::Display for BlockParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = BLOCKS.iter()
+            .find(|(block, _)| *block == self.block)
+            .map_or("", |(_, name)| name);
+
+        write!(f, "couldn't parse {} block: {}", name, self.error)
+    }
+}
+
+impl std::error::Error for BlockParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A response parsed by `ApiResponse::from_str_partial`.
+#[derive(Debug)]
+pub struct PartialResponse {
+    /// The response, with any block which couldn't be parsed set to
+    /// `None`.
+    pub response: ApiResponse,
+
+    /// The blocks which couldn't be parsed, in the order `currently`,
+    /// `minutely`, `hourly`, `daily`, `alerts`, `flags`.
+    pub errors: Vec<BlockParseError>
+}
+
+impl PartialResponse {
+    /// Whether every block of the response was parsed.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl ApiResponse {
+    /// Parse a response from JSON, leaving out any block which can't be
+    /// parsed instead of failing. See the `partial` module.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `raw_json` isn't a JSON object, or its
+    /// location or time zone fields can't be parsed.
+    pub fn from_str_partial(raw_json: &str) -> Result<PartialResponse, serde_json::Error> {
+        let mut object: Map<String, Value> = serde_json::from_str(raw_json)?;
+        let blocks: Vec<(ExcludeBlock, Value)> = BLOCKS.iter()
+            .filter_map(|(block, name)| object.remove(*name).map(|value| (*block, value)))
+            .collect();
+
+        let mut response: ApiResponse = serde_json::from_value(Value::Object(object))?;
+        let mut errors = Vec::new();
+
+        for (block, value) in blocks {
+            let parsed = match block {
+                ExcludeBlock::Currently => parse(value).map(|v| response.currently = v),
+                ExcludeBlock::Minutely => parse(value).map(|v| response.minutely = v),
+                ExcludeBlock::Hourly => parse(value).map(|v| response.hourly = v),
+                ExcludeBlock::Daily => parse(value).map(|v| response.daily = v),
+                ExcludeBlock::Alerts => parse(value).map(|v| response.alerts = v),
+                ExcludeBlock::Flags => parse(value).map(|v| response.flags = v)
+            };
+
+            if let Err(error) = parsed {
+                errors.push(BlockParseError { block, error });
+            }
+        }
+
+        Ok(PartialResponse { response, errors })
+    }
+}
+
+fn parse<T: DeserializeOwned>(value: Value) -> Result<T, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+    use crate::{ApiResponse, ExcludeBlock};
+
+    use serde_json::json;
+
+    use std::fs;
+
+    #[test]
+    fn test_from_str_partial() {
+        let json = testing::response_json(json!({
+            "hourly": {"data": [{"time": 0, "temperature": 3.5}]},
+            "daily": {"data": [{"time": 0, "temperatureHigh": "warm"}]},
+            "flags": {"units": 7}
+        }));
+        let partial = ApiResponse::from_str_partial(&json.to_string()).unwrap();
+
+        assert!(!partial.is_complete());
+        assert_eq!(partial.response.hourly.as_ref().unwrap()[0].temperature, Some(3.5));
+        assert!(partial.response.daily.is_none());
+        assert!(partial.response.flags.is_none());
+
+        let blocks: Vec<ExcludeBlock> = partial.errors.iter().map(|e| e.block).collect();
+
+        assert_eq!(blocks, vec![ExcludeBlock::Daily, ExcludeBlock::Flags]);
+        assert!(partial.errors[0].to_string().starts_with("couldn't parse daily block: "));
+
+        assert!(ApiResponse::from_str_partial("{\"latitude\":\"north\"}").is_err());
+        assert!(ApiResponse::from_str_partial("[]").is_err());
+    }
+
+    #[test]
+    fn test_from_str_partial_complete() {
+        let json = fs::read_to_string("resources/tests/forecast_response_01-21-2018.json").unwrap();
+        let partial = ApiResponse::from_str_partial(&json).unwrap();
+
+        assert!(partial.is_complete());
+        assert_eq!(partial.response, serde_json::from_str::<ApiResponse>(&json).unwrap());
+    }
+}
@@ -0,0 +1,258 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Charts of the hourly forecast, available with the `plot` feature.
+//!
+//! `HourlyChart` draws the temperature curve and precipitation
+//! probability bars for the next 48 hours of a response's `hourly`
+//! block, as SVG or PNG, for bots and static sites to embed:
+//!
+//! ```no_run
+//! # fn example(response: &forecast::ApiResponse) -> Result<(), forecast::plot::PlotError> {
+//! use forecast::plot::HourlyChart;
+//!
+//! let svg = HourlyChart::new(response).size(800, 300).svg()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Labels are drawn with the system fonts found through fontconfig.
+
+use std::fmt;
+use std::ops::Range;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::datetime::CivilDateTime;
+use crate::{ApiResponse, DataPoint};
+
+// constants
+
+const DEFAULT_WIDTH: u32 = 800;
+const DEFAULT_HEIGHT: u32 = 400;
+const DEFAULT_HOURS: usize = 48;
+
+const TEMPERATURE_COLOR: RGBColor = RGBColor(220, 80, 40);
+const PRECIP_COLOR: RGBColor = RGBColor(50, 110, 200);
+
+/// Errors which can occur while rendering a chart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlotError {
+    /// The response has no hourly temperatures to plot.
+    NoHourlyData,
+
+    /// The chart couldn't be drawn, e.g. because no font was found.
+    Draw(String),
+
+    /// The chart couldn't be encoded as a PNG.
+    Encode(String)
+}
+
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlotError::NoHourlyData => write!(f, "the response has no hourly data"),
+            PlotError::Draw(e) => write!(f, "couldn't draw chart: {}", e),
+            PlotError::Encode(e) => write!(f, "couldn't encode chart: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for PlotError {}
+
+/// A chart of the hourly temperature and chance of precipitation.
+#[derive(Debug, Clone)]
+pub struct HourlyChart<'a> {
+    response: &'a ApiResponse,
+    width: u32,
+    height: u32,
+    hours: usize
+}
+
+impl<'a> HourlyChart<'a> {
+    /// Chart the first 48 hours of `response`, at 800x400 pixels.
+    pub fn new(response: &'a ApiResponse) -> HourlyChart<'a> {
+        HourlyChart {
+            response,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            hours: DEFAULT_HOURS
+        }
+    }
+
+    /// Set the size of the chart in pixels.
+    pub fn size(mut self, width: u32, height: u32) -> HourlyChart<'a> {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set how many hours to chart.
+    pub fn hours(mut self, hours: usize) -> HourlyChart<'a> {
+        self.hours = hours;
+        self
+    }
+
+    /// Render the chart as an SVG document.
+    pub fn svg(&self) -> Result<String, PlotError> {
+        let mut svg = String::new();
+
+        {
+            let root = SVGBackend::with_string(&mut svg, (self.width, self.height))
+                .into_drawing_area();
+            self.draw(&root)?;
+            root.present().map_err(draw_error)?;
+        }
+
+        Ok(svg)
+    }
+
+    /// Render the chart as a PNG image.
+    pub fn png(&self) -> Result<Vec<u8>, PlotError> {
+        let mut rgb = vec![0; self.width as usize * self.height as usize * 3];
+
+        {
+            let root = BitMapBackend::with_buffer(&mut rgb, (self.width, self.height))
+                .into_drawing_area();
+            self.draw(&root)?;
+            root.present().map_err(draw_error)?;
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&rgb))
+            .map_err(|e| PlotError::Encode(e.to_string()))?;
+
+        Ok(png)
+    }
+
+    fn points(&self) -> Vec<&'a DataPoint> {
+        self.response.hourly.iter().flatten().take(self.hours).collect()
+    }
+
+    #[allow(deprecated)]
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> Result<(), PlotError> {
+        let points = self.points();
+        let temperatures: Vec<(f64, f64)> = points.iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.temperature.map(|t| (i as f64, t)))
+            .collect();
+
+        let range = temperature_range(&temperatures).ok_or(PlotError::NoHourlyData)?;
+        let hours = 0.0..points.len() as f64;
+        let offset = self.response.offset * 3600;
+        let time = |x: &f64| points.get(*x as usize).map_or(0, |p| p.time);
+
+        root.fill(&WHITE).map_err(draw_error)?;
+
+        let mut chart = ChartBuilder::on(root)
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .right_y_label_area_size(40)
+            .build_cartesian_2d(hours.clone(), range)
+            .map_err(draw_error)?
+            .set_secondary_coord(hours, 0.0..100.0);
+
+        chart.configure_mesh()
+            .disable_x_mesh()
+            .x_labels(8)
+            .x_label_formatter(&|x| {
+                let t = CivilDateTime::from_unix(time(x), offset);
+                format!("{:02}:00", t.hour)
+            })
+            .y_desc("°")
+            .draw()
+            .map_err(draw_error)?;
+
+        chart.configure_secondary_axes()
+            .y_desc("%")
+            .draw()
+            .map_err(draw_error)?;
+
+        chart.draw_secondary_series(points.iter().enumerate().map(|(i, p)| {
+            let x = i as f64;
+            let probability = p.precip_probability.unwrap_or(0.0) * 100.0;
+
+            Rectangle::new([(x + 0.1, 0.0), (x + 0.9, probability)], PRECIP_COLOR.mix(0.4).filled())
+        })).map_err(draw_error)?;
+
+        chart.draw_series(LineSeries::new(
+            temperatures.iter().map(|(x, t)| (x + 0.5, *t)),
+            TEMPERATURE_COLOR.stroke_width(2)
+        )).map_err(draw_error)?;
+
+        Ok(())
+    }
+}
+
+// The temperature axis, padded by a degree either side.
+fn temperature_range(temperatures: &[(f64, f64)]) -> Option<Range<f64>> {
+    let min = temperatures.iter().map(|(_, t)| *t).reduce(f64::min)?;
+    let max = temperatures.iter().map(|(_, t)| *t).reduce(f64::max)?;
+
+    Some((min - 1.0).floor()..(max + 1.0).ceil())
+}
+
+fn draw_error<E: std::error::Error + Send + Sync>(error: DrawingAreaErrorKind<E>) -> PlotError {
+    PlotError::Draw(error.to_string())
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{HourlyChart, PlotError};
+
+    use crate::ApiResponse;
+
+    use std::fs::File;
+
+    fn response() -> ApiResponse {
+        let file = File::open("resources/tests/forecast_response_01-21-2018.json").unwrap();
+
+        serde_json::from_reader(file).unwrap()
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = HourlyChart::new(&response()).size(400, 200).svg().unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<polyline"));
+        assert!(svg.contains("<rect"));
+    }
+
+    #[test]
+    fn test_png() {
+        let png = HourlyChart::new(&response()).size(400, 200).hours(12).png().unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_no_hourly_data() {
+        let mut response = response();
+
+        response.hourly = None;
+
+        assert_eq!(HourlyChart::new(&response).svg(), Err(PlotError::NoHourlyData));
+    }
+}
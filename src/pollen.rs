@@ -0,0 +1,223 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Pollen counts from pluggable sources, summarized per day and joined
+//! with the daily forecast.
+//!
+//! A `PollenSource` fetches hourly counts of grass, tree and weed
+//! pollen for a location. With the `air-quality` feature,
+//! `AirQualityClient` is one, backed by Open-Meteo's European pollen
+//! forecast; implement the trait to plug in another. `daily` reduces
+//! hourly counts to each local day's peak level, and `join_daily` pairs
+//! those with the points of a forecast's daily block.
+//!
+//! Levels follow the count ranges of the US National Allergy Bureau,
+//! which differ by type of pollen since people react to different
+//! concentrations of each.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::calendar::{LocalCalendar, LocalDate};
+use crate::error;
+use crate::{ApiResponse, DataPoint};
+
+/// The future returned by `PollenSource::fetch`.
+pub type PollenFuture<'a> =
+    Pin<Box<dyn Future<Output = error::Result<Vec<PollenCount>>> + Send + 'a>>;
+
+/// A source of hourly pollen counts.
+pub trait PollenSource: fmt::Debug + Send + Sync {
+    /// Fetch the hourly pollen counts for a location.
+    fn fetch(&self, latitude: f64, longitude: f64) -> PollenFuture<'_>;
+}
+
+/// A kind of pollen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PollenType {
+    Grass,
+    Tree,
+    Weed
+}
+
+/// How much pollen there is, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PollenLevel {
+    Low,
+    Moderate,
+    High,
+    VeryHigh
+}
+
+impl PollenLevel {
+    /// The level of `count` grains/m³ of `pollen_type`, or `None` if
+    /// there is no pollen.
+    pub fn from_count(pollen_type: PollenType, count: f64) -> Option<PollenLevel> {
+        let thresholds = match pollen_type {
+            PollenType::Grass => [1.0, 5.0, 20.0, 200.0],
+            PollenType::Tree => [1.0, 15.0, 90.0, 1500.0],
+            PollenType::Weed => [1.0, 10.0, 50.0, 500.0]
+        };
+        let levels = [
+            PollenLevel::Low, PollenLevel::Moderate, PollenLevel::High, PollenLevel::VeryHigh
+        ];
+
+        thresholds.iter()
+            .zip(levels.iter())
+            .rev()
+            .find(|(threshold, _)| count >= **threshold)
+            .map(|(_, level)| *level)
+    }
+}
+
+/// The pollen counts in one hour, in grains/m³.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PollenCount {
+    /// The start of the hour, in seconds since the UNIX epoch.
+    pub time: i64,
+
+    pub grass: Option<f64>,
+
+    pub tree: Option<f64>,
+
+    pub weed: Option<f64>
+}
+
+/// The peak pollen levels on one local day. A level is `None` if there
+/// was no pollen of the type or no count for it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DailyPollen {
+    pub date: LocalDate,
+
+    pub grass: Option<PollenLevel>,
+
+    pub tree: Option<PollenLevel>,
+
+    pub weed: Option<PollenLevel>
+}
+
+/// The peak pollen level of each type on each local day of `counts`,
+/// which must be sorted by time.
+pub fn daily(counts: &[PollenCount], calendar: &LocalCalendar) -> Vec<DailyPollen> {
+    let mut days: Vec<DailyPollen> = Vec::new();
+
+    for count in counts {
+        let date = calendar.date(count.time);
+
+        if days.last().is_none_or(|day| day.date != date) {
+            days.push(DailyPollen { date, grass: None, tree: None, weed: None });
+        }
+
+        if let Some(day) = days.last_mut() {
+            peak(&mut day.grass, PollenType::Grass, count.grass);
+            peak(&mut day.tree, PollenType::Tree, count.tree);
+            peak(&mut day.weed, PollenType::Weed, count.weed);
+        }
+    }
+
+    days
+}
+
+/// Pair each point of `response`'s daily block with the pollen levels
+/// for the same local day, if there are any.
+pub fn join_daily<'a>(response: &'a ApiResponse, pollen: &'a [DailyPollen])
+    -> Vec<(&'a DataPoint, Option<&'a DailyPollen>)> {
+    let calendar = response.local_calendar();
+
+    response.daily.iter()
+        .flatten()
+        .map(|point| {
+            let date = calendar.date(point.time);
+
+            (point, pollen.iter().find(|day| day.date == date))
+        })
+        .collect()
+}
+
+fn peak(level: &mut Option<PollenLevel>, pollen_type: PollenType, count: Option<f64>) {
+    let count_level = count.and_then(|count| PollenLevel::from_count(pollen_type, count));
+
+    *level = (*level).max(count_level);
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{daily, join_daily, DailyPollen, PollenCount, PollenLevel, PollenType};
+
+    use crate::calendar::{LocalCalendar, LocalDate};
+    use crate::testing;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_from_count() {
+        assert_eq!(PollenLevel::from_count(PollenType::Grass, 0.0), None);
+        assert_eq!(PollenLevel::from_count(PollenType::Grass, 4.0), Some(PollenLevel::Low));
+        assert_eq!(PollenLevel::from_count(PollenType::Grass, 20.0), Some(PollenLevel::High));
+        assert_eq!(PollenLevel::from_count(PollenType::Tree, 20.0), Some(PollenLevel::Moderate));
+        assert_eq!(
+            PollenLevel::from_count(PollenType::Weed, 600.0),
+            Some(PollenLevel::VeryHigh)
+        );
+    }
+
+    #[test]
+    fn test_daily() {
+        let count = |time, grass, tree| PollenCount { time, grass, tree, weed: None };
+        let counts = vec![
+            count(0, Some(2.0), None),
+            count(3600, Some(30.0), Some(0.0)),
+            count(86_400, Some(6.0), Some(100.0))
+        ];
+
+        let days = daily(&counts, &LocalCalendar::fixed(0));
+
+        assert_eq!(days, vec![
+            DailyPollen {
+                date: LocalDate::new(1970, 1, 1),
+                grass: Some(PollenLevel::High),
+                tree: None,
+                weed: None
+            },
+            DailyPollen {
+                date: LocalDate::new(1970, 1, 2),
+                grass: Some(PollenLevel::Moderate),
+                tree: Some(PollenLevel::High),
+                weed: None
+            }
+        ]);
+    }
+
+    #[test]
+    fn test_join_daily() {
+        let response = testing::response(json!({
+            "daily": {"data": [{"time": 0}, {"time": 86_400}]}
+        }));
+        let pollen = vec![DailyPollen {
+            date: LocalDate::new(1970, 1, 2),
+            grass: Some(PollenLevel::Low),
+            tree: None,
+            weed: None
+        }];
+
+        let joined = join_daily(&response, &pollen);
+
+        assert_eq!(joined.len(), 2);
+        assert_eq!(joined[0].1, None);
+        assert_eq!(joined[1].1, Some(&pollen[0]));
+    }
+}
@@ -0,0 +1,349 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Monitoring of many locations at once.
+//!
+//! A `Portfolio` holds named `Site`s, each with its own request options,
+//! and keeps the latest response for each. `Portfolio::refresh` fetches
+//! every site in turn, pausing between requests so that a large
+//! portfolio doesn't exceed the API's rate limit, and stops early if the
+//! API reports that the quota is exhausted. `Portfolio::alerts` merges
+//! the alerts of every site, so that an alert covering several sites is
+//! reported once. The latest responses can be saved to a `StateStore`
+//! so that alerts seen before a restart are known afterwards.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::state::{self, StateError, StateStore};
+use crate::{Alert, ApiClient, ApiResponse, ExcludeBlock, ExcludeBlocks, ExtendBy,
+            ForecastRequestBuilder, Lang, Units};
+
+/// A monitored location and the options used to request its forecast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    latitude: f64,
+    longitude: f64,
+    exclude: ExcludeBlocks,
+    extend: Option<ExtendBy>,
+    lang: Option<Lang>,
+    units: Option<Units>
+}
+
+impl Site {
+    /// A site at `latitude` and `longitude`, requested with the API's
+    /// default options.
+    pub fn new(latitude: f64, longitude: f64) -> Site {
+        Site {
+            latitude,
+            longitude,
+            exclude: ExcludeBlocks::new(),
+            extend: None,
+            lang: None,
+            units: None
+        }
+    }
+
+    /// Add a DataBlock to exclude from this site's responses.
+    pub fn exclude_block(mut self, exclude_block: ExcludeBlock) -> Site {
+        if !self.exclude.contains(&exclude_block) {
+            self.exclude.push(exclude_block);
+        }
+        self
+    }
+
+    /// Extend the time window of this site's responses.
+    pub fn extend(mut self, extend: ExtendBy) -> Site {
+        self.extend = Some(extend);
+        self
+    }
+
+    /// Set the language for messages in this site's responses.
+    pub fn lang(mut self, lang: Lang) -> Site {
+        self.lang = Some(lang);
+        self
+    }
+
+    /// Set the measurement units for this site's responses.
+    pub fn units(mut self, units: Units) -> Site {
+        self.units = Some(units);
+        self
+    }
+
+    pub(crate) fn request<'a>(&self, api_key: &'a str) -> ForecastRequestBuilder<'a> {
+        let mut builder = ForecastRequestBuilder::new(api_key, self.latitude, self.longitude)
+            .exclude(&self.exclude);
+
+        if let Some(extend) = &self.extend {
+            builder = builder.extend(*extend);
+        }
+        if let Some(lang) = &self.lang {
+            builder = builder.lang(*lang);
+        }
+        if let Some(units) = &self.units {
+            builder = builder.units(*units);
+        }
+
+        builder
+    }
+}
+
+/// The outcome of `Portfolio::refresh`.
+#[derive(Debug, Default)]
+pub struct RefreshReport {
+    /// Sites whose latest response was replaced.
+    pub refreshed: Vec<String>,
+
+    /// Sites whose request failed, keeping their previous response.
+    pub failed: Vec<(String, Error)>,
+
+    /// Sites which weren't requested because the quota ran out.
+    pub skipped: Vec<String>
+}
+
+/// An alert and every site it was issued for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteAlert<'a> {
+    pub alert: &'a Alert,
+
+    pub sites: Vec<&'a str>
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    site: Site,
+    latest: Option<ApiResponse>
+}
+
+/// A named set of monitored sites.
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+    api_key: String,
+    interval: Duration,
+    entries: BTreeMap<String, Entry>
+}
+
+impl Portfolio {
+    /// Construct an empty Portfolio whose requests use `api_key`.
+    pub fn new(api_key: &str) -> Portfolio {
+        Portfolio {
+            api_key: api_key.to_string(),
+            interval: Duration::from_secs(0),
+            entries: BTreeMap::new()
+        }
+    }
+
+    /// Wait `interval` between consecutive requests during a refresh.
+    pub fn interval(mut self, interval: Duration) -> Portfolio {
+        self.interval = interval;
+        self
+    }
+
+    /// Add a site, replacing any site with the same name along with its
+    /// latest response.
+    pub fn add(&mut self, name: &str, site: Site) {
+        self.entries.insert(name.to_string(), Entry { site, latest: None });
+    }
+
+    /// Remove a site, returning it if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<Site> {
+        self.entries.remove(name).map(|entry| entry.site)
+    }
+
+    /// The names of every site, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// The site named `name`.
+    pub fn site(&self, name: &str) -> Option<&Site> {
+        self.entries.get(name).map(|entry| &entry.site)
+    }
+
+    /// The latest response for the site named `name`, if it has been
+    /// fetched.
+    pub fn latest(&self, name: &str) -> Option<&ApiResponse> {
+        self.entries.get(name).and_then(|entry| entry.latest.as_ref())
+    }
+
+    /// Store `response` as the latest response for the site named
+    /// `name`, e.g. one fetched by other means. Returns false if there
+    /// is no such site.
+    pub fn update(&mut self, name: &str, response: ApiResponse) -> bool {
+        match self.entries.get_mut(name) {
+            Some(entry) => {
+                entry.latest = Some(response);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Save the latest response of every site in `store` under `key`.
+    pub fn save_state(&self, store: &dyn StateStore, key: &str) -> Result<(), StateError> {
+        let latest: BTreeMap<&str, &ApiResponse> = self.entries.iter()
+            .filter_map(|(name, entry)| entry.latest.as_ref().map(|r| (name.as_str(), r)))
+            .collect();
+
+        state::save(store, key, &latest)
+    }
+
+    /// Restore the latest responses saved in `store` under `key` for the
+    /// sites in this portfolio. Saved responses of other sites are
+    /// ignored.
+    pub fn restore_state(&mut self, store: &dyn StateStore, key: &str) -> Result<(), StateError> {
+        let saved: BTreeMap<String, ApiResponse> = state::load(store, key)?.unwrap_or_default();
+
+        for (name, response) in saved {
+            self.update(&name, response);
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the forecast for every site with `client`, in name order.
+    pub async fn refresh(&mut self, client: &ApiClient<'_>) -> RefreshReport {
+        let mut report = RefreshReport::default();
+        let mut first = true;
+
+        for (name, entry) in self.entries.iter_mut() {
+            if report.failed.iter().any(|(_, e)| e.is_quota()) {
+                report.skipped.push(name.clone());
+                continue;
+            }
+
+            if !first && self.interval > Duration::from_secs(0) {
+                tokio::time::sleep(self.interval).await;
+            }
+            first = false;
+
+            match client.get_forecast_parsed(entry.site.request(&self.api_key).build()).await {
+                Ok(response) => {
+                    entry.latest = Some(response);
+                    report.refreshed.push(name.clone());
+                },
+                Err(e) => report.failed.push((name.clone(), e))
+            }
+        }
+
+        report
+    }
+
+    /// The alerts in the latest responses of every site. Alerts with the
+    /// same `uri` are reported once, listing every site they cover.
+    pub fn alerts(&self) -> Vec<SiteAlert<'_>> {
+        let mut alerts: Vec<SiteAlert<'_>> = Vec::new();
+
+        for (name, entry) in &self.entries {
+            let site_alerts = entry.latest.iter().flat_map(|r| r.alerts.iter().flatten());
+
+            for alert in site_alerts {
+                match alerts.iter_mut().find(|a| a.alert.uri == alert.uri) {
+                    Some(existing) => existing.sites.push(name),
+                    None => alerts.push(SiteAlert { alert, sites: vec![name] })
+                }
+            }
+        }
+
+        alerts
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Portfolio, Site};
+
+    use crate::state::MemoryStore;
+    use crate::testing;
+    use crate::{ApiResponse, ExcludeBlock, Units};
+
+    use serde_json::json;
+
+    fn response(uris: &[&str]) -> ApiResponse {
+        let alerts: Vec<_> = uris.iter().map(|uri| json!({
+            "title": "Flood Warning",
+            "regions": [],
+            "severity": "warning",
+            "time": 0,
+            "expires": 3600,
+            "description": "",
+            "uri": uri
+        })).collect();
+
+        testing::response(json!({
+            "latitude": 42.4, "longitude": -71.1, "timezone": "America/New_York", "offset": -5,
+            "alerts": alerts
+        }))
+    }
+
+    #[test]
+    fn test_sites() {
+        let mut portfolio = Portfolio::new("some_api_key");
+
+        portfolio.add("boston", Site::new(42.4, -71.1).units(Units::SI));
+        portfolio.add("albany", Site::new(42.7, -73.8).exclude_block(ExcludeBlock::Minutely));
+
+        assert_eq!(portfolio.names().collect::<Vec<_>>(), vec!["albany", "boston"]);
+        assert!(portfolio.latest("boston").is_none());
+        assert!(portfolio.update("boston", response(&[])));
+        assert!(!portfolio.update("denver", response(&[])));
+        assert!(portfolio.latest("boston").is_some());
+
+        let url = portfolio.site("albany").unwrap().request("some_api_key").build().url();
+
+        assert_eq!(url.query(), Some("exclude=minutely"));
+        assert!(portfolio.remove("albany").is_some());
+        assert!(portfolio.site("albany").is_none());
+    }
+
+    #[test]
+    fn test_alerts() {
+        let mut portfolio = Portfolio::new("some_api_key");
+
+        portfolio.add("boston", Site::new(42.4, -71.1));
+        portfolio.add("cambridge", Site::new(42.4, -71.1));
+        portfolio.add("albany", Site::new(42.7, -73.8));
+        portfolio.update("boston", response(&["https://alerts/1", "https://alerts/2"]));
+        portfolio.update("cambridge", response(&["https://alerts/1"]));
+
+        let alerts = portfolio.alerts();
+
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].alert.uri, "https://alerts/1");
+        assert_eq!(alerts[0].sites, vec!["boston", "cambridge"]);
+        assert_eq!(alerts[1].sites, vec!["boston"]);
+    }
+
+    #[test]
+    fn test_state_store() {
+        let store = MemoryStore::new();
+        let mut portfolio = Portfolio::new("some_api_key");
+
+        portfolio.add("boston", Site::new(42.4, -71.1));
+        portfolio.update("boston", response(&["https://alerts/1"]));
+        portfolio.save_state(&store, "portfolio").unwrap();
+
+        let mut restored = Portfolio::new("some_api_key");
+
+        restored.add("boston", Site::new(42.4, -71.1));
+        restored.restore_state(&store, "portfolio").unwrap();
+
+        assert_eq!(restored.latest("boston"), portfolio.latest("boston"));
+        assert_eq!(restored.alerts().len(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Projection of `DataPoint`s into your own structs, e.g. the rows of
+//! an ETL job, without copying fields by hand.
+//!
+//! Any struct which derives `Deserialize` can be the target. Its fields
+//! are matched by name against the snake_case fields of `DataPoint`, as
+//! serialized by the `snake_case` module; use `#[serde(rename)]` to map
+//! a field to a differently named one. Fields of `DataPoint` which the
+//! struct doesn't declare are ignored:
+//!
+//! ```
+//! # #[macro_use] extern crate serde_derive;
+//! use forecast::DataPoint;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Row {
+//!     time: i64,
+//!     temperature: Option<f64>,
+//!     #[serde(rename = "wind_gust")]
+//!     gust: Option<f64>
+//! }
+//!
+//! # fn main() {
+//! let point = DataPoint::builder().time(3600).with_temperature(21.5).with_humidity(0.4).build();
+//! let row: Row = point.project().unwrap();
+//!
+//! assert_eq!(row, Row { time: 3600, temperature: Some(21.5), gust: None });
+//! # }
+//! ```
+//!
+//! A missing value is omitted rather than null, so a field which isn't
+//! an `Option` fails the projection when the API didn't report it.
+
+use serde::de::DeserializeOwned;
+
+use crate::snake_case::SnakeCase;
+use crate::{DataBlock, DataPoint};
+
+impl DataPoint {
+    /// Project this point into a `T`. See the module documentation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `T` requires a field which is missing from
+    /// this point, or whose type doesn't match.
+    pub fn project<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::to_value(SnakeCase(self)).and_then(serde_json::from_value)
+    }
+}
+
+impl DataBlock {
+    /// Project every point of this block into a `T`, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first point which can't be projected.
+    pub fn project<T: DeserializeOwned>(&self) -> Result<Vec<T>, serde_json::Error> {
+        self.iter().map(DataPoint::project).collect()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataBlock, DataPoint, Icon};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Row {
+        time: i64,
+
+        icon: Option<Icon>,
+
+        #[serde(rename = "precip_probability")]
+        chance: Option<f64>
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Required {
+        humidity: f64
+    }
+
+    #[test]
+    fn test_project() {
+        let block = DataBlock {
+            data: vec![
+                DataPoint::builder().time(0).with_icon(Icon::Rain).with_precip_probability(0.9).build(),
+                DataPoint::builder().time(3600).with_humidity(0.5).build()
+            ],
+            ..DataBlock::default()
+        };
+
+        assert_eq!(block.project::<Row>().unwrap(), vec![
+            Row { time: 0, icon: Some(Icon::Rain), chance: Some(0.9) },
+            Row { time: 3600, icon: None, chance: None }
+        ]);
+        assert_eq!(block[1].project::<Required>().unwrap(), Required { humidity: 0.5 });
+        assert!(block.project::<Required>().is_err());
+    }
+}
@@ -0,0 +1,571 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Python bindings, available with the `python` feature.
+//!
+//! The `forecast` Python module wraps the parsed client, the request
+//! builders and the response models:
+//!
+//! ```python
+//! import forecast
+//!
+//! client = forecast.Client()
+//! request = forecast.ForecastRequest(api_key, 37.8267, -122.423).units("si")
+//! response = client.get_forecast(request)
+//!
+//! print(response.currently.temperature)
+//! ```
+//!
+//! The client is the shared client (see `ApiClient::shared`), so it is
+//! configured from the environment and reuses its connections. Requests
+//! block the calling thread but release the GIL. Failed requests raise
+//! `forecast.ForecastError`; invalid arguments raise `ValueError`.
+//!
+//! To build an importable extension module, e.g. with `maturin`, also
+//! enable `pyo3/extension-module`.
+
+use std::sync::Arc;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyIndexError, PyValueError};
+use pyo3::prelude::*;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::blocking;
+use crate::error::Error;
+use crate::{Alert, ApiClient, ApiResponse, BuildError, DataBlock, DataPoint, ExcludeBlock,
+            ExcludeBlocks, ExtendBy, ForecastRequestBuilder, Lang, Time,
+            TimeMachineRequestBuilder, Units};
+
+create_exception!(forecast, ForecastError, PyException, "A request to the API failed.");
+
+macro_rules! data_point_getters {
+    (floats: [$($float:ident),*], times: [$($time:ident),*]) => {
+        #[pymethods]
+        impl PyDataPoint {
+            $(
+                #[getter]
+                fn $float(&self) -> Option<f64> {
+                    self.0.$float
+                }
+            )*
+
+            $(
+                #[getter]
+                fn $time(&self) -> Option<i64> {
+                    self.0.$time
+                }
+            )*
+
+            #[getter]
+            fn time(&self) -> i64 {
+                self.0.time
+            }
+
+            #[getter]
+            fn summary(&self) -> Option<String> {
+                self.0.summary.clone()
+            }
+
+            #[getter]
+            fn icon(&self) -> Option<String> {
+                self.0.icon.as_ref().map(enum_name)
+            }
+
+            #[getter]
+            fn precip_type(&self) -> Option<String> {
+                self.0.precip_type.as_ref().map(enum_name)
+            }
+
+            fn __repr__(&self) -> String {
+                format!("DataPoint(time={}, summary={})", self.0.time, repr(&self.0.summary))
+            }
+        }
+    }
+}
+
+data_point_getters! {
+    floats: [
+        apparent_temperature, apparent_temperature_high, apparent_temperature_low,
+        cape, cloud_cover, dew_point, humidity, moon_phase, nearest_storm_bearing,
+        nearest_storm_distance, ozone, precip_accumulation, precip_intensity,
+        precip_intensity_max, precip_probability, pressure, swell_direction, swell_height,
+        swell_period, temperature, temperature_high, temperature_low, uv_index, visibility,
+        wave_height, wave_period, wind_bearing, wind_gust, wind_speed
+    ],
+    times: [
+        apparent_temperature_high_time, apparent_temperature_low_time,
+        precip_intensity_max_time, sunrise_time, sunset_time, temperature_high_time,
+        temperature_low_time, uv_index_time, wind_gust_time
+    ]
+}
+
+/// The client, exposed to Python as `forecast.Client`.
+#[pyclass(name = "Client", module = "forecast", frozen)]
+pub struct PyClient {
+    client: Arc<ApiClient<'static>>
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    fn new() -> PyResult<PyClient> {
+        let client = ApiClient::shared().map_err(|e| ForecastError::new_err(e.to_string()))?;
+
+        Ok(PyClient { client })
+    }
+
+    /// Send a Forecast request.
+    fn get_forecast(&self, py: Python<'_>, request: PyForecastRequest)
+        -> PyResult<PyApiResponse> {
+        request.builder().try_build().map_err(build_error)?;
+
+        let client = self.client.clone();
+
+        py.allow_threads(move || blocking::block_on(async move {
+            client.get_forecast_parsed(request.builder().build()).await
+        }))
+        .map_or_else(|| Err(ForecastError::new_err("the request was abandoned")), response)
+    }
+
+    /// Send a Time Machine request.
+    fn get_time_machine(&self, py: Python<'_>, request: PyTimeMachineRequest)
+        -> PyResult<PyApiResponse> {
+        request.builder().try_build().map_err(build_error)?;
+
+        let client = self.client.clone();
+
+        py.allow_threads(move || blocking::block_on(async move {
+            client.get_time_machine_parsed(request.builder().build()).await
+        }))
+        .map_or_else(|| Err(ForecastError::new_err("the request was abandoned")), response)
+    }
+}
+
+/// A Forecast request, exposed to Python as `forecast.ForecastRequest`.
+/// Its methods return an updated copy, so that calls can be chained.
+#[pyclass(name = "ForecastRequest", module = "forecast", frozen)]
+#[derive(Clone, Debug)]
+pub struct PyForecastRequest {
+    api_key: String,
+    latitude: f64,
+    longitude: f64,
+    exclude: ExcludeBlocks,
+    extend: bool,
+    lang: Option<Lang>,
+    units: Option<Units>
+}
+
+impl PyForecastRequest {
+    fn builder(&self) -> ForecastRequestBuilder<'_> {
+        let mut builder = ForecastRequestBuilder::new(&self.api_key, self.latitude, self.longitude)
+            .exclude(&self.exclude);
+
+        if self.extend {
+            builder = builder.extend(ExtendBy::Hourly);
+        }
+        if let Some(lang) = &self.lang {
+            builder = builder.lang(*lang);
+        }
+        if let Some(units) = &self.units {
+            builder = builder.units(*units);
+        }
+
+        builder
+    }
+}
+
+#[pymethods]
+impl PyForecastRequest {
+    #[new]
+    fn new(api_key: String, latitude: f64, longitude: f64) -> PyForecastRequest {
+        PyForecastRequest {
+            api_key,
+            latitude,
+            longitude,
+            exclude: ExcludeBlocks::new(),
+            extend: false,
+            lang: None,
+            units: None
+        }
+    }
+
+    /// Exclude a block, e.g. `"minutely"`, from the response.
+    fn exclude(&self, block: &str) -> PyResult<PyForecastRequest> {
+        let mut request = self.clone();
+
+        request.exclude.push(parse_enum::<ExcludeBlock>("block", block)?);
+        Ok(request)
+    }
+
+    /// Extend the hourly forecast from 48 hours to 168 hours.
+    fn extend_hourly(&self) -> PyForecastRequest {
+        PyForecastRequest { extend: true, ..self.clone() }
+    }
+
+    /// Set the language, e.g. `"de"`, for messages in the response.
+    fn lang(&self, lang: &str) -> PyResult<PyForecastRequest> {
+        Ok(PyForecastRequest { lang: Some(parse_enum("language", lang)?), ..self.clone() })
+    }
+
+    /// Set the measurement units, e.g. `"si"`, for the response.
+    fn units(&self, units: &str) -> PyResult<PyForecastRequest> {
+        Ok(PyForecastRequest { units: Some(parse_enum("units", units)?), ..self.clone() })
+    }
+
+    /// The request URL. Contains the API key.
+    fn url(&self) -> String {
+        self.builder().build().url().to_string()
+    }
+}
+
+/// A Time Machine request, exposed to Python as
+/// `forecast.TimeMachineRequest`. Its methods return an updated copy, so
+/// that calls can be chained.
+#[pyclass(name = "TimeMachineRequest", module = "forecast", frozen)]
+#[derive(Clone, Debug)]
+pub struct PyTimeMachineRequest {
+    api_key: String,
+    latitude: f64,
+    longitude: f64,
+    time: Time,
+    exclude: ExcludeBlocks,
+    lang: Option<Lang>,
+    units: Option<Units>
+}
+
+impl PyTimeMachineRequest {
+    fn builder(&self) -> TimeMachineRequestBuilder<'_> {
+        let mut builder = TimeMachineRequestBuilder::new(
+            &self.api_key, self.latitude, self.longitude, self.time.clone()
+        ).exclude(&self.exclude);
+
+        if let Some(lang) = &self.lang {
+            builder = builder.lang(*lang);
+        }
+        if let Some(units) = &self.units {
+            builder = builder.units(*units);
+        }
+
+        builder
+    }
+}
+
+#[pymethods]
+impl PyTimeMachineRequest {
+    /// `time` is either seconds since the UNIX epoch or an ISO 8601
+    /// string, as accepted by `forecast::Time`.
+    #[new]
+    fn new(api_key: String, latitude: f64, longitude: f64, time: &Bound<'_, PyAny>)
+        -> PyResult<PyTimeMachineRequest> {
+        let time = match time.extract::<i64>() {
+            Ok(seconds) => Time::Unix(seconds),
+            Err(_) => Time::Iso8601(time.extract()?)
+        };
+
+        Ok(PyTimeMachineRequest {
+            api_key,
+            latitude,
+            longitude,
+            time,
+            exclude: ExcludeBlocks::new(),
+            lang: None,
+            units: None
+        })
+    }
+
+    /// Exclude a block, e.g. `"minutely"`, from the response.
+    fn exclude(&self, block: &str) -> PyResult<PyTimeMachineRequest> {
+        let mut request = self.clone();
+
+        request.exclude.push(parse_enum::<ExcludeBlock>("block", block)?);
+        Ok(request)
+    }
+
+    /// Set the language, e.g. `"de"`, for messages in the response.
+    fn lang(&self, lang: &str) -> PyResult<PyTimeMachineRequest> {
+        Ok(PyTimeMachineRequest { lang: Some(parse_enum("language", lang)?), ..self.clone() })
+    }
+
+    /// Set the measurement units, e.g. `"si"`, for the response.
+    fn units(&self, units: &str) -> PyResult<PyTimeMachineRequest> {
+        Ok(PyTimeMachineRequest { units: Some(parse_enum("units", units)?), ..self.clone() })
+    }
+
+    /// The request URL. Contains the API key.
+    fn url(&self) -> String {
+        self.builder().build().url().to_string()
+    }
+}
+
+/// An `ApiResponse`, exposed to Python as `forecast.ApiResponse`.
+#[pyclass(name = "ApiResponse", module = "forecast", frozen, eq)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PyApiResponse(ApiResponse);
+
+#[pymethods]
+impl PyApiResponse {
+    /// Parse a response from its JSON representation.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<PyApiResponse> {
+        serde_json::from_str(json)
+            .map(PyApiResponse)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// The JSON representation of this response, as sent by the API.
+    fn to_json(&self) -> PyResult<String> {
+        crate::wire::to_string(&self.0).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn latitude(&self) -> f64 {
+        self.0.latitude
+    }
+
+    #[getter]
+    fn longitude(&self) -> f64 {
+        self.0.longitude
+    }
+
+    #[getter]
+    fn timezone(&self) -> String {
+        self.0.timezone.clone()
+    }
+
+    #[getter]
+    fn currently(&self) -> Option<PyDataPoint> {
+        self.0.currently.clone().map(PyDataPoint)
+    }
+
+    #[getter]
+    fn minutely(&self) -> Option<PyDataBlock> {
+        self.0.minutely.clone().map(PyDataBlock)
+    }
+
+    #[getter]
+    fn hourly(&self) -> Option<PyDataBlock> {
+        self.0.hourly.clone().map(PyDataBlock)
+    }
+
+    #[getter]
+    fn daily(&self) -> Option<PyDataBlock> {
+        self.0.daily.clone().map(PyDataBlock)
+    }
+
+    #[getter]
+    fn alerts(&self) -> Vec<PyAlert> {
+        self.0.alerts.iter().flatten().cloned().map(PyAlert).collect()
+    }
+
+    /// Conditions which indicate that the data in this response is
+    /// degraded.
+    #[getter]
+    fn warnings(&self) -> Vec<String> {
+        self.0.warnings().iter().map(|w| w.to_string()).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ApiResponse(latitude={}, longitude={}, timezone={:?})",
+            self.0.latitude, self.0.longitude, self.0.timezone
+        )
+    }
+}
+
+/// A `DataBlock`, exposed to Python as `forecast.DataBlock`. Behaves as
+/// a sequence of DataPoints.
+#[pyclass(name = "DataBlock", module = "forecast", frozen, eq, sequence)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PyDataBlock(DataBlock);
+
+#[pymethods]
+impl PyDataBlock {
+    #[getter]
+    fn data(&self) -> Vec<PyDataPoint> {
+        self.0.data.iter().cloned().map(PyDataPoint).collect()
+    }
+
+    #[getter]
+    fn summary(&self) -> Option<String> {
+        self.0.summary.clone()
+    }
+
+    #[getter]
+    fn icon(&self) -> Option<String> {
+        self.0.icon.as_ref().map(enum_name)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<PyDataPoint> {
+        let len = self.0.len() as isize;
+        let index = if index < 0 { index + len } else { index };
+
+        if index < 0 || index >= len {
+            return Err(PyIndexError::new_err("DataBlock index out of range"));
+        }
+
+        Ok(PyDataPoint(self.0[index as usize].clone()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DataBlock(len={}, summary={})", self.0.len(), repr(&self.0.summary))
+    }
+}
+
+/// A `DataPoint`, exposed to Python as `forecast.DataPoint`.
+#[pyclass(name = "DataPoint", module = "forecast", frozen, eq)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PyDataPoint(DataPoint);
+
+/// An `Alert`, exposed to Python as `forecast.Alert`.
+#[pyclass(name = "Alert", module = "forecast", frozen, eq)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PyAlert(Alert);
+
+#[pymethods]
+impl PyAlert {
+    #[getter]
+    fn title(&self) -> String {
+        self.0.title.clone()
+    }
+
+    #[getter]
+    fn description(&self) -> String {
+        self.0.description.clone()
+    }
+
+    #[getter]
+    fn severity(&self) -> String {
+        enum_name(&self.0.severity)
+    }
+
+    #[getter]
+    fn regions(&self) -> Vec<String> {
+        self.0.regions.clone()
+    }
+
+    #[getter]
+    fn time(&self) -> i64 {
+        self.0.time
+    }
+
+    #[getter]
+    fn expires(&self) -> i64 {
+        self.0.expires
+    }
+
+    #[getter]
+    fn uri(&self) -> String {
+        self.0.uri.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Alert(title={:?}, severity={:?})", self.0.title, enum_name(&self.0.severity))
+    }
+}
+
+/// The `forecast` Python module.
+#[pymodule]
+#[pyo3(name = "forecast")]
+pub fn forecast_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ForecastError", m.py().get_type::<ForecastError>())?;
+    m.add_class::<PyClient>()?;
+    m.add_class::<PyForecastRequest>()?;
+    m.add_class::<PyTimeMachineRequest>()?;
+    m.add_class::<PyApiResponse>()?;
+    m.add_class::<PyDataBlock>()?;
+    m.add_class::<PyDataPoint>()?;
+    m.add_class::<PyAlert>()?;
+
+    Ok(())
+}
+
+fn response(result: Result<ApiResponse, Error>) -> PyResult<PyApiResponse> {
+    result.map(PyApiResponse).map_err(|e| ForecastError::new_err(e.to_string()))
+}
+
+fn build_error(error: BuildError) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+// The API's name for an enum value, e.g. `"partly-cloudy-day"`.
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new()
+    }
+}
+
+// A Python-style repr of an optional string.
+fn repr(value: &Option<String>) -> String {
+    value.as_ref().map_or_else(|| "None".to_string(), |s| format!("{:?}", s))
+}
+
+fn parse_enum<T: DeserializeOwned>(kind: &str, name: &str) -> PyResult<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| PyValueError::new_err(format!("unknown {}: {:?}", kind, name)))
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{enum_name, parse_enum, PyForecastRequest, PyTimeMachineRequest};
+
+    use crate::{ExcludeBlock, Icon, Time, Units};
+
+    #[test]
+    fn test_enum_names() {
+        assert_eq!(enum_name(&Icon::PartlyCloudyDay), "partly-cloudy-day");
+        assert_eq!(parse_enum::<Units>("units", "si").unwrap(), Units::SI);
+        assert!(parse_enum::<ExcludeBlock>("block", "weekly").is_err());
+    }
+
+    #[test]
+    fn test_request_chaining() {
+        let request = PyForecastRequest::new("some_api_key".to_string(), 1.0, 2.0)
+            .exclude("minutely").unwrap()
+            .units("si").unwrap()
+            .extend_hourly();
+
+        assert_eq!(
+            request.url(),
+            "https://api.darksky.net/forecast/some_api_key/1.0000000000000000,2.0000000000000000\
+             ?exclude=minutely&extend=hourly&units=si"
+        );
+
+        let request = PyTimeMachineRequest {
+            api_key: "some_api_key".to_string(),
+            latitude: 1.0,
+            longitude: 2.0,
+            time: Time::Unix(3),
+            exclude: Default::default(),
+            lang: None,
+            units: None
+        }.lang("de").unwrap();
+
+        assert_eq!(
+            request.url(),
+            "https://api.darksky.net/forecast/some_api_key/1.0000000000000000,2.0000000000000000,3\
+             ?lang=de"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! An async rate limiter shared between clients.
+//!
+//! A `RateLimiter` enforces, per API key, a cap on requests per second
+//! and optionally the daily quota. Every `ApiClient` given the same
+//! limiter (see `ApiClient::with_rate_limiter`) draws from the same
+//! budget, so several clients in one process can't exceed the limits
+//! together.
+//!
+//! Requests are admitted in the order `acquire` is called: each call
+//! reserves the earliest slot after those already reserved, and the
+//! returned future completes when that slot arrives. A slow caller
+//! can't be overtaken by later ones, and a reserved slot is used up
+//! even if the future is dropped. Once the daily quota is spent,
+//! `acquire` fails immediately until the quota resets at midnight UTC.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::time::{sleep_until, Instant};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Error returned by `RateLimiter::acquire` when the daily quota for a
+/// key is spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaExhausted {
+    /// When the quota resets, in seconds since the UNIX epoch.
+    pub resets_at: u64
+}
+
+impl fmt::Display for QuotaExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "daily quota exhausted until {}", self.resets_at)
+    }
+}
+
+impl std::error::Error for QuotaExhausted {}
+
+#[derive(Debug, Default)]
+struct KeyState {
+    // the most recent reserved slots, at most `per_second` of them
+    slots: VecDeque<Instant>,
+    day: u64,
+    used_today: u64
+}
+
+/// Limits the rate of requests per API key.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: usize,
+    daily_quota: Option<u64>,
+    keys: Mutex<HashMap<String, KeyState>>
+}
+
+impl RateLimiter {
+    /// Allow at most `per_second` requests per key in any one-second
+    /// window. A limit of zero is treated as one.
+    pub fn new(per_second: u32) -> RateLimiter {
+        RateLimiter {
+            per_second: per_second.max(1) as usize,
+            daily_quota: None,
+            keys: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Also allow at most `daily_quota` requests per key per UTC day.
+    pub fn daily_quota(mut self, daily_quota: u64) -> RateLimiter {
+        self.daily_quota = Some(daily_quota);
+        self
+    }
+
+    /// Requests remaining today for `api_key`, or `None` without a daily
+    /// quota.
+    pub fn remaining_today(&self, api_key: &str) -> Option<u64> {
+        let quota = self.daily_quota?;
+        let today = unix_now() / SECONDS_PER_DAY;
+
+        self.with_keys(|keys| match keys.get(api_key) {
+            Some(state) if state.day == today => Some(quota.saturating_sub(state.used_today)),
+            _ => Some(quota)
+        })
+    }
+
+    /// Reserve a request for `api_key`. The slot is reserved when this
+    /// method is called; the returned future completes once the request
+    /// may be sent, or fails at once if the daily quota is spent.
+    pub fn acquire(&self, api_key: &str) -> impl Future<Output = Result<(), QuotaExhausted>> {
+        let reservation = self.reserve(api_key, Instant::now(), unix_now());
+
+        async move {
+            sleep_until(reservation?).await;
+            Ok(())
+        }
+    }
+
+    fn reserve(&self, api_key: &str, now: Instant, unix_now: u64)
+        -> Result<Instant, QuotaExhausted> {
+        let today = unix_now / SECONDS_PER_DAY;
+
+        self.with_keys(|keys| {
+            let state = keys.entry(api_key.to_string()).or_default();
+
+            if state.day != today {
+                state.day = today;
+                state.used_today = 0;
+            }
+
+            if let Some(quota) = self.daily_quota {
+                if state.used_today >= quota {
+                    return Err(QuotaExhausted { resets_at: (today + 1) * SECONDS_PER_DAY });
+                }
+            }
+
+            let slot = match state.slots.front() {
+                Some(oldest) if state.slots.len() >= self.per_second => now.max(*oldest + WINDOW),
+                _ => now
+            };
+
+            // slots are reserved in order, so the newest is the latest
+            let slot = state.slots.back().map_or(slot, |newest| slot.max(*newest));
+
+            state.slots.push_back(slot);
+            if state.slots.len() > self.per_second {
+                state.slots.pop_front();
+            }
+            state.used_today += 1;
+
+            Ok(slot)
+        })
+    }
+
+    fn with_keys<T, F: FnOnce(&mut HashMap<String, KeyState>) -> T>(&self, f: F) -> T {
+        match self.keys.lock() {
+            Ok(mut keys) => f(&mut keys),
+            Err(poisoned) => f(&mut poisoned.into_inner())
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{QuotaExhausted, RateLimiter, SECONDS_PER_DAY};
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    #[test]
+    fn test_reserve_per_second() {
+        let limiter = RateLimiter::new(2);
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+
+        let slots: Vec<Instant> = (0..5).map(|_| limiter.reserve("a", now, 0).unwrap()).collect();
+
+        assert_eq!(slots, vec![now, now, now + second, now + second, now + second * 2]);
+
+        // keys have separate budgets
+        assert_eq!(limiter.reserve("b", now, 0), Ok(now));
+    }
+
+    #[test]
+    fn test_daily_quota() {
+        let limiter = RateLimiter::new(100).daily_quota(2);
+        let now = Instant::now();
+
+        assert!(limiter.reserve("a", now, 10).is_ok());
+        assert!(limiter.reserve("a", now, 20).is_ok());
+        assert_eq!(limiter.reserve("a", now, 30),
+                   Err(QuotaExhausted { resets_at: SECONDS_PER_DAY }));
+
+        // the quota resets at midnight UTC
+        assert!(limiter.reserve("a", now, SECONDS_PER_DAY).is_ok());
+        assert_eq!(RateLimiter::new(1).remaining_today("a"), None);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_is_fair() {
+        let limiter = Arc::new(RateLimiter::new(20));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let start = Instant::now();
+
+        // reserve in order, then run the waiters in reverse order
+        let acquisitions: Vec<_> = (0..30).map(|i| (i, limiter.acquire("a"))).collect();
+        let handles: Vec<_> = acquisitions.into_iter().rev().map(|(i, acquire)| {
+            let order = order.clone();
+
+            tokio::spawn(async move {
+                acquire.await.unwrap();
+                order.lock().unwrap().push((i, Instant::now()));
+            })
+        }).collect();
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let order = order.lock().unwrap();
+
+        // the first 20 reservations were admitted at once and the rest a
+        // second later, even though the later ones started waiting first
+        for (i, admitted) in order.iter() {
+            assert_eq!(*admitted - start >= Duration::from_secs(1), *i >= 20, "waiter {}", i);
+        }
+
+        assert_eq!(limiter.remaining_today("a"), None);
+    }
+}
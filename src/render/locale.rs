@@ -0,0 +1,157 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Locale-aware formatting of numbers and measurements, e.g. `1,5 mm/h`
+//! in German, using the CLDR separators provided by `num-format`.
+//!
+//! The locale is chosen by the `Lang` a response was requested in;
+//! languages without a CLDR locale are formatted as English.
+
+use num_format::{Grouping, Locale};
+
+use crate::render::{precipitation_unit, speed_unit, temperature_symbol};
+use crate::{Lang, Units};
+
+/// The separators used to format numbers in one locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleFormat {
+    decimal: &'static str,
+    separator: &'static str,
+    minus_sign: &'static str,
+    grouping: Grouping
+}
+
+impl LocaleFormat {
+    /// The format for `lang`.
+    pub fn for_lang(lang: &Lang) -> LocaleFormat {
+        let code = serde_json::to_string(lang).unwrap();
+        let name = match code.trim_matches('"') {
+            "cz" => "cs",
+            "zh-tw" => "zh-Hant",
+            code => code
+        };
+
+        LocaleFormat::from(Locale::from_name(name).unwrap_or(Locale::en))
+    }
+
+    /// `value` rounded to `decimals` decimal places.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = match formatted.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (formatted.as_str(), None)
+        };
+
+        let mut number = String::new();
+
+        if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            number.push_str(self.minus_sign);
+        }
+
+        number.push_str(&self.group(integer));
+
+        if let Some(fraction) = fraction {
+            number.push_str(self.decimal);
+            number.push_str(fraction);
+        }
+
+        number
+    }
+
+    /// A temperature in `units`, rounded to a whole degree, e.g. `-3°C`.
+    pub fn temperature(&self, value: f64, units: &Units) -> String {
+        format!("{}{}", self.number(value, 0), temperature_symbol(units))
+    }
+
+    /// A wind speed in `units`, rounded to a whole number, e.g. `12 km/h`.
+    pub fn speed(&self, value: f64, units: &Units) -> String {
+        format!("{} {}", self.number(value, 0), speed_unit(units))
+    }
+
+    /// A precipitation intensity in `units`, to one decimal place, or
+    /// two for inches, e.g. `1.5 mm/h`.
+    pub fn precipitation(&self, value: f64, units: &Units) -> String {
+        let decimals = if *units == Units::Imperial { 2 } else { 1 };
+
+        format!("{} {}", self.number(value, decimals), precipitation_unit(units))
+    }
+
+    // Insert the locale's group separator into a string of digits.
+    fn group(&self, digits: &str) -> String {
+        let sizes: &[usize] = match self.grouping {
+            Grouping::Standard => &[3, 3],
+            Grouping::Indian => &[3, 2],
+            Grouping::Posix => return digits.to_string()
+        };
+
+        let mut groups = Vec::new();
+        let mut end = digits.len();
+        let mut size = sizes[0];
+
+        while end > size {
+            groups.push(&digits[end - size..end]);
+            end -= size;
+            size = sizes[1];
+        }
+
+        groups.push(&digits[..end]);
+        groups.reverse();
+        groups.join(self.separator)
+    }
+}
+
+impl From<Locale> for LocaleFormat {
+    fn from(locale: Locale) -> LocaleFormat {
+        LocaleFormat {
+            decimal: locale.decimal(),
+            separator: locale.separator(),
+            minus_sign: locale.minus_sign(),
+            grouping: locale.grouping()
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::LocaleFormat;
+
+    use crate::{Lang, Units};
+
+    #[test]
+    fn test_number() {
+        let english = LocaleFormat::for_lang(&Lang::English);
+        let german = LocaleFormat::for_lang(&Lang::German);
+
+        assert_eq!(english.number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(german.number(1234567.891, 2), "1.234.567,89");
+        assert_eq!(german.number(-2.5, 1), "-2,5");
+        assert_eq!(german.number(-0.2, 0), "0");
+        assert_eq!(english.number(999.0, 0), "999");
+        assert_eq!(LocaleFormat::for_lang(&Lang::IgpayAtinlay), english);
+    }
+
+    #[test]
+    fn test_measurements() {
+        let french = LocaleFormat::for_lang(&Lang::French);
+
+        assert_eq!(french.temperature(-3.4, &Units::SI), "-3°C");
+        assert_eq!(french.speed(12.2, &Units::CA), "12 km/h");
+        assert_eq!(french.precipitation(1.54, &Units::UK), "1,5 mm/h");
+        assert_eq!(LocaleFormat::for_lang(&Lang::English).precipitation(0.061, &Units::Imperial),
+                   "0.06 in/h");
+    }
+}
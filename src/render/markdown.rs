@@ -0,0 +1,257 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Chat-flavored markdown for weather bots.
+//!
+//! `render` produces a message with the current conditions, one line
+//! per day of the `daily` block with the high in bold, and a callout
+//! quote for each alert, e.g. for Slack:
+//!
+//! ```text
+//! 🌧️ *Now:* 3°C, Light rain
+//! 🌧️ Mon 22: *6°* / 1°, 80% rain
+//! ⛅ Tue 23: *8°* / 2°
+//! > 🚨 *<https://alerts.weather.gov/...|Flood Warning>* until Mon 22 18:00
+//! ```
+//!
+//! Text from the API is escaped so that it can't break the formatting.
+
+use crate::datetime::CivilDateTime;
+use crate::render::temperature_unit;
+use crate::summary::format_value;
+use crate::{Alert, ApiResponse, DataPoint, Icon, PrecipType, Severity, Units};
+
+// constants
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// The chance of precipitation above which a day mentions it.
+const WET_PRECIP_PROBABILITY: f64 = 0.2;
+
+static WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+/// The markdown dialect to emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Slack's mrkdwn: `*bold*` and `<url|text>` links.
+    Slack,
+
+    /// Discord markdown: `**bold**` and `[text](url)` links.
+    Discord
+}
+
+/// Render `response`, whose data is in `units`, as a chat message.
+/// Blocks missing from the response are left out.
+#[allow(deprecated)]
+pub fn render(response: &ApiResponse, units: &Units, flavor: Flavor) -> String {
+    let offset = response.offset * 3600;
+    let unit = temperature_unit(response, units);
+    let mut lines = Vec::new();
+
+    if let Some(currently) = &response.currently {
+        let mut line = format!(
+            "{} {} {}{}",
+            icon_emoji(&currently.inferred_icon()),
+            bold("Now:", flavor),
+            format_value(currently.temperature, 1.0),
+            unit
+        );
+
+        if let Some(summary) = currently.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+            line.push_str(", ");
+            line.push_str(&escape(&crate::summary::normalize_summary(summary), flavor));
+        }
+
+        lines.push(line);
+    }
+
+    for day in response.daily.iter().flatten() {
+        lines.push(day_line(day, offset, flavor));
+    }
+
+    for alert in response.alerts.iter().flatten() {
+        lines.push(alert_callout(alert, offset, flavor));
+    }
+
+    lines.join("\n")
+}
+
+/// The emoji shown for an icon.
+pub fn icon_emoji(icon: &Icon) -> &'static str {
+    match icon {
+        Icon::ClearDay => "☀️",
+        Icon::ClearNight => "🌙",
+        Icon::Rain => "🌧️",
+        Icon::Snow => "❄️",
+        Icon::Sleet => "🌨️",
+        Icon::Wind => "💨",
+        Icon::Fog => "🌫️",
+        Icon::Cloudy => "☁️",
+        Icon::PartlyCloudyDay => "⛅",
+        Icon::PartlyCloudyNight => "☁️",
+        Icon::Hail => "🧊",
+        Icon::Thunderstorm => "⛈️",
+        Icon::Tornado => "🌪️"
+    }
+}
+
+fn day_line(day: &DataPoint, offset: i64, flavor: Flavor) -> String {
+    let mut line = format!(
+        "{} {}: {} / {}°",
+        icon_emoji(&day.inferred_icon()),
+        format_day(day.time, offset),
+        bold(&format!("{}°", format_value(day.temperature_high, 1.0)), flavor),
+        format_value(day.temperature_low, 1.0)
+    );
+
+    if day.precip_probability.is_some_and(|p| p >= WET_PRECIP_PROBABILITY) {
+        let precip = match day.precip_type {
+            Some(PrecipType::Rain) => "rain",
+            Some(PrecipType::Snow) => "snow",
+            Some(PrecipType::Sleet) => "sleet",
+            None => "precipitation"
+        };
+
+        line.push_str(&format!(", {}% {}", format_value(day.precip_probability, 100.0), precip));
+    }
+
+    line
+}
+
+fn alert_callout(alert: &Alert, offset: i64, flavor: Flavor) -> String {
+    let emoji = match alert.severity {
+        Severity::Advisory => "ℹ️",
+        Severity::Watch => "⚠️",
+        Severity::Warning => "🚨"
+    };
+
+    let title = escape(&alert.title, flavor);
+    let link = match flavor {
+        Flavor::Slack => format!("<{}|{}>", alert.uri, title),
+        Flavor::Discord => format!("[{}]({})", title, alert.uri)
+    };
+
+    let expires = CivilDateTime::from_unix(alert.expires, offset);
+
+    format!(
+        "> {} {} until {} {:02}:{:02}",
+        emoji,
+        bold(&link, flavor),
+        format_day(alert.expires, offset),
+        expires.hour,
+        expires.minute
+    )
+}
+
+fn bold(text: &str, flavor: Flavor) -> String {
+    match flavor {
+        Flavor::Slack => format!("*{}*", text),
+        Flavor::Discord => format!("**{}**", text)
+    }
+}
+
+// e.g. "Mon 22"
+fn format_day(time: i64, offset: i64) -> String {
+    let weekday = (time + offset).div_euclid(SECONDS_PER_DAY).rem_euclid(7) as usize;
+
+    format!("{} {}", WEEKDAYS[weekday], CivilDateTime::from_unix(time, offset).day)
+}
+
+// Slack only requires its control characters to be escaped as HTML
+// entities; Discord markdown is escaped with backslashes.
+fn escape(text: &str, flavor: Flavor) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match (flavor, c) {
+            (Flavor::Slack, '&') => escaped.push_str("&amp;"),
+            (Flavor::Slack, '<') => escaped.push_str("&lt;"),
+            (Flavor::Slack, '>') => escaped.push_str("&gt;"),
+            (Flavor::Discord, '*' | '_' | '~' | '`' | '|' | '[' | ']' | '\\') => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            (_, c) => escaped.push(c)
+        }
+    }
+
+    escaped
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, render, Flavor};
+
+    use crate::testing;
+    use crate::{ApiResponse, Units};
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 42.4, "longitude": -71.1, "timezone": "America/New_York", "offset": -5,
+            "currently": {"time": 1516640400, "summary": "Light Rain", "temperature": 3.2,
+                          "icon": "rain"},
+            "daily": {"data": [
+                {"time": 1516597200, "icon": "rain", "temperatureHigh": 6.4,
+                 "temperatureLow": 1.0, "precipProbability": 0.8, "precipType": "rain"},
+                {"time": 1516683600, "icon": "partly-cloudy-day", "temperatureHigh": 8.0,
+                 "temperatureLow": 2.0, "precipProbability": 0.05}
+            ]},
+            "alerts": [{
+                "title": "Flood Warning",
+                "regions": ["Middlesex"],
+                "severity": "warning",
+                "time": 1516640400,
+                "expires": 1516662000,
+                "description": "Flooding is occurring.",
+                "uri": "https://alerts.weather.gov/1"
+            }],
+            "flags": {"sources": [], "units": "si"}
+        }))
+    }
+
+    #[test]
+    fn test_render_slack() {
+        assert_eq!(
+            render(&response(), &Units::Auto, Flavor::Slack),
+            "🌧️ *Now:* 3°C, Light Rain\n\
+             🌧️ Mon 22: *6°* / 1°, 80% rain\n\
+             ⛅ Tue 23: *8°* / 2°\n\
+             > 🚨 *<https://alerts.weather.gov/1|Flood Warning>* until Mon 22 18:00"
+        );
+    }
+
+    #[test]
+    fn test_render_discord() {
+        let mut response = response();
+
+        response.currently = None;
+        response.daily = None;
+
+        assert_eq!(
+            render(&response, &Units::SI, Flavor::Discord),
+            "> 🚨 **[Flood Warning](https://alerts.weather.gov/1)** until Mon 22 18:00"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<b> & *x*", Flavor::Slack), "&lt;b&gt; &amp; *x*");
+        assert_eq!(escape("*x_y*", Flavor::Discord), "\\*x\\_y\\*");
+    }
+}
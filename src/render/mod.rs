@@ -0,0 +1,109 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Human-readable renderings of API responses.
+//!
+//! `snapshot` collects every rendering of a response into one string,
+//! so that a snapshot test, e.g. with `insta`, catches changes to any
+//! of them. These are the text and markdown renderings; the crate has
+//! no CSV rendering to include:
+//!
+//! ```
+//! # use forecast::{ApiResponse, Lang, Units};
+//! # let response: ApiResponse = serde_json::from_str(
+//! #     "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+//! #       \"currently\":{\"time\":0,\"summary\":\"Clear\",\"temperature\":20.0}}").unwrap();
+//! let rendered = forecast::render::snapshot(&response, &Lang::English, &Units::SI);
+//!
+//! assert!(rendered.starts_with("--- text ---\nNow: 20°C, clear."));
+//! ```
+
+#[cfg(feature = "num-format")]
+pub mod locale;
+pub mod markdown;
+pub mod text;
+
+use crate::{ApiResponse, Lang, Units};
+use crate::render::markdown::Flavor;
+
+static MISSING: &str = "--";
+
+/// `units`, resolving `Units::Auto` through the units reported in the
+/// response's flags.
+pub(crate) fn resolve_units<'a>(response: &'a ApiResponse, units: &'a Units) -> &'a Units {
+    match units {
+        Units::Auto => response.flags.as_ref().map_or(&Units::Auto, |flags| &flags.units),
+        units => units
+    }
+}
+
+/// The temperature unit symbol for a response in `units`, resolving
+/// `Units::Auto` through the units reported in the response's flags.
+pub(crate) fn temperature_unit(response: &ApiResponse, units: &Units) -> &'static str {
+    temperature_symbol(resolve_units(response, units))
+}
+
+pub(crate) fn temperature_symbol(units: &Units) -> &'static str {
+    match units {
+        Units::Imperial => "°F",
+        Units::SI | Units::CA | Units::UK => "°C",
+        Units::Auto => "°"
+    }
+}
+
+pub(crate) fn speed_unit(units: &Units) -> &'static str {
+    match units {
+        Units::SI => "m/s",
+        Units::CA => "km/h",
+        Units::Imperial | Units::UK | Units::Auto => "mph"
+    }
+}
+
+pub(crate) fn precipitation_unit(units: &Units) -> &'static str {
+    match units {
+        Units::Imperial => "in/h",
+        _ => "mm/h"
+    }
+}
+
+/// `value` rounded to `decimals` decimal places, with the separators of
+/// `lang` when the `num-format` feature is enabled.
+pub(crate) fn format_number(value: Option<f64>, decimals: usize, lang: &Lang) -> String {
+    match value {
+        #[cfg(feature = "num-format")]
+        Some(v) => locale::LocaleFormat::for_lang(lang).number(v, decimals),
+        #[cfg(not(feature = "num-format"))]
+        Some(v) => {
+            let _ = lang;
+            format!("{:.*}", decimals, v)
+        },
+        None => MISSING.to_string()
+    }
+}
+
+/// Every rendering of `response`, whose data is in `units`, with the
+/// text report in `lang`, each under a `--- name ---` heading. The
+/// output is deterministic, for snapshot tests.
+pub fn snapshot(response: &ApiResponse, lang: &Lang, units: &Units) -> String {
+    let renderings = [
+        ("text", response.render_text(lang, units)),
+        ("markdown (slack)", markdown::render(response, units, Flavor::Slack)),
+        ("markdown (discord)", markdown::render(response, units, Flavor::Discord))
+    ];
+
+    renderings.iter()
+        .map(|(name, rendered)| format!("--- {} ---\n{}\n", name, rendered))
+        .collect()
+}
@@ -0,0 +1,338 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! A short plain-text report of the current conditions and today's
+//! forecast, e.g.
+//!
+//! ```text
+//! Now: 3°C, light rain.
+//! Today: high 6°, 80% chance of rain this afternoon.
+//! ```
+
+use crate::datetime::CivilDateTime;
+use crate::render::{format_number, precipitation_unit, resolve_units, speed_unit, temperature_unit};
+use crate::{ApiResponse, DataPoint, Lang, PrecipType, Units};
+
+// constants
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// The chance of precipitation above which today's line mentions it.
+const WET_PRECIP_PROBABILITY: f64 = 0.2;
+
+static ENGLISH_NOW: &str = "Now: {temperature}{unit}, {condition}.";
+static ENGLISH_TODAY: &str = "Today: high {high}°, {precip_probability}% chance of {precip_type}{period}.";
+static ENGLISH_TODAY_DRY: &str = "Today: high {high}°, low {low}°.";
+
+static GERMAN_NOW: &str = "Jetzt: {temperature}{unit}, {condition}.";
+static GERMAN_TODAY: &str = "Heute: Höchstwert {high}°, {precip_probability}% Wahrscheinlichkeit für {precip_type}{period}.";
+static GERMAN_TODAY_DRY: &str = "Heute: Höchstwert {high}°, Tiefstwert {low}°.";
+
+static FRENCH_NOW: &str = "Maintenant : {temperature}{unit}, {condition}.";
+static FRENCH_TODAY: &str = "Aujourd'hui : max. {high}°, {precip_probability}% de risque de {precip_type}{period}.";
+static FRENCH_TODAY_DRY: &str = "Aujourd'hui : max. {high}°, min. {low}°.";
+
+static SPANISH_NOW: &str = "Ahora: {temperature}{unit}, {condition}.";
+static SPANISH_TODAY: &str = "Hoy: máxima {high}°, {precip_probability}% de probabilidad de {precip_type}{period}.";
+static SPANISH_TODAY_DRY: &str = "Hoy: máxima {high}°, mínima {low}°.";
+
+/// The templates used to render a plain-text report, one per line.
+///
+/// The `now` template describes the `currently` block, with the
+/// placeholders `{temperature}`, `{unit}`, `{condition}`, `{wind_speed}`
+/// and `{precip_intensity}`, the last two including their units. The `today`
+/// template describes the first day of the `daily` block when
+/// precipitation is likely, with the placeholders `{high}`, `{low}`,
+/// `{precip_probability}`, `{precip_type}` and `{period}`; `today_dry`
+/// is used otherwise. `{period}` is the part of the day when
+/// precipitation is most likely, with a leading space, or empty when
+/// the response has no hourly data. A line is omitted when its block is
+/// missing from the response.
+///
+/// With the `num-format` feature enabled, numbers are formatted with the
+/// decimal and group separators of the templates' language.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextTemplates {
+    now: String,
+    today: String,
+    today_dry: String,
+    lang: Lang
+}
+
+impl TextTemplates {
+    /// Return the built-in templates for `lang`, falling back to English
+    /// for languages without them.
+    pub fn for_lang(lang: &Lang) -> TextTemplates {
+        let (now, today, today_dry, lang) = match lang {
+            Lang::German => (GERMAN_NOW, GERMAN_TODAY, GERMAN_TODAY_DRY, Lang::German),
+            Lang::French => (FRENCH_NOW, FRENCH_TODAY, FRENCH_TODAY_DRY, Lang::French),
+            Lang::Spanish => (SPANISH_NOW, SPANISH_TODAY, SPANISH_TODAY_DRY, Lang::Spanish),
+            _ => (ENGLISH_NOW, ENGLISH_TODAY, ENGLISH_TODAY_DRY, Lang::English)
+        };
+
+        TextTemplates {
+            now: now.to_string(),
+            today: today.to_string(),
+            today_dry: today_dry.to_string(),
+            lang
+        }
+    }
+
+    /// Override the template for the current conditions.
+    pub fn now(mut self, template: &str) -> TextTemplates {
+        self.now = template.to_string();
+        self
+    }
+
+    /// Override the template for today when precipitation is likely.
+    pub fn today(mut self, template: &str) -> TextTemplates {
+        self.today = template.to_string();
+        self
+    }
+
+    /// Override the template for today when precipitation is unlikely.
+    pub fn today_dry(mut self, template: &str) -> TextTemplates {
+        self.today_dry = template.to_string();
+        self
+    }
+
+    /// Render a report of `response`, whose data is in `units`.
+    pub fn render(&self, response: &ApiResponse, units: &Units) -> String {
+        let resolved = resolve_units(response, units);
+        let mut lines = Vec::new();
+
+        if let Some(currently) = &response.currently {
+            let precip_decimals = if *resolved == Units::Imperial { 2 } else { 1 };
+
+            lines.push(fill(&self.now, &[
+                ("{temperature}", format_number(currently.temperature, 0, &self.lang)),
+                ("{unit}", temperature_unit(response, units).to_string()),
+                ("{condition}", self.condition(currently)),
+                ("{wind_speed}", format!(
+                    "{} {}",
+                    format_number(currently.wind_speed, 0, &self.lang),
+                    speed_unit(resolved)
+                )),
+                ("{precip_intensity}", format!(
+                    "{} {}",
+                    format_number(currently.precip_intensity, precip_decimals, &self.lang),
+                    precipitation_unit(resolved)
+                ))
+            ]));
+        }
+
+        if let Some(today) = response.daily.as_ref().and_then(|daily| daily.first()) {
+            let wet = today.precip_probability.is_some_and(|p| p >= WET_PRECIP_PROBABILITY);
+            let template = if wet { &self.today } else { &self.today_dry };
+
+            lines.push(fill(template, &[
+                ("{high}", format_number(today.temperature_high, 0, &self.lang)),
+                ("{low}", format_number(today.temperature_low, 0, &self.lang)),
+                ("{precip_probability}",
+                 format_number(today.precip_probability.map(|p| p * 100.0), 0, &self.lang)),
+                ("{precip_type}", precip_type(today.precip_type.as_ref(), &self.lang).to_string()),
+                ("{period}", self.wettest_period(response, today))
+            ]));
+        }
+
+        lines.join("\n")
+    }
+
+    fn condition(&self, point: &DataPoint) -> String {
+        let summary = point.normalized_summary(&self.lang);
+
+        // German capitalizes nouns, so only lowercase other languages
+        match self.lang {
+            Lang::German => summary,
+            _ => summary.to_lowercase()
+        }
+    }
+
+    // The part of `today` in which the hourly forecast has the highest
+    // chance of precipitation, with a leading space.
+    #[allow(deprecated)]
+    fn wettest_period(&self, response: &ApiResponse, today: &DataPoint) -> String {
+        let wettest = response.hourly.iter()
+            .flatten()
+            .filter(|point| point.time >= today.time && point.time < today.time + SECONDS_PER_DAY)
+            .filter(|point| point.precip_probability.is_some())
+            .fold(None, |wettest: Option<&DataPoint>, point| match wettest {
+                Some(w) if w.precip_probability >= point.precip_probability => Some(w),
+                _ => Some(point)
+            });
+
+        match wettest {
+            Some(point) => {
+                let hour = CivilDateTime::from_unix(point.time, response.offset * 3600).hour;
+                format!(" {}", period(hour, &self.lang))
+            },
+            None => String::new()
+        }
+    }
+}
+
+impl ApiResponse {
+    /// Render a short report of the current conditions and today's
+    /// forecast with the built-in templates for `lang`. `units` are the
+    /// units the response was requested in. See `TextTemplates` to
+    /// customize the wording.
+    pub fn render_text(&self, lang: &Lang, units: &Units) -> String {
+        TextTemplates::for_lang(lang).render(self, units)
+    }
+}
+
+fn fill(template: &str, replacements: &[(&str, String)]) -> String {
+    replacements.iter().fold(template.to_string(), |rendered, (placeholder, value)| {
+        rendered.replace(placeholder, value)
+    })
+}
+
+fn precip_type(precip_type: Option<&PrecipType>, lang: &Lang) -> &'static str {
+    match (lang, precip_type) {
+        (Lang::German, Some(PrecipType::Rain)) => "Regen",
+        (Lang::German, Some(PrecipType::Snow)) => "Schnee",
+        (Lang::German, Some(PrecipType::Sleet)) => "Schneeregen",
+        (Lang::German, None) => "Niederschlag",
+        (Lang::French, Some(PrecipType::Rain)) => "pluie",
+        (Lang::French, Some(PrecipType::Snow)) => "neige",
+        (Lang::French, Some(PrecipType::Sleet)) => "grésil",
+        (Lang::French, None) => "précipitations",
+        (Lang::Spanish, Some(PrecipType::Rain)) => "lluvia",
+        (Lang::Spanish, Some(PrecipType::Snow)) => "nieve",
+        (Lang::Spanish, Some(PrecipType::Sleet)) => "aguanieve",
+        (Lang::Spanish, None) => "precipitación",
+        (_, Some(PrecipType::Rain)) => "rain",
+        (_, Some(PrecipType::Snow)) => "snow",
+        (_, Some(PrecipType::Sleet)) => "sleet",
+        (_, None) => "precipitation"
+    }
+}
+
+fn period(hour: u32, lang: &Lang) -> &'static str {
+    let index = match hour {
+        5..=11 => 0,
+        12..=16 => 1,
+        17..=20 => 2,
+        _ => 3
+    };
+
+    let periods = match lang {
+        Lang::German => ["am Morgen", "am Nachmittag", "am Abend", "in der Nacht"],
+        Lang::French => ["ce matin", "cet après-midi", "ce soir", "cette nuit"],
+        Lang::Spanish => ["por la mañana", "por la tarde", "por la noche", "de madrugada"],
+        _ => ["this morning", "this afternoon", "this evening", "overnight"]
+    };
+
+    periods[index]
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::TextTemplates;
+
+    use crate::testing;
+    use crate::{ApiResponse, Lang, Units};
+
+    use serde_json::json;
+
+    fn response() -> ApiResponse {
+        testing::response(json!({
+            "latitude": 52.5, "longitude": 13.4, "timezone": "Europe/Berlin", "offset": 1,
+            "currently": {
+                "time": 1516611600,
+                "summary": "Light Rain",
+                "temperature": 3.2,
+                "windSpeed": 4.4,
+                "precipIntensity": 1.26
+            },
+            "hourly": {"data": [
+                {"time": 1516611600, "precipProbability": 0.3},
+                {"time": 1516626000, "precipProbability": 0.8},
+                {"time": 1516651200, "precipProbability": 0.5}
+            ]},
+            "daily": {"data": [{
+                "time": 1516575600,
+                "temperatureHigh": 6.4,
+                "temperatureLow": 1.0,
+                "precipProbability": 0.8,
+                "precipType": "rain"
+            }]},
+            "flags": {"sources": [], "units": "si"}
+        }))
+    }
+
+    #[test]
+    fn test_render_text() {
+        let response = response();
+
+        assert_eq!(
+            response.render_text(&Lang::English, &Units::SI),
+            "Now: 3°C, light rain.\nToday: high 6°, 80% chance of rain this afternoon."
+        );
+        assert_eq!(
+            response.render_text(&Lang::German, &Units::Auto),
+            "Jetzt: 3°C, Light Rain.\n\
+             Heute: Höchstwert 6°, 80% Wahrscheinlichkeit für Regen am Nachmittag."
+        );
+    }
+
+    #[test]
+    fn test_render_text_dry_and_partial() {
+        let mut response = response();
+
+        response.currently = None;
+        response.hourly = None;
+
+        response.daily.as_mut().unwrap().data[0].precip_probability = Some(0.1);
+
+        assert_eq!(response.render_text(&Lang::English, &Units::SI), "Today: high 6°, low 1°.");
+
+        response.daily.as_mut().unwrap().data[0].precip_probability = Some(0.8);
+
+        assert_eq!(response.render_text(&Lang::Spanish, &Units::SI),
+                   "Hoy: máxima 6°, 80% de probabilidad de lluvia.");
+    }
+
+    #[test]
+    fn test_custom_templates() {
+        let templates = TextTemplates::for_lang(&Lang::English)
+            .now("{condition} at {temperature}{unit}")
+            .today("Rain likely{period}");
+
+        assert_eq!(
+            templates.render(&response(), &Units::Imperial),
+            "light rain at 3°F\nRain likely this afternoon"
+        );
+    }
+
+    #[test]
+    fn test_measurements() {
+        let templates = TextTemplates::for_lang(&Lang::German).now("{wind_speed}, {precip_intensity}");
+        let mut response = response();
+
+        response.daily = None;
+
+        #[cfg(feature = "num-format")]
+        assert_eq!(templates.render(&response, &Units::Auto), "4 m/s, 1,3 mm/h");
+        #[cfg(not(feature = "num-format"))]
+        assert_eq!(templates.render(&response, &Units::Auto), "4 m/s, 1.3 mm/h");
+
+        let templates = TextTemplates::for_lang(&Lang::English).now("{wind_speed}, {precip_intensity}");
+
+        assert_eq!(templates.render(&response, &Units::Imperial), "4 mph, 1.26 in/h");
+    }
+}
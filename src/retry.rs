@@ -0,0 +1,150 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Retry policy for the parsed `ApiClient` methods.
+
+use std::time::Duration;
+
+use crate::error::Error;
+
+const DEFAULT_MAX_BACKOFF_SECS: u64 = 30;
+
+/// Controls how many times, and how long to wait before, a failed
+/// request is sent again. Only errors classified as retryable by
+/// `Error::is_retryable` are retried, so e.g. an invalid API key fails
+/// immediately. The wait doubles after every attempt, starting at
+/// `initial_backoff` and capped at `max_backoff`, unless the API asked
+/// for a specific delay with a `Retry-After` header. A request is not
+/// retried if the API asked for a longer delay than `max_backoff`, so
+/// that the caller gets the `ErrorKind::RateLimited` instead of waiting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_retries` times, waiting `initial_backoff` before
+    /// the first retry.
+    pub fn new(max_retries: u32, initial_backoff: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff,
+            max_backoff: Duration::from_secs(DEFAULT_MAX_BACKOFF_SECS)
+        }
+    }
+
+    /// A policy which never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(0, Duration::from_secs(0))
+    }
+
+    /// Cap the wait between attempts.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> RetryPolicy {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Whether a request which failed with `error` on its `attempt`th
+    /// retry (counting from zero) should be sent again.
+    pub fn should_retry(&self, error: &Error, attempt: u32) -> bool {
+        attempt < self.max_retries
+            && error.is_retryable()
+            && error.retry_after().is_none_or(|delay| delay <= self.max_backoff)
+    }
+
+    /// How long to wait before the `attempt`th retry (counting from
+    /// zero).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// How long to wait before retrying a request which failed with
+    /// `error` on its `attempt`th retry. The delay requested by a
+    /// rate-limited response is honored up to `max_backoff`; otherwise
+    /// this is `backoff(attempt)`.
+    pub fn delay(&self, error: &Error, attempt: u32) -> Duration {
+        error.retry_after()
+            .map_or_else(|| self.backoff(attempt), |delay| delay.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::none()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+
+    use crate::error::{Error, ErrorKind};
+    use crate::http::StatusCode;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(100));
+        let unavailable = Error::from(ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE));
+        let forbidden = Error::from(ErrorKind::Status(StatusCode::FORBIDDEN));
+
+        assert!(policy.should_retry(&unavailable, 0));
+        assert!(policy.should_retry(&unavailable, 1));
+        assert!(!policy.should_retry(&unavailable, 2));
+        assert!(!policy.should_retry(&forbidden, 0));
+        assert!(!RetryPolicy::default().should_retry(&unavailable, 0));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+        let retry_after = Some(Duration::from_secs(5));
+        let rate_limited = Error::from(ErrorKind::RateLimited { retry_after });
+        let unspecified = Error::from(ErrorKind::RateLimited { retry_after: None });
+
+        assert!(!policy.should_retry(&rate_limited, 0));
+        assert_eq!(policy.delay(&rate_limited, 0), Duration::from_secs(1));
+
+        let policy = policy.max_backoff(Duration::from_secs(10));
+
+        assert!(policy.should_retry(&rate_limited, 0));
+        assert_eq!(policy.delay(&rate_limited, 0), Duration::from_secs(5));
+        assert_eq!(policy.delay(&unspecified, 2), Duration::from_millis(400));
+        assert_eq!(
+            policy.delay(&ErrorKind::Status(StatusCode::BAD_GATEWAY).into(), 1),
+            Duration::from_millis(200)
+        );
+    }
+}
@@ -0,0 +1,464 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Per-location request scheduling.
+//!
+//! A `Scheduler` holds named entries, each pairing a `ForecastRequest`
+//! with a `Schedule` (a fixed interval or a cron expression). The
+//! scheduler reports which entries are due at a given time and, via
+//! `Scheduler::run`, drives an `ApiClient` to execute them as they come
+//! due. The time each entry last ran is kept in a serializable
+//! `SchedulerState` so that a restarted process can pick up where it
+//! left off.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cancel::CancellationToken;
+use crate::datetime::CivilDateTime;
+use crate::error;
+use crate::http::Response;
+use crate::state::{self, StateError, StateStore};
+use crate::{ApiClient, ForecastRequest};
+
+const SECONDS_PER_MINUTE: u64 = 60;
+const SECONDS_PER_HOUR: u64 = 3600;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+// Cron expressions are evaluated at most this far into the future.
+const CRON_HORIZON_SECONDS: u64 = 5 * 366 * SECONDS_PER_DAY;
+
+/// When a scheduled entry should run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Run immediately, then every `Duration` thereafter.
+    Every(Duration),
+
+    /// Run whenever the cron expression matches.
+    Cron(Cron)
+}
+
+/// A standard five-field cron expression (`minute hour day-of-month
+/// month day-of-week`), evaluated in UTC.
+///
+/// Each field accepts `*`, single values, ranges (`1-5`), lists
+/// (`1,15,30`), and steps (`*/15`, `0-30/10`). Day-of-week runs from 0
+/// (Sunday) to 6, and 7 is accepted as an alias for Sunday. As in
+/// classic cron, when both day-of-month and day-of-week are restricted
+/// an entry matches if either of them does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool
+}
+
+/// Error returned when a cron expression can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronParseError {
+    expression: String
+}
+
+impl std::fmt::Display for CronParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid cron expression: {}", self.expression)
+    }
+}
+
+impl std::error::Error for CronParseError {}
+
+impl FromStr for Cron {
+    type Err = CronParseError;
+
+    fn from_str(expression: &str) -> Result<Cron, CronParseError> {
+        let error = || CronParseError { expression: expression.to_string() };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return Err(error());
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7).ok_or_else(error)?;
+
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+
+        days_of_week.truncate(7);
+
+        Ok(Cron {
+            minutes: parse_field(fields[0], 0, 59).ok_or_else(error)?,
+            hours: parse_field(fields[1], 0, 23).ok_or_else(error)?,
+            days_of_month: parse_field(fields[2], 1, 31).ok_or_else(error)?,
+            months: parse_field(fields[3], 1, 12).ok_or_else(error)?,
+            days_of_week,
+            day_of_month_restricted: fields[2] != "*",
+            day_of_week_restricted: fields[4] != "*"
+        })
+    }
+}
+
+impl Cron {
+    /// Return the first time, in seconds since the UNIX epoch, at or
+    /// after `time` at which this expression matches. Returns `None` if
+    /// the expression never matches (e.g. `0 0 31 2 *`).
+    pub fn next_at_or_after(&self, time: u64) -> Option<u64> {
+        // round up to the start of the next whole minute
+        let mut t = time.div_ceil(SECONDS_PER_MINUTE) * SECONDS_PER_MINUTE;
+        let horizon = t + CRON_HORIZON_SECONDS;
+
+        while t < horizon {
+            let civil = CivilDateTime::from_unix(t as i64, 0);
+
+            if !self.months[civil.month as usize] || !self.matches_day(t, &civil) {
+                t = (t / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY;
+            } else if !self.hours[civil.hour as usize] {
+                t = (t / SECONDS_PER_HOUR + 1) * SECONDS_PER_HOUR;
+            } else if !self.minutes[civil.minute as usize] {
+                t += SECONDS_PER_MINUTE;
+            } else {
+                return Some(t);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: u64, civil: &CivilDateTime) -> bool {
+        let day_of_month = self.days_of_month[civil.day as usize];
+        let day_of_week = self.days_of_week[((time / SECONDS_PER_DAY + 4) % 7) as usize];
+
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week
+        }
+    }
+}
+
+// Parse a single cron field into a lookup table indexed by value.
+fn parse_field(field: &str, min: usize, max: usize) -> Option<Vec<bool>> {
+    let mut table = vec![false; max + 1];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|s| *s > 0)?),
+            None => (part, 1)
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        for value in (start..=end).step_by(step) {
+            table[value] = true;
+        }
+    }
+
+    Some(table)
+}
+
+/// The serializable state of a `Scheduler`: the time, in seconds since
+/// the UNIX epoch, each entry last ran.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SchedulerState {
+    pub last_run: HashMap<String, u64>
+}
+
+#[derive(Debug, Clone)]
+struct Entry<'a> {
+    name: String,
+    request: ForecastRequest<'a>,
+    schedule: Schedule
+}
+
+/// Drives periodic Forecast API requests for a set of named entries.
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler<'a> {
+    entries: Vec<Entry<'a>>,
+    state: SchedulerState
+}
+
+impl<'a> Scheduler<'a> {
+    /// Construct a new, empty Scheduler.
+    pub fn new() -> Scheduler<'a> {
+        Scheduler::default()
+    }
+
+    /// Construct a Scheduler which resumes from previously saved state.
+    pub fn with_state(state: SchedulerState) -> Scheduler<'a> {
+        Scheduler { entries: Vec::new(), state }
+    }
+
+    /// Add an entry, replacing any existing entry with the same name.
+    /// Any saved state for the name is kept.
+    pub fn add(&mut self, name: &str, request: ForecastRequest<'a>, schedule: Schedule) {
+        self.entries.retain(|e| e.name != name);
+        self.entries.push(Entry { name: name.to_string(), request, schedule });
+    }
+
+    /// Remove the entry with the given name, returning whether it
+    /// existed. Its last-run state is discarded as well.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.entries.len();
+
+        self.entries.retain(|e| e.name != name);
+        self.state.last_run.remove(name);
+
+        self.entries.len() != before
+    }
+
+    /// Construct a Scheduler which resumes from the state saved in
+    /// `store` under `key`, or starts afresh if nothing was saved.
+    pub fn restore(store: &dyn StateStore, key: &str) -> Result<Scheduler<'a>, StateError> {
+        Ok(Scheduler::with_state(state::load(store, key)?.unwrap_or_default()))
+    }
+
+    /// The current state, suitable for persisting across restarts.
+    pub fn state(&self) -> &SchedulerState {
+        &self.state
+    }
+
+    /// Save the current state in `store` under `key`.
+    pub fn save_state(&self, store: &dyn StateStore, key: &str) -> Result<(), StateError> {
+        state::save(store, key, &self.state)
+    }
+
+    /// Record that the named entry ran at `time`.
+    pub fn mark_run(&mut self, name: &str, time: u64) {
+        self.state.last_run.insert(name.to_string(), time);
+    }
+
+    /// The next time the named entry should run, as of `now`.
+    pub fn next_run(&self, name: &str, now: u64) -> Option<u64> {
+        self.entries.iter()
+            .find(|e| e.name == name)
+            .and_then(|e| self.entry_next_run(e, now))
+    }
+
+    /// The names of every entry due to run at `now`.
+    pub fn due(&self, now: u64) -> Vec<&str> {
+        self.entries.iter()
+            .filter(|e| self.entry_next_run(e, now).is_some_and(|next| next <= now))
+            .map(|e| e.name.as_str())
+            .collect()
+    }
+
+    /// Execute entries against `client` as they come due, passing the
+    /// name of each entry and the result of its request to `handler`.
+    /// Returns once no entry will ever run again.
+    pub async fn run<F, Fut>(&mut self, client: &ApiClient<'_>, handler: F)
+        where F: FnMut(String, error::Result<Response>) -> Fut, Fut: Future<Output = ()> {
+        self.run_until_cancelled(client, handler, &CancellationToken::new()).await
+    }
+
+    /// Like `run`, but also returns once `token` is cancelled. Shutdown
+    /// is bounded: a pending sleep or in-flight request is abandoned as
+    /// soon as cancellation is requested, and `handler` is not called
+    /// for an abandoned request.
+    pub async fn run_until_cancelled<F, Fut>(
+        &mut self,
+        client: &ApiClient<'_>,
+        mut handler: F,
+        token: &CancellationToken
+    ) where F: FnMut(String, error::Result<Response>) -> Fut, Fut: Future<Output = ()> {
+        while !token.is_cancelled() {
+            let now = unix_now();
+
+            let due: Vec<String> = self.due(now).into_iter().map(String::from).collect();
+
+            for name in due {
+                let request = match self.entries.iter().find(|e| e.name == name) {
+                    Some(entry) => client.get_forecast(&entry.request),
+                    None => continue
+                };
+
+                let result = tokio::select! {
+                    result = request => result,
+                    _ = token.cancelled() => return
+                };
+
+                self.mark_run(&name, now);
+                handler(name, result).await;
+            }
+
+            let now = unix_now();
+
+            let next = self.entries.iter()
+                .filter_map(|e| self.entry_next_run(e, now))
+                .min();
+
+            let delay = match next {
+                Some(next) => tokio::time::sleep(Duration::from_secs(next.saturating_sub(now))),
+                None => return
+            };
+
+            tokio::select! {
+                _ = delay => (),
+                _ = token.cancelled() => return
+            }
+        }
+    }
+
+    fn entry_next_run(&self, entry: &Entry, now: u64) -> Option<u64> {
+        let last_run = self.state.last_run.get(&entry.name);
+
+        match (&entry.schedule, last_run) {
+            (Schedule::Every(_), None) => Some(now),
+            (Schedule::Every(interval), Some(last)) => Some(last + interval.as_secs().max(1)),
+            (Schedule::Cron(cron), None) => cron.next_at_or_after(now),
+            (Schedule::Cron(cron), Some(last)) => cron.next_at_or_after(last + 1)
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{unix_now, Cron, Schedule, Scheduler, SchedulerState};
+
+    use crate::cancel::CancellationToken;
+    use crate::http::Client;
+    use crate::state::MemoryStore;
+    use crate::{ApiClient, ForecastRequestBuilder};
+
+    use std::time::Duration;
+
+    // 2018-01-22T00:00:00Z, a Monday
+    const MONDAY: u64 = 1_516_579_200;
+
+    #[test]
+    fn test_cron_parse() {
+        assert!("*/15 * * * *".parse::<Cron>().is_ok());
+        assert!("0 6,18 1-15 * 1-5".parse::<Cron>().is_ok());
+        assert!("* * * *".parse::<Cron>().is_err());
+        assert!("60 * * * *".parse::<Cron>().is_err());
+        assert!("*/0 * * * *".parse::<Cron>().is_err());
+        assert!("5-1 * * * *".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn test_cron_next_at_or_after() {
+        let quarter_hourly: Cron = "*/15 * * * *".parse().unwrap();
+
+        assert_eq!(quarter_hourly.next_at_or_after(MONDAY), Some(MONDAY));
+        assert_eq!(quarter_hourly.next_at_or_after(MONDAY + 1), Some(MONDAY + 900));
+
+        let morning: Cron = "30 6 * * *".parse().unwrap();
+
+        assert_eq!(morning.next_at_or_after(MONDAY), Some(MONDAY + 6 * 3600 + 1800));
+
+        let saturday: Cron = "0 12 * * 6".parse().unwrap();
+
+        assert_eq!(saturday.next_at_or_after(MONDAY), Some(MONDAY + 5 * 86_400 + 12 * 3600));
+
+        let sunday: Cron = "0 0 * * 7".parse().unwrap();
+
+        assert_eq!(sunday.next_at_or_after(MONDAY), Some(MONDAY + 6 * 86_400));
+
+        let never: Cron = "0 0 31 2 *".parse().unwrap();
+
+        assert_eq!(never.next_at_or_after(MONDAY), None);
+    }
+
+    #[test]
+    fn test_scheduler_due() {
+        let request = ForecastRequestBuilder::new("key", 1.0, 2.0).build();
+        let mut scheduler = Scheduler::new();
+
+        scheduler.add("every", request.clone(), Schedule::Every(Duration::from_secs(600)));
+        scheduler.add("cron", request, Schedule::Cron("0 * * * *".parse().unwrap()));
+
+        assert_eq!(scheduler.due(MONDAY), vec!["every", "cron"]);
+        assert_eq!(scheduler.due(MONDAY + 60), vec!["every"]);
+
+        scheduler.mark_run("every", MONDAY);
+        scheduler.mark_run("cron", MONDAY);
+
+        assert!(scheduler.due(MONDAY + 599).is_empty());
+        assert_eq!(scheduler.due(MONDAY + 600), vec!["every"]);
+        assert_eq!(scheduler.next_run("cron", MONDAY + 600), Some(MONDAY + 3600));
+        assert!(scheduler.remove("every"));
+        assert!(!scheduler.remove("every"));
+    }
+
+    #[test]
+    fn test_scheduler_state() {
+        let request = ForecastRequestBuilder::new("key", 1.0, 2.0).build();
+        let mut scheduler = Scheduler::new();
+
+        scheduler.add("every", request.clone(), Schedule::Every(Duration::from_secs(600)));
+        scheduler.mark_run("every", MONDAY);
+
+        let saved = serde_json::to_string(scheduler.state()).unwrap();
+        let state: SchedulerState = serde_json::from_str(&saved).unwrap();
+
+        let mut restored = Scheduler::with_state(state);
+
+        restored.add("every", request, Schedule::Every(Duration::from_secs(600)));
+
+        assert_eq!(restored.next_run("every", MONDAY + 1), Some(MONDAY + 600));
+
+        let store = MemoryStore::new();
+
+        scheduler.save_state(&store, "scheduler").unwrap();
+
+        assert_eq!(Scheduler::restore(&store, "scheduler").unwrap().state(), scheduler.state());
+    }
+
+    #[tokio::test]
+    async fn test_run_until_cancelled() {
+        let request = ForecastRequestBuilder::new("key", 1.0, 2.0).build();
+        let mut scheduler = Scheduler::new();
+
+        scheduler.add("hourly", request, Schedule::Every(Duration::from_secs(3600)));
+        scheduler.mark_run("hourly", unix_now());
+
+        let client = Client::new();
+        let api_client = ApiClient::new(&client);
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let run = scheduler.run_until_cancelled(&api_client, |_, _| async {}, &token);
+
+        tokio::time::timeout(Duration::from_secs(1), run).await.unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! The version of the API schema the models support, and detection of
+//! payloads which have drifted from it, e.g. to alert an operator when
+//! a provider starts sending new fields:
+//!
+//! ```
+//! use forecast::schema;
+//!
+//! let drift = schema::detect_drift(
+//!     "{\"latitude\":1.0,\"longitude\":2.0,\"timezone\":\"UTC\",\"offset\":0,\
+//!      \"currently\":{\"time\":0,\"smokeDensity\":0.2}}"
+//! ).unwrap();
+//!
+//! assert_eq!(drift.unknown_fields, vec!["currently.smokeDensity"]);
+//! ```
+//!
+//! Unknown fields are ignored when parsing, so drift never breaks
+//! deserialization; it only means data is being dropped.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde_json::{Map, Value};
+
+use crate::{Alert, ApiResponse, DataBlock, DataPoint, Flags};
+
+/// The version of the API schema the models support. The major version
+/// changes when a field is removed or changes type, the minor version
+/// when fields are added, and the patch version for fixes to how
+/// fields are parsed.
+pub const SCHEMA_VERSION: &str = "1.0.0";
+
+/// Fields of a payload unknown to the models.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// The path of each unknown field, e.g. `hourly.data[].smokeDensity`,
+    /// sorted and without duplicates.
+    pub unknown_fields: Vec<String>
+}
+
+impl Drift {
+    /// Whether the payload matches the supported schema.
+    pub fn is_empty(&self) -> bool {
+        self.unknown_fields.is_empty()
+    }
+}
+
+/// Report the fields of a Forecast or Time Machine response, as raw
+/// JSON, which the models don't know about.
+///
+/// # Errors
+///
+/// Returns an error if `raw_json` isn't a JSON object.
+pub fn detect_drift(raw_json: &str) -> Result<Drift, serde_json::Error> {
+    let value: Map<String, Value> = serde_json::from_str(raw_json)?;
+    let mut unknown = BTreeSet::new();
+
+    check::<ApiResponse>(&value, "", &mut unknown);
+
+    if let Some(Value::Object(currently)) = value.get("currently") {
+        check::<DataPoint>(currently, "currently.", &mut unknown);
+    }
+
+    for block in &["minutely", "hourly", "daily"] {
+        if let Some(Value::Object(object)) = value.get(*block) {
+            check::<DataBlock>(object, &format!("{}.", block), &mut unknown);
+
+            let points = object.get("data").and_then(Value::as_array).into_iter().flatten();
+
+            for point in points.filter_map(Value::as_object) {
+                check::<DataPoint>(point, &format!("{}.data[].", block), &mut unknown);
+            }
+        }
+    }
+
+    let alerts = value.get("alerts").and_then(Value::as_array).into_iter().flatten();
+
+    for alert in alerts.filter_map(Value::as_object) {
+        check::<Alert>(alert, "alerts[].", &mut unknown);
+    }
+
+    if let Some(Value::Object(flags)) = value.get("flags") {
+        check::<Flags>(flags, "flags.", &mut unknown);
+    }
+
+    Ok(Drift { unknown_fields: unknown.into_iter().collect() })
+}
+
+fn check<'de, T>(object: &Map<String, Value>, prefix: &str, unknown: &mut BTreeSet<String>)
+    where T : Deserialize<'de> {
+    let known = fields::<T>();
+
+    for key in object.keys().filter(|key| !known.contains(&key.as_str())) {
+        unknown.insert(format!("{}{}", prefix, key));
+    }
+}
+
+// The serialized field names of a struct, which serde's derived
+// implementation passes to `deserialize_struct`.
+fn fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    match T::deserialize(FieldsDeserializer) {
+        Err(FieldsError(Some(fields))) => fields,
+        _ => &[]
+    }
+}
+
+struct FieldsDeserializer;
+
+#[derive(Debug)]
+struct FieldsError(Option<&'static [&'static str]>);
+
+impl fmt::Display for FieldsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("not a struct")
+    }
+}
+
+impl std::error::Error for FieldsError {}
+
+impl de::Error for FieldsError {
+    fn custom<T: fmt::Display>(_: T) -> FieldsError {
+        FieldsError(None)
+    }
+}
+
+impl<'de> Deserializer<'de> for FieldsDeserializer {
+    type Error = FieldsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, FieldsError> {
+        Err(FieldsError(None))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V
+    ) -> Result<V::Value, FieldsError> {
+        Err(FieldsError(Some(fields)))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_drift, fields};
+
+    use crate::testing;
+    use crate::DataPoint;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_fields() {
+        let known = fields::<DataPoint>();
+
+        assert!(known.contains(&"apparentTemperature"));
+        assert!(known.contains(&"windGust"));
+        assert!(!known.contains(&"wind_gust"));
+    }
+
+    #[test]
+    fn test_detect_drift() {
+        let json = testing::response_json(json!({
+            "elevation": 12,
+            "hourly": {"summary": "Dry", "data": [{"time": 0, "smoke": 1}, {"time": 1, "smoke": 2}]},
+            "alerts": [{"title": "Flood", "certainty": "likely"}],
+            "flags": {"sources": [], "units": "us", "nearest-station": 1.2}
+        }));
+        let drift = detect_drift(&json.to_string()).unwrap();
+
+        assert_eq!(drift.unknown_fields, vec![
+            "alerts[].certainty", "elevation", "flags.nearest-station", "hourly.data[].smoke"
+        ]);
+        assert!(detect_drift("{\"latitude\":1.0}").unwrap().is_empty());
+        assert!(detect_drift("[]").is_err());
+    }
+}
@@ -0,0 +1,31 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Hook for signing requests before they are sent.
+//!
+//! Gateways which proxy Dark Sky-compatible traffic often require an
+//! HMAC signature or OAuth bearer token on every request. Implement
+//! `RequestSigner` and install it with `ApiClient::with_signer` to add
+//! such headers.
+
+use std::fmt::Debug;
+
+use crate::http::{HeaderMap, Url};
+
+/// Signs a request just before it is sent, including every retry.
+pub trait RequestSigner: Debug + Send + Sync {
+    /// Add or modify `headers` for a request to the final `url`, which
+    /// already carries every query parameter.
+    fn sign(&self, url: &Url, headers: &mut HeaderMap);
+}
@@ -0,0 +1,187 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! An alternate serialization profile with snake_case keys.
+//!
+//! Wrapping a model in `SnakeCase` serializes it with keys matching the
+//! Rust field names, e.g. `apparent_temperature` rather than the API's
+//! `apparentTemperature`, and deserializes JSON written that way:
+//!
+//! ```
+//! use forecast::snake_case::SnakeCase;
+//! use forecast::DataPoint;
+//!
+//! let point: DataPoint = serde_json::from_str("{\"time\":0,\"windSpeed\":3.5}").unwrap();
+//! let json = serde_json::to_string(&SnakeCase(&point)).unwrap();
+//!
+//! assert_eq!(json, "{\"time\":0,\"wind_speed\":3.5}");
+//!
+//! let SnakeCase(parsed) = serde_json::from_str::<SnakeCase<DataPoint>>(&json).unwrap();
+//!
+//! assert_eq!(parsed, point);
+//! ```
+//!
+//! Absent fields are omitted, as in the `wire` module. Only keys are
+//! renamed; values such as `"partly-cloudy-day"` are left as the API
+//! sends them. The `wire` module remains the way to produce
+//! JSON in the API's own format.
+
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{self, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::wire;
+
+// API keys which aren't camelCase, and their snake_case equivalents.
+const IRREGULAR_KEYS: [(&str, &str); 1] = [("darksky-unavailable", "darksky_unavailable")];
+
+/// A model serialized with snake_case keys. Wrap a reference to
+/// serialize, or the model itself to deserialize.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SnakeCase<T>(pub T);
+
+impl<T> SnakeCase<T> {
+    /// Unwrap the model.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Serialize for SnakeCase<T> where T : Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::to_value(&self.0).map_err(ser::Error::custom)?;
+        wire::omit_nulls(&mut value);
+
+        rename_keys(value, &to_snake_case).serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for SnakeCase<T> where T : DeserializeOwned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SnakeCase<T>, D::Error> {
+        let value = rename_keys(Value::deserialize(deserializer)?, &to_camel_case);
+
+        serde_json::from_value(value).map(SnakeCase).map_err(de::Error::custom)
+    }
+}
+
+fn rename_keys(value: Value, rename: &dyn Fn(&str) -> String) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object.into_iter()
+                .map(|(key, value)| (rename(&key), rename_keys(value, rename)))
+                .collect::<Map<String, Value>>()
+        ),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(|value| rename_keys(value, rename)).collect())
+        },
+        other => other
+    }
+}
+
+fn to_snake_case(key: &str) -> String {
+    if let Some((_, snake)) = IRREGULAR_KEYS.iter().find(|(api, _)| *api == key) {
+        return snake.to_string();
+    }
+
+    let mut snake = String::with_capacity(key.len() + 4);
+
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
+fn to_camel_case(key: &str) -> String {
+    if let Some((api, _)) = IRREGULAR_KEYS.iter().find(|(_, snake)| *snake == key) {
+        return api.to_string();
+    }
+
+    let mut camel = String::with_capacity(key.len());
+    let mut upper = false;
+
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+
+    camel
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{to_camel_case, to_snake_case, SnakeCase};
+
+    use crate::ApiResponse;
+
+    use serde_json::Value;
+
+    use std::fs;
+
+    fn fixture() -> String {
+        fs::read_to_string("resources/tests/forecast_response_01-21-2018.json").unwrap()
+    }
+
+    #[test]
+    fn test_key_conversion() {
+        assert_eq!(to_snake_case("apparentTemperatureHighTime"), "apparent_temperature_high_time");
+        assert_eq!(to_snake_case("uvIndex"), "uv_index");
+        assert_eq!(to_snake_case("darksky-unavailable"), "darksky_unavailable");
+        assert_eq!(to_camel_case("apparent_temperature_high_time"), "apparentTemperatureHighTime");
+        assert_eq!(to_camel_case("darksky_unavailable"), "darksky-unavailable");
+        assert_eq!(to_camel_case("latitude"), "latitude");
+    }
+
+    #[test]
+    fn test_round_trip_wire() {
+        let response: ApiResponse = serde_json::from_str(&fixture()).unwrap();
+        let json = crate::wire::to_string(&response).unwrap();
+
+        assert!(json.contains("\"apparentTemperature\""));
+        assert_eq!(serde_json::from_str::<ApiResponse>(&json).unwrap(), response);
+    }
+
+    #[test]
+    fn test_round_trip_snake_case() {
+        let response: ApiResponse = serde_json::from_str(&fixture()).unwrap();
+        let json = serde_json::to_string(&SnakeCase(&response)).unwrap();
+
+        assert!(json.contains("\"apparent_temperature\""));
+        assert!(!json.contains("\"apparentTemperature\""));
+
+        let parsed: SnakeCase<ApiResponse> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.into_inner(), response);
+
+        // the snake_case profile isn't accepted as the wire format
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let point = serde_json::from_value::<crate::DataPoint>(value["currently"].clone()).unwrap();
+
+        assert_eq!(point.apparent_temperature, None);
+    }
+}
@@ -0,0 +1,184 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Provenance of fetched data.
+//!
+//! `ApiClient::get_forecast_sourced` and `get_time_machine_sourced`
+//! return the response wrapped in a `Sourced`, which records when and
+//! where it was fetched, so that pipelines downstream can reason about
+//! how fresh it is. `Sourced` dereferences to the response, and is
+//! serializable so that it can be stored alongside it.
+
+use std::ops::Deref;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use url::Url;
+
+// constants
+
+static REDACTED: &str = "REDACTED";
+
+/// Whether data was fetched from the API or served from a cache.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// Served from a cache without contacting the API.
+    Hit,
+
+    /// Fetched from the API.
+    Miss,
+
+    /// Served from a cache after the API confirmed it's still current.
+    Revalidated
+}
+
+/// A value together with where and when it was fetched.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Sourced<T> {
+    pub value: T,
+
+    /// When the value was fetched from the API, in seconds since the
+    /// UNIX epoch. A cache hit keeps the time of the original fetch.
+    pub fetched_at: u64,
+
+    /// The host which served the value, e.g. `api.darksky.net`.
+    pub provider: String,
+
+    /// The request URL, with the API key replaced by `REDACTED`.
+    pub url: String,
+
+    pub cache_status: CacheStatus,
+
+    /// The number of calls made with the API key so far today, as
+    /// reported by the `X-Forecast-API-Calls` response header.
+    pub api_calls: Option<u64>
+}
+
+impl<T> Sourced<T> {
+    /// Record that `value` was fetched from `url` at `fetched_at`. The
+    /// API key in the URL's path is redacted.
+    pub fn new(value: T, url: &Url, api_key: &str, fetched_at: u64) -> Sourced<T> {
+        Sourced {
+            value,
+            fetched_at,
+            provider: url.host_str().unwrap_or_default().to_string(),
+            url: redact(url, api_key),
+            cache_status: CacheStatus::Miss,
+            api_calls: None
+        }
+    }
+
+    /// How many seconds old the value is at `now`.
+    pub fn age(&self, now: u64) -> u64 {
+        now.saturating_sub(self.fetched_at)
+    }
+
+    /// Whether the value was fetched more than `max_age` ago.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.age(now) > max_age.as_secs()
+    }
+
+    /// The same provenance for another value, e.g. a value derived from
+    /// this one.
+    pub fn map<U, F>(self, f: F) -> Sourced<U> where F : FnOnce(T) -> U {
+        Sourced {
+            value: f(self.value),
+            fetched_at: self.fetched_at,
+            provider: self.provider,
+            url: self.url,
+            cache_status: self.cache_status,
+            api_calls: self.api_calls
+        }
+    }
+
+    /// Discard the provenance.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Sourced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+// Replace the path segment holding the API key.
+fn redact(url: &Url, api_key: &str) -> String {
+    let mut redacted = url.clone();
+
+    if let Some(segments) = url.path_segments() {
+        let path: Vec<&str> = segments
+            .map(|segment| if segment == api_key { REDACTED } else { segment })
+            .collect();
+
+        redacted.set_path(&path.join("/"));
+    }
+
+    redacted.to_string()
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheStatus, Sourced};
+
+    use crate::{ForecastRequestBuilder, Units};
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_sourced() {
+        let request = ForecastRequestBuilder::new("secret_key", 1.5, -2.5).units(Units::SI).build();
+        let sourced = Sourced::new(vec![1, 2, 3], &request.url(), "secret_key", 1_000);
+
+        assert_eq!(sourced.provider, "api.darksky.net");
+        assert_eq!(
+            sourced.url,
+            "https://api.darksky.net/forecast/REDACTED/1.5000000000000000,-2.5000000000000000?units=si"
+        );
+        assert!(!sourced.url.contains("secret_key"));
+        assert_eq!(sourced.cache_status, CacheStatus::Miss);
+        assert_eq!(sourced.len(), 3);
+        assert_eq!(sourced.age(1_600), 600);
+        assert_eq!(sourced.age(900), 0);
+        assert!(sourced.is_stale(Duration::from_secs(3600)));
+        assert!(!sourced.is_stale(Duration::from_secs(u64::MAX)));
+
+        let mapped = sourced.map(|value| value.len());
+
+        assert_eq!(mapped.value, 3);
+        assert_eq!(mapped.fetched_at, 1_000);
+    }
+
+    #[test]
+    fn test_sourced_serde() {
+        let request = ForecastRequestBuilder::new("secret_key", 1.5, -2.5).build();
+        let mut sourced = Sourced::new(42, &request.url(), "secret_key", 1_000);
+        sourced.cache_status = CacheStatus::Revalidated;
+
+        let json = serde_json::to_value(&sourced).unwrap();
+
+        assert_eq!(json["cache_status"], "revalidated");
+        assert_eq!(serde_json::from_value::<Sourced<i32>>(json).unwrap(), sourced);
+    }
+}
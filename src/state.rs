@@ -0,0 +1,220 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+
+//! Pluggable persistence for long-running processes.
+//!
+//! A `StateStore` saves and loads named blobs. The scheduler, usage
+//! tracker and portfolio can save their state to a store and restore
+//! it after a restart, so that a daemon doesn't re-run requests early,
+//! forget how much quota it has spent or re-notify about alerts it has
+//! already seen. `FileStore` keeps each blob in a file and `MemoryStore`
+//! keeps them in memory, e.g. for tests; other backends such as an
+//! embedded database implement the trait.
+//!
+//! State is serialized as JSON with `save` and deserialized with
+//! `load`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Storage for named blobs of state.
+pub trait StateStore: fmt::Debug + Send + Sync {
+    /// Load the blob saved under `key`, or `None` if there isn't one.
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Save `value` under `key`, replacing any previous blob.
+    fn save(&self, key: &str, value: &[u8]) -> io::Result<()>;
+}
+
+/// Errors which can occur while saving or loading state.
+#[derive(Debug)]
+pub enum StateError {
+    /// The store couldn't be read or written.
+    Io(io::Error),
+
+    /// The state couldn't be serialized, or the saved state couldn't be
+    /// deserialized.
+    Serde(serde_json::Error)
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::Io(e) => write!(f, "state store error: {}", e),
+            StateError::Serde(e) => write!(f, "invalid state: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for StateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StateError::Io(e) => Some(e),
+            StateError::Serde(e) => Some(e)
+        }
+    }
+}
+
+impl From<io::Error> for StateError {
+    fn from(error: io::Error) -> StateError {
+        StateError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for StateError {
+    fn from(error: serde_json::Error) -> StateError {
+        StateError::Serde(error)
+    }
+}
+
+/// Serialize `value` and save it in `store` under `key`.
+pub fn save<T>(store: &dyn StateStore, key: &str, value: &T) -> Result<(), StateError>
+    where T : Serialize {
+    Ok(store.save(key, &serde_json::to_vec(value)?)?)
+}
+
+/// Load and deserialize the value saved in `store` under `key`, or
+/// `None` if nothing has been saved.
+pub fn load<T>(store: &dyn StateStore, key: &str) -> Result<Option<T>, StateError>
+    where T : DeserializeOwned {
+    match store.load(key)? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        None => Ok(None)
+    }
+}
+
+/// A store which keeps each blob in a file named after its key in a
+/// directory. Writes go to a temporary file which is then renamed, so a
+/// crash mid-write leaves the previous state intact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStore {
+    directory: PathBuf
+}
+
+impl FileStore {
+    /// Construct a FileStore in `directory`, which is created on the
+    /// first save if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> FileStore {
+        FileStore { directory: directory.into() }
+    }
+
+    // Characters other than ASCII letters, digits, `-` and `_` are
+    // replaced so that a key can't escape the directory.
+    fn path(&self, key: &str) -> PathBuf {
+        let name: String = key.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+
+        self.directory.join(format!("{}.json", name))
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn save(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        let path = self.path(key);
+        let temporary = path.with_extension("json.tmp");
+
+        fs::create_dir_all(&self.directory)?;
+        fs::write(&temporary, value)?;
+        fs::rename(&temporary, &path)
+    }
+}
+
+/// A store which keeps blobs in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    blobs: Mutex<HashMap<String, Vec<u8>>>
+}
+
+impl MemoryStore {
+    /// Construct an empty MemoryStore.
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl StateStore for MemoryStore {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let blobs = self.blobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        Ok(blobs.get(key).cloned())
+    }
+
+    fn save(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        let mut blobs = self.blobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        blobs.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save, FileStore, MemoryStore, StateError, StateStore};
+
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryStore::new();
+        let mut state = BTreeMap::new();
+
+        state.insert("boston".to_string(), 3);
+
+        assert_eq!(load::<BTreeMap<String, u64>>(&store, "counts").unwrap(), None);
+
+        save(&store, "counts", &state).unwrap();
+
+        assert_eq!(load(&store, "counts").unwrap(), Some(state));
+
+        store.save("counts", b"not json").unwrap();
+
+        assert!(matches!(load::<BTreeMap<String, u64>>(&store, "counts"), Err(StateError::Serde(_))));
+    }
+
+    #[test]
+    fn test_file_store() {
+        let directory = env::temp_dir().join(format!("forecast-state-{}", std::process::id()));
+        let store = FileStore::new(&directory);
+
+        assert_eq!(store.load("../scheduler").unwrap(), None);
+
+        save(&store, "../scheduler", &vec![1, 2, 3]).unwrap();
+
+        assert!(directory.join("___scheduler.json").exists());
+        assert_eq!(load::<Vec<u64>>(&store, "../scheduler").unwrap(), Some(vec![1, 2, 3]));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
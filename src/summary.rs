@@ -0,0 +1,216 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Helpers for post-processing the `summary` text returned by the API.
+//!
+//! `normalize_summary` tidies up a summary string, and
+//! `SummaryTemplate` regenerates a summary from the structured fields
+//! of a `DataPoint` when the text in the requested `Lang` is missing.
+
+use crate::{DataPoint, Icon, Lang};
+
+// constants
+
+static MISSING: &str = "--";
+
+static ENGLISH_TEMPLATE: &str = "{condition}, {temperature}°, {precip_probability}% chance of precipitation";
+static GERMAN_TEMPLATE: &str = "{condition}, {temperature}°, {precip_probability}% Niederschlagswahrscheinlichkeit";
+static FRENCH_TEMPLATE: &str = "{condition}, {temperature}°, {precip_probability}% de risque de précipitations";
+static SPANISH_TEMPLATE: &str = "{condition}, {temperature}°, {precip_probability}% de probabilidad de precipitación";
+
+/// Normalize a summary string: surrounding whitespace and trailing
+/// periods are removed and the first character is capitalized.
+pub fn normalize_summary(summary: &str) -> String {
+    let trimmed = summary.trim().trim_end_matches('.').trim_end();
+
+    let mut chars = trimmed.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+/// A template used to regenerate a summary from the structured fields
+/// of a `DataPoint`.
+///
+/// The following placeholders are substituted when the template is
+/// rendered: `{condition}`, `{temperature}`, `{apparent_temperature}`,
+/// `{humidity}`, `{precip_probability}`, and `{wind_speed}`.
+/// Probabilities and humidity are rendered as percentages, and any
+/// value missing from the `DataPoint` is rendered as `--`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryTemplate {
+    template: String,
+    lang: Lang
+}
+
+impl SummaryTemplate {
+    /// Construct a new SummaryTemplate. The `lang` is used to render
+    /// the `{condition}` placeholder.
+    pub fn new(template: &str, lang: Lang) -> SummaryTemplate {
+        SummaryTemplate { template: template.to_string(), lang }
+    }
+
+    /// Return the built-in template for `lang`, falling back to English
+    /// for languages without one.
+    pub fn for_lang(lang: &Lang) -> SummaryTemplate {
+        match lang {
+            Lang::German => SummaryTemplate::new(GERMAN_TEMPLATE, Lang::German),
+            Lang::French => SummaryTemplate::new(FRENCH_TEMPLATE, Lang::French),
+            Lang::Spanish => SummaryTemplate::new(SPANISH_TEMPLATE, Lang::Spanish),
+            _ => SummaryTemplate::new(ENGLISH_TEMPLATE, Lang::English)
+        }
+    }
+
+    /// Render this template against a `DataPoint`.
+    pub fn render(&self, point: &DataPoint) -> String {
+        let replacements = [
+            ("{condition}", condition(&point.inferred_icon(), &self.lang).to_string()),
+            ("{temperature}", format_value(point.temperature, 1.0)),
+            ("{apparent_temperature}", format_value(point.apparent_temperature, 1.0)),
+            ("{humidity}", format_value(point.humidity, 100.0)),
+            ("{precip_probability}", format_value(point.precip_probability, 100.0)),
+            ("{wind_speed}", format_value(point.wind_speed, 1.0))
+        ];
+
+        replacements.iter().fold(self.template.clone(), |rendered, (placeholder, value)| {
+            rendered.replace(placeholder, value)
+        })
+    }
+}
+
+impl DataPoint {
+    /// Return the normalized summary for this `DataPoint`, or a summary
+    /// regenerated with the built-in template for `lang` when the API
+    /// returned no usable summary text.
+    pub fn normalized_summary(&self, lang: &Lang) -> String {
+        match self.summary.as_deref().map(normalize_summary) {
+            Some(summary) if !summary.is_empty() => summary,
+            _ => normalize_summary(&SummaryTemplate::for_lang(lang).render(self))
+        }
+    }
+}
+
+pub(crate) fn format_value(value: Option<f64>, scale: f64) -> String {
+    match value {
+        Some(v) => format!("{:.0}", v * scale),
+        None => MISSING.to_string()
+    }
+}
+
+fn condition(icon: &Icon, lang: &Lang) -> &'static str {
+    match lang {
+        Lang::German => match icon {
+            Icon::ClearDay | Icon::ClearNight => "Klar",
+            Icon::Rain => "Regen",
+            Icon::Snow => "Schnee",
+            Icon::Sleet => "Schneeregen",
+            Icon::Wind => "Windig",
+            Icon::Fog => "Nebel",
+            Icon::Cloudy => "Bewölkt",
+            Icon::PartlyCloudyDay | Icon::PartlyCloudyNight => "Teilweise bewölkt",
+            Icon::Hail => "Hagel",
+            Icon::Thunderstorm => "Gewitter",
+            Icon::Tornado => "Tornado"
+        },
+        Lang::French => match icon {
+            Icon::ClearDay | Icon::ClearNight => "Ciel dégagé",
+            Icon::Rain => "Pluie",
+            Icon::Snow => "Neige",
+            Icon::Sleet => "Grésil",
+            Icon::Wind => "Venteux",
+            Icon::Fog => "Brouillard",
+            Icon::Cloudy => "Nuageux",
+            Icon::PartlyCloudyDay | Icon::PartlyCloudyNight => "Partiellement nuageux",
+            Icon::Hail => "Grêle",
+            Icon::Thunderstorm => "Orage",
+            Icon::Tornado => "Tornade"
+        },
+        Lang::Spanish => match icon {
+            Icon::ClearDay | Icon::ClearNight => "Despejado",
+            Icon::Rain => "Lluvia",
+            Icon::Snow => "Nieve",
+            Icon::Sleet => "Aguanieve",
+            Icon::Wind => "Ventoso",
+            Icon::Fog => "Niebla",
+            Icon::Cloudy => "Nublado",
+            Icon::PartlyCloudyDay | Icon::PartlyCloudyNight => "Parcialmente nublado",
+            Icon::Hail => "Granizo",
+            Icon::Thunderstorm => "Tormenta",
+            Icon::Tornado => "Tornado"
+        },
+        _ => match icon {
+            Icon::ClearDay | Icon::ClearNight => "Clear",
+            Icon::Rain => "Rain",
+            Icon::Snow => "Snow",
+            Icon::Sleet => "Sleet",
+            Icon::Wind => "Windy",
+            Icon::Fog => "Foggy",
+            Icon::Cloudy => "Overcast",
+            Icon::PartlyCloudyDay | Icon::PartlyCloudyNight => "Partly cloudy",
+            Icon::Hail => "Hail",
+            Icon::Thunderstorm => "Thunderstorms",
+            Icon::Tornado => "Tornado"
+        }
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_summary, SummaryTemplate};
+
+    use crate::{DataPoint, Lang};
+
+    #[test]
+    fn test_normalize_summary() {
+        assert_eq!(normalize_summary("  light rain starting in the evening. "),
+                   "Light rain starting in the evening");
+        assert_eq!(normalize_summary("Clear..."), "Clear");
+        assert_eq!(normalize_summary(" . "), "");
+    }
+
+    #[test]
+    fn test_summary_template() {
+        let point: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"temperature\":12.4,\"precipProbability\":0.3,\"cloudCover\":0.9}"
+        ).unwrap();
+
+        assert_eq!(SummaryTemplate::for_lang(&Lang::English).render(&point),
+                   "Overcast, 12°, 30% chance of precipitation");
+        assert_eq!(SummaryTemplate::for_lang(&Lang::German).render(&point),
+                   "Bewölkt, 12°, 30% Niederschlagswahrscheinlichkeit");
+        assert_eq!(SummaryTemplate::new("{wind_speed} m/s", Lang::English).render(&point),
+                   "-- m/s");
+    }
+
+    #[test]
+    fn test_normalized_summary_fallback() {
+        let missing: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"summary\":\" \",\"temperature\":3.0,\"precipProbability\":0.8,\
+             \"precipIntensity\":0.2,\"precipType\":\"rain\"}"
+        ).unwrap();
+
+        assert_eq!(missing.normalized_summary(&Lang::French),
+                   "Pluie, 3°, 80% de risque de précipitations");
+
+        let present: DataPoint = serde_json::from_str(
+            "{\"time\":0,\"summary\":\"drizzle.\"}"
+        ).unwrap();
+
+        assert_eq!(present.normalized_summary(&Lang::French), "Drizzle");
+    }
+}
@@ -0,0 +1,230 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Fluent construction of synthetic `DataPoint`s, e.g. for simulations
+//! and tests, either from scratch or as a modified copy of a real point:
+//!
+//! ```
+//! use forecast::DataPoint;
+//!
+//! let observed = DataPoint::builder()
+//!     .time(1516611600)
+//!     .with_temperature(3.5)
+//!     .with_humidity(0.8)
+//!     .build();
+//!
+//! // the same hour, but 5° warmer and with no humidity reported
+//! let warmer = observed.to_builder()
+//!     .adjust_temperature(5.0)
+//!     .with_humidity(None)
+//!     .build();
+//!
+//! assert_eq!(warmer.temperature, Some(8.5));
+//! assert_eq!(warmer.humidity, None);
+//! ```
+//!
+//! Each `with_` setter takes either a value or an `Option`, so that a
+//! field can be cleared with `None`.
+
+use crate::{DataPoint, Icon, PrecipType};
+
+macro_rules! builder_fields {
+    (floats: [$($float_setter:ident => $float:ident),*],
+     times: [$($time_setter:ident => $time:ident),*]) => {
+        impl DataPointBuilder {
+            $(
+                #[doc = concat!("Set the `", stringify!($float), "` field.")]
+                #[allow(deprecated)]
+                pub fn $float_setter<T: Into<Option<f64>>>(mut self, value: T) -> DataPointBuilder {
+                    self.point.$float = value.into();
+                    self
+                }
+            )*
+
+            $(
+                #[doc = concat!("Set the `", stringify!($time), "` field.")]
+                #[allow(deprecated)]
+                pub fn $time_setter<T: Into<Option<i64>>>(mut self, value: T) -> DataPointBuilder {
+                    self.point.$time = value.into();
+                    self
+                }
+            )*
+        }
+    }
+}
+
+/// A builder of `DataPoint`s. See the module documentation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataPointBuilder {
+    point: DataPoint
+}
+
+impl DataPointBuilder {
+    /// Set the time of the point, in seconds since the UNIX epoch.
+    pub fn time(mut self, time: i64) -> DataPointBuilder {
+        self.point.time = time;
+        self
+    }
+
+    /// Set the `icon` field.
+    pub fn with_icon<T: Into<Option<Icon>>>(mut self, icon: T) -> DataPointBuilder {
+        self.point.icon = icon.into();
+        self
+    }
+
+    /// Set the `precip_type` field.
+    pub fn with_precip_type<T>(mut self, precip_type: T) -> DataPointBuilder
+        where T : Into<Option<PrecipType>> {
+        self.point.precip_type = precip_type.into();
+        self
+    }
+
+    /// Set the `summary` field.
+    pub fn with_summary<T: Into<Option<String>>>(mut self, summary: T) -> DataPointBuilder {
+        self.point.summary = summary.into();
+        self
+    }
+
+    /// Add `delta` to every temperature of the point, actual and
+    /// apparent, which is present.
+    #[allow(deprecated)]
+    pub fn adjust_temperature(mut self, delta: f64) -> DataPointBuilder {
+        let point = &mut self.point;
+        let temperatures = [
+            &mut point.temperature, &mut point.temperature_high, &mut point.temperature_low,
+            &mut point.temperature_max, &mut point.temperature_min,
+            &mut point.apparent_temperature, &mut point.apparent_temperature_high,
+            &mut point.apparent_temperature_low, &mut point.apparent_temperature_max,
+            &mut point.apparent_temperature_min
+        ];
+
+        for temperature in IntoIterator::into_iter(temperatures).flatten() {
+            *temperature += delta;
+        }
+
+        self
+    }
+
+    /// Return the built `DataPoint`.
+    pub fn build(self) -> DataPoint {
+        self.point
+    }
+}
+
+impl From<DataPoint> for DataPointBuilder {
+    fn from(point: DataPoint) -> DataPointBuilder {
+        DataPointBuilder { point }
+    }
+}
+
+builder_fields! {
+    floats: [
+        with_apparent_temperature => apparent_temperature,
+        with_apparent_temperature_high => apparent_temperature_high,
+        with_apparent_temperature_low => apparent_temperature_low,
+        with_apparent_temperature_max => apparent_temperature_max,
+        with_apparent_temperature_min => apparent_temperature_min,
+        with_cape => cape,
+        with_cloud_cover => cloud_cover,
+        with_dew_point => dew_point,
+        with_humidity => humidity,
+        with_moon_phase => moon_phase,
+        with_nearest_storm_bearing => nearest_storm_bearing,
+        with_nearest_storm_distance => nearest_storm_distance,
+        with_ozone => ozone,
+        with_precip_accumulation => precip_accumulation,
+        with_precip_intensity => precip_intensity,
+        with_precip_intensity_max => precip_intensity_max,
+        with_precip_probability => precip_probability,
+        with_pressure => pressure,
+        with_swell_direction => swell_direction,
+        with_swell_height => swell_height,
+        with_swell_period => swell_period,
+        with_temperature => temperature,
+        with_temperature_high => temperature_high,
+        with_temperature_low => temperature_low,
+        with_temperature_max => temperature_max,
+        with_temperature_min => temperature_min,
+        with_uv_index => uv_index,
+        with_visibility => visibility,
+        with_wave_height => wave_height,
+        with_wave_period => wave_period,
+        with_wind_bearing => wind_bearing,
+        with_wind_gust => wind_gust,
+        with_wind_speed => wind_speed
+    ],
+    times: [
+        with_apparent_temperature_high_time => apparent_temperature_high_time,
+        with_apparent_temperature_low_time => apparent_temperature_low_time,
+        with_apparent_temperature_max_time => apparent_temperature_max_time,
+        with_apparent_temperature_min_time => apparent_temperature_min_time,
+        with_precip_intensity_max_time => precip_intensity_max_time,
+        with_sunrise_time => sunrise_time,
+        with_sunset_time => sunset_time,
+        with_temperature_high_time => temperature_high_time,
+        with_temperature_low_time => temperature_low_time,
+        with_temperature_max_time => temperature_max_time,
+        with_temperature_min_time => temperature_min_time,
+        with_uv_index_time => uv_index_time,
+        with_wind_gust_time => wind_gust_time
+    ]
+}
+
+impl DataPoint {
+    /// A builder of a point with no data at time 0.
+    pub fn builder() -> DataPointBuilder {
+        DataPointBuilder::default()
+    }
+
+    /// A builder starting from a copy of this point.
+    pub fn to_builder(&self) -> DataPointBuilder {
+        DataPointBuilder::from(self.clone())
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataPoint, Icon};
+
+    #[test]
+    fn test_builder() {
+        let point = DataPoint::builder()
+            .time(3600)
+            .with_temperature(10.0)
+            .with_temperature_high(12.0)
+            .with_sunrise_time(1800)
+            .with_icon(Icon::Rain)
+            .with_summary("Rain".to_string())
+            .build();
+
+        assert_eq!(point.time, 3600);
+        assert_eq!(point.temperature, Some(10.0));
+        assert_eq!(point.sunrise_time, Some(1800));
+        assert_eq!(point.icon, Some(Icon::Rain));
+        assert_eq!(point.humidity, None);
+
+        let modified = point.to_builder()
+            .adjust_temperature(-5.0)
+            .with_icon(None)
+            .build();
+
+        assert_eq!(modified.temperature, Some(5.0));
+        assert_eq!(modified.temperature_high, Some(7.0));
+        assert_eq!(modified.temperature_low, None);
+        assert_eq!(modified.icon, None);
+        assert_eq!(modified.summary, point.summary);
+    }
+}
@@ -0,0 +1,37 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Helpers shared by the unit tests.
+
+use serde_json::{json, Value};
+
+use crate::ApiResponse;
+
+/// The JSON of a response at latitude 1.0 and longitude 2.0 in UTC, with
+/// the members of `members`, e.g. its blocks or a different location,
+/// added or replaced.
+pub(crate) fn response_json(members: Value) -> Value {
+    let mut response = json!({"latitude": 1.0, "longitude": 2.0, "timezone": "UTC", "offset": 0});
+
+    if let (Value::Object(response), Value::Object(members)) = (&mut response, members) {
+        response.extend(members);
+    }
+
+    response
+}
+
+/// The response parsed from `response_json(members)`.
+pub(crate) fn response(members: Value) -> ApiResponse {
+    serde_json::from_value(response_json(members)).unwrap()
+}
@@ -0,0 +1,143 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Certificate pinning for the `rustls-tls` backend.
+
+use std::sync::Arc;
+
+use ring::digest::{digest, SHA256};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, Error as TlsError, SignatureScheme};
+
+/// Trusts a server if and only if its leaf certificate's SHA-256
+/// fingerprint is one of `pins`. The certificate chain, the host name
+/// and the validity period are not consulted, so a pinned self-signed
+/// certificate is accepted. Handshake signatures are still verified.
+#[derive(Debug)]
+pub(crate) struct PinnedCertVerifier {
+    pins: Vec<[u8; 32]>,
+    provider: Arc<CryptoProvider>
+}
+
+impl PinnedCertVerifier {
+    pub(crate) fn new(pins: Vec<[u8; 32]>) -> PinnedCertVerifier {
+        PinnedCertVerifier { pins, provider: Arc::new(crypto::ring::default_provider()) }
+    }
+
+    fn is_pinned(&self, certificate: &CertificateDer<'_>) -> bool {
+        let fingerprint = digest(&SHA256, certificate.as_ref());
+
+        self.pins.iter().any(|pin| pin[..] == *fingerprint.as_ref())
+    }
+
+    /// A TLS configuration which trusts only the pinned certificates,
+    /// offering the same ALPN protocols as reqwest's own configuration.
+    pub(crate) fn client_config(self, http2_only: bool) -> ClientConfig {
+        // the ring provider supports every default protocol version
+        let mut config = ClientConfig::builder_with_provider(self.provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(self))
+            .with_no_client_auth();
+
+        if http2_only {
+            config.alpn_protocols = vec![b"h2".to_vec()];
+        } else {
+            config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        }
+
+        config
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime
+    ) -> Result<ServerCertVerified, TlsError> {
+        if self.is_pinned(end_entity) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(TlsError::General("server certificate isn't pinned".to_string()))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        crypto::verify_tls12_signature(
+            message, certificate, signature, &self.provider.signature_verification_algorithms
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        crypto::verify_tls13_signature(
+            message, certificate, signature, &self.provider.signature_verification_algorithms
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::PinnedCertVerifier;
+
+    use ring::digest::{digest, SHA256};
+
+    use rustls::client::danger::ServerCertVerifier;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::Error as TlsError;
+
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_pinned_cert_verifier() {
+        let pinned = CertificateDer::from(b"pinned certificate".to_vec());
+        let other = CertificateDer::from(b"other certificate".to_vec());
+        let mut pin = [0; 32];
+        pin.copy_from_slice(digest(&SHA256, pinned.as_ref()).as_ref());
+
+        let verifier = PinnedCertVerifier::new(vec![pin]);
+        let host = ServerName::try_from("api.darksky.net").unwrap();
+        let now = UnixTime::now();
+
+        assert!(verifier.verify_server_cert(&pinned, &[], &host, &[], now).is_ok());
+        assert!(matches!(
+            verifier.verify_server_cert(&other, &[pinned], &host, &[], now),
+            Err(TlsError::General(_))
+        ));
+        assert!(!verifier.supported_verify_schemes().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! API usage tracking and quota forecasting.
+//!
+//! A `UsageTracker` shared with an `ApiClient` (see
+//! `ApiClient::with_usage_tracker`) records every request the client
+//! sends. `UsageTracker::report` summarizes the recorded calls for a
+//! key: calls per UTC day (the API's billing day), calls per location,
+//! and when the daily quota will run out at the current rate.
+//!
+//! API keys are never stored: calls are attributed to a fingerprint made
+//! of the last four characters of the key. The recorded `UsageLog` is
+//! serializable so that usage survives restarts.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::datetime::CivilDateTime;
+use crate::state::{self, StateError, StateStore};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const FINGERPRINT_LEN: usize = 4;
+
+/// A single recorded API call.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct UsageRecord {
+    pub key: String,
+
+    pub latitude: f64,
+
+    pub longitude: f64,
+
+    pub time: u64
+}
+
+/// Every API call recorded by a `UsageTracker`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct UsageLog {
+    pub records: Vec<UsageRecord>
+}
+
+/// Summary of the calls made with one key.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UsageReport {
+    /// Calls per UTC day, keyed by `YYYY-MM-DD`.
+    pub daily_counts: BTreeMap<String, u64>,
+
+    /// Calls per location, keyed by `latitude,longitude`.
+    pub by_location: BTreeMap<String, u64>,
+
+    /// Calls made so far today (UTC).
+    pub today: u64,
+
+    /// Calls remaining today under the daily limit.
+    pub remaining_today: u64,
+
+    /// When, in seconds since the UNIX epoch, the daily limit will be
+    /// reached if calls continue at today's average rate. `None` if the
+    /// limit won't be reached before the quota resets at midnight UTC.
+    pub projected_exhaustion: Option<u64>
+}
+
+/// Thread-safe recorder of API calls.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    log: Mutex<UsageLog>
+}
+
+impl UsageTracker {
+    /// Construct a new UsageTracker with no recorded calls.
+    pub fn new() -> UsageTracker {
+        UsageTracker::default()
+    }
+
+    /// Construct a UsageTracker which resumes from a saved `UsageLog`.
+    pub fn from_log(log: UsageLog) -> UsageTracker {
+        UsageTracker { log: Mutex::new(log) }
+    }
+
+    /// Construct a UsageTracker which resumes from the log saved in
+    /// `store` under `key`, or starts afresh if nothing was saved.
+    pub fn restore(store: &dyn StateStore, key: &str) -> Result<UsageTracker, StateError> {
+        Ok(UsageTracker::from_log(state::load(store, key)?.unwrap_or_default()))
+    }
+
+    /// Return a copy of the recorded calls, suitable for persisting.
+    pub fn snapshot(&self) -> UsageLog {
+        self.with_log(|log| log.clone())
+    }
+
+    /// Save the recorded calls in `store` under `key`.
+    pub fn save_state(&self, store: &dyn StateStore, key: &str) -> Result<(), StateError> {
+        state::save(store, key, &self.snapshot())
+    }
+
+    /// Record a call made with `api_key` for the given location at
+    /// `time`.
+    pub fn record(&self, api_key: &str, latitude: f64, longitude: f64, time: u64) {
+        let record = UsageRecord { key: fingerprint(api_key), latitude, longitude, time };
+
+        self.with_log(|log| log.records.push(record));
+    }
+
+    /// Discard calls recorded before `time`.
+    pub fn prune_before(&self, time: u64) {
+        self.with_log(|log| log.records.retain(|r| r.time >= time));
+    }
+
+    /// Summarize the calls made with `api_key` as of `now`, given the
+    /// key's `daily_limit`.
+    pub fn report(&self, api_key: &str, now: u64, daily_limit: u64) -> UsageReport {
+        let key = fingerprint(api_key);
+        let today_start = now - now % SECONDS_PER_DAY;
+
+        self.with_log(|log| {
+            let mut daily_counts = BTreeMap::new();
+            let mut by_location = BTreeMap::new();
+            let mut today = 0;
+
+            for record in log.records.iter().filter(|r| r.key == key) {
+                *daily_counts.entry(format_day(record.time)).or_insert(0) += 1;
+                *by_location.entry(format!("{},{}", record.latitude, record.longitude))
+                    .or_insert(0) += 1;
+
+                if record.time >= today_start && record.time <= now {
+                    today += 1;
+                }
+            }
+
+            let remaining_today = daily_limit.saturating_sub(today);
+            let elapsed = now - today_start;
+
+            let projected_exhaustion = if remaining_today == 0 {
+                Some(now)
+            } else if today == 0 || elapsed == 0 {
+                None
+            } else {
+                // calls per second so far today, extrapolated forward
+                let seconds_left = remaining_today as f64 * elapsed as f64 / today as f64;
+                let exhaustion = now + seconds_left.ceil() as u64;
+
+                Some(exhaustion).filter(|t| *t < today_start + SECONDS_PER_DAY)
+            };
+
+            UsageReport {
+                daily_counts,
+                by_location,
+                today,
+                remaining_today,
+                projected_exhaustion
+            }
+        })
+    }
+
+    fn with_log<T, F: FnOnce(&mut UsageLog) -> T>(&self, f: F) -> T {
+        match self.log.lock() {
+            Ok(mut log) => f(&mut log),
+            Err(poisoned) => f(&mut poisoned.into_inner())
+        }
+    }
+}
+
+fn fingerprint(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.chars().collect();
+    let start = chars.len().saturating_sub(FINGERPRINT_LEN);
+
+    chars[start..].iter().collect()
+}
+
+fn format_day(time: u64) -> String {
+    let t = CivilDateTime::from_unix(time as i64, 0);
+
+    format!("{:04}-{:02}-{:02}", t.year, t.month, t.day)
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{UsageLog, UsageTracker};
+
+    use crate::state::MemoryStore;
+
+    // 2018-01-22T00:00:00Z
+    const MIDNIGHT: u64 = 1_516_579_200;
+
+    #[test]
+    fn test_report() {
+        let tracker = UsageTracker::new();
+
+        tracker.record("secret_key_abcd", 1.0, 2.0, MIDNIGHT - 10);
+        tracker.record("secret_key_abcd", 1.0, 2.0, MIDNIGHT + 100);
+        tracker.record("secret_key_abcd", 3.0, 4.0, MIDNIGHT + 200);
+        tracker.record("other_key_wxyz", 1.0, 2.0, MIDNIGHT + 300);
+
+        let report = tracker.report("secret_key_abcd", MIDNIGHT + 3600, 1000);
+
+        assert_eq!(report.daily_counts.get("2018-01-21"), Some(&1));
+        assert_eq!(report.daily_counts.get("2018-01-22"), Some(&2));
+        assert_eq!(report.by_location.get("1,2"), Some(&2));
+        assert_eq!(report.by_location.get("3,4"), Some(&1));
+        assert_eq!(report.today, 2);
+        assert_eq!(report.remaining_today, 998);
+
+        // 2 calls per hour won't exhaust 1000 calls today
+        assert_eq!(report.projected_exhaustion, None);
+
+        let report = tracker.report("secret_key_abcd", MIDNIGHT + 3600, 4);
+
+        // 2 calls per hour exhausts the remaining 2 calls in another hour
+        assert_eq!(report.projected_exhaustion, Some(MIDNIGHT + 7200));
+
+        let report = tracker.report("secret_key_abcd", MIDNIGHT + 3600, 2);
+
+        assert_eq!(report.remaining_today, 0);
+        assert_eq!(report.projected_exhaustion, Some(MIDNIGHT + 3600));
+    }
+
+    #[test]
+    fn test_snapshot_does_not_store_keys() {
+        let tracker = UsageTracker::new();
+
+        tracker.record("secret_key_abcd", 1.0, 2.0, MIDNIGHT);
+        tracker.prune_before(0);
+
+        let saved = serde_json::to_string(&tracker.snapshot()).unwrap();
+
+        assert!(!saved.contains("secret"));
+
+        let restored = UsageTracker::from_log(serde_json::from_str::<UsageLog>(&saved).unwrap());
+
+        assert_eq!(restored.report("secret_key_abcd", MIDNIGHT + 1, 10).today, 1);
+
+        restored.prune_before(MIDNIGHT + 1);
+
+        assert_eq!(restored.report("secret_key_abcd", MIDNIGHT + 1, 10).today, 0);
+    }
+
+    #[test]
+    fn test_state_store() {
+        let store = MemoryStore::new();
+        let tracker = UsageTracker::restore(&store, "usage").unwrap();
+
+        tracker.record("secret_key_abcd", 1.0, 2.0, MIDNIGHT);
+        tracker.save_state(&store, "usage").unwrap();
+
+        let restored = UsageTracker::restore(&store, "usage").unwrap();
+
+        assert_eq!(restored.report("secret_key_abcd", MIDNIGHT + 1, 10).today, 1);
+    }
+}
@@ -0,0 +1,180 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! A fixed number of consecutive hours, stored in an array, for
+//! consumers which need to know their memory use up front, e.g. on
+//! embedded or real-time systems:
+//!
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use forecast::window::HourlyWindow;
+//! # use forecast::{DataBlock, DataPoint};
+//! # let hourly = DataBlock {
+//! #     data: (0..49).map(|hour| DataPoint::builder().time(hour * 3600).build()).collect(),
+//! #     ..DataBlock::default()
+//! # };
+//!
+//! let window = HourlyWindow::<48>::try_from(&hourly).unwrap();
+//!
+//! assert_eq!(window.len(), 48);
+//! assert_eq!(window.end(), 48 * 3600);
+//! ```
+//!
+//! A window is taken from the start of a block, which must have at
+//! least `N` points, one hour apart; any further points are left out.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Index;
+
+use crate::{DataBlock, DataPoint};
+
+// constants
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// `N` consecutive hourly points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyWindow<const N: usize> {
+    points: [DataPoint; N]
+}
+
+impl<const N: usize> HourlyWindow<N> {
+    /// The number of hours in the window, `N`.
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// The points of the window, in time order.
+    pub fn points(&self) -> &[DataPoint; N] {
+        &self.points
+    }
+
+    /// Iterate over the points of the window, in time order.
+    pub fn iter(&self) -> std::slice::Iter<'_, DataPoint> {
+        self.points.iter()
+    }
+
+    /// The start of the first hour, in seconds since the UNIX epoch, or
+    /// 0 for an empty window.
+    pub fn start(&self) -> i64 {
+        self.points.first().map_or(0, |point| point.time)
+    }
+
+    /// The end of the last hour, in seconds since the UNIX epoch.
+    pub fn end(&self) -> i64 {
+        self.start() + N as i64 * SECONDS_PER_HOUR
+    }
+
+    /// Unwrap the points of the window.
+    pub fn into_inner(self) -> [DataPoint; N] {
+        self.points
+    }
+}
+
+impl<const N: usize> TryFrom<&DataBlock> for HourlyWindow<N> {
+    type Error = WindowError;
+
+    fn try_from(block: &DataBlock) -> Result<HourlyWindow<N>, WindowError> {
+        if block.len() < N {
+            return Err(WindowError::TooShort { expected: N, actual: block.len() });
+        }
+
+        let gap = block.data[..N].windows(2)
+            .find(|pair| pair[1].time - pair[0].time != SECONDS_PER_HOUR);
+
+        if let Some(pair) = gap {
+            return Err(WindowError::NotHourly { time: pair[1].time });
+        }
+
+        Ok(HourlyWindow { points: std::array::from_fn(|i| block.data[i].clone()) })
+    }
+}
+
+impl<const N: usize> Index<usize> for HourlyWindow<N> {
+    type Output = DataPoint;
+
+    fn index(&self, index: usize) -> &DataPoint {
+        &self.points[index]
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a HourlyWindow<N> {
+    type Item = &'a DataPoint;
+    type IntoIter = std::slice::Iter<'a, DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+/// The reasons a block can't be made into a window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowError {
+    /// The block has fewer points than the window.
+    TooShort { expected: usize, actual: usize },
+
+    /// The point at `time` isn't an hour after the previous one.
+    NotHourly { time: i64 }
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowError::TooShort { expected, actual } =>
+                write!(f, "expected at least {} hourly points, found {}", expected, actual),
+            WindowError::NotHourly { time } =>
+                write!(f, "point at {} is not an hour after the previous point", time)
+        }
+    }
+}
+
+impl std::error::Error for WindowError {}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{HourlyWindow, WindowError};
+
+    use crate::{DataBlock, DataPoint};
+
+    use std::convert::TryFrom;
+
+    fn block(times: &[i64]) -> DataBlock {
+        DataBlock {
+            data: times.iter().map(|&time| DataPoint::builder().time(time).build()).collect(),
+            ..DataBlock::default()
+        }
+    }
+
+    #[test]
+    fn test_try_from() {
+        let hours = block(&[0, 3600, 7200, 10800]);
+        let window = HourlyWindow::<3>::try_from(&hours).unwrap();
+
+        assert_eq!(window.start(), 0);
+        assert_eq!(window.end(), 10800);
+        assert_eq!(window[2].time, 7200);
+        assert_eq!(window.iter().count(), 3);
+
+        assert_eq!(HourlyWindow::<5>::try_from(&hours),
+                   Err(WindowError::TooShort { expected: 5, actual: 4 }));
+        assert_eq!(HourlyWindow::<3>::try_from(&block(&[0, 3600, 9000])),
+                   Err(WindowError::NotHourly { time: 9000 }));
+        assert!(HourlyWindow::<0>::try_from(&block(&[])).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+//! Serialization in the Dark Sky wire format.
+//!
+//! The models serialize every field, with `null` for the ones the API
+//! didn't report. The functions in this module instead omit absent
+//! fields and blocks, as the API does, and use Dark Sky's camelCase
+//! names, so that existing Dark Sky client apps can consume the output.
+//! Services which proxy or augment responses should serialize through
+//! this module.
+//!
+//! The output is equivalent JSON, not a byte-for-byte copy of the API's:
+//! object keys are written in alphabetical order rather than the API's.
+
+use std::io::Write;
+
+use serde_json::{Result, Value};
+
+use crate::ApiResponse;
+
+/// Serialize a response in the Dark Sky wire format.
+pub fn to_string(response: &ApiResponse) -> Result<String> {
+    serde_json::to_string(&to_value(response)?)
+}
+
+/// Serialize a response in the Dark Sky wire format as a byte vector.
+pub fn to_vec(response: &ApiResponse) -> Result<Vec<u8>> {
+    serde_json::to_vec(&to_value(response)?)
+}
+
+/// Serialize a response in the Dark Sky wire format into `writer`.
+pub fn to_writer<W: Write>(writer: W, response: &ApiResponse) -> Result<()> {
+    serde_json::to_writer(writer, &to_value(response)?)
+}
+
+fn to_value(response: &ApiResponse) -> Result<Value> {
+    let mut value = serde_json::to_value(response)?;
+    omit_nulls(&mut value);
+
+    Ok(value)
+}
+
+// Remove the `null` members of every object in `value`.
+pub(crate) fn omit_nulls(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|_, member| !member.is_null());
+            object.values_mut().for_each(omit_nulls);
+        },
+        Value::Array(values) => values.iter_mut().for_each(omit_nulls),
+        _ => ()
+    }
+}
+
+// unit tests
+
+#[cfg(test)]
+mod tests {
+    use super::{to_string, to_vec};
+
+    use crate::ApiResponse;
+
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_absent_fields_are_omitted() {
+        let response: ApiResponse = serde_json::from_value(json!({
+            "latitude": 42.5,
+            "longitude": -71.25,
+            "timezone": "America/New_York",
+            "offset": -5,
+            "currently": {"time": 100, "temperature": 3.5, "precipType": "snow"},
+            "flags": {"sources": ["isd"], "units": "us"}
+        })).unwrap();
+
+        let wire: Value = serde_json::from_str(&to_string(&response).unwrap()).unwrap();
+
+        assert_eq!(wire, json!({
+            "latitude": 42.5,
+            "longitude": -71.25,
+            "timezone": "America/New_York",
+            "offset": -5,
+            "currently": {"time": 100, "temperature": 3.5, "precipType": "snow"},
+            "flags": {"sources": ["isd"], "units": "us"}
+        }));
+
+        assert_eq!(to_vec(&response).unwrap(), to_string(&response).unwrap().into_bytes());
+
+        let plain = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(plain["currently"]["humidity"], Value::Null);
+        assert_eq!(plain["hourly"], Value::Null);
+    }
+}
@@ -14,26 +14,36 @@ limitations under the License.*/
 
 use std::fs::File;
 use std::path::{PathBuf, Path};
+#[cfg(feature = "integration")]
 use std::time::Duration;
 
-use reqwest::{Client, StatusCode};
-
-use forecast::{ApiResponse, ApiClient, ForecastRequestBuilder,
-               TimeMachineRequestBuilder, ExcludeBlock, ExtendBy,
-               Lang, Units};
+use forecast::ApiResponse;
+#[cfg(feature = "record-fixtures")]
+use forecast::fixtures::{Fixture, Manifest};
+#[cfg(feature = "integration")]
+use forecast::http::StatusCode;
+#[cfg(feature = "integration")]
+use forecast::{ApiClientBuilder, ForecastRequestBuilder, TimeMachineRequestBuilder,
+               ExcludeBlock, ExtendBy, Lang, RetryPolicy, Units};
 
 // constants
 
+#[cfg(feature = "integration")]
 const LAT: f64 = 42.3736;
+#[cfg(feature = "integration")]
 const LONG: f64 = -71.1097;
+#[cfg(feature = "integration")]
 const TIME: u64 = 1505899999;
+#[cfg(feature = "integration")]
+const TIME_1965: i64 = -143_452_800;
 
+#[cfg(feature = "integration")]
 const TIMEOUT_SECS: u64 = 60;
 
 // tests for serde models
 
 fn test_response_serde(path: &Path) {
-    let file = match File::open(&path) {
+    let file = match File::open(path) {
         Err(reason) => panic!("couldn't open {}: {}", path.display(), reason),
         Ok(file) => file
     };
@@ -67,6 +77,41 @@ fn test_response_serde_01_21_2018() {
     test_response_serde(path);
 }
 
+// The 1965 fixture is hand-written in the time machine format rather
+// than recorded from the API, to cover pre-1970 timestamps.
+#[test]
+fn test_response_serde_06_15_1965() {
+    let mut path_buf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path_buf.push("resources/tests/synthetic_time_machine_response_06-15-1965.json");
+
+    let path = path_buf.as_path();
+
+    test_response_serde(path);
+
+    let response: ApiResponse = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+    let day = &response.daily.unwrap().data[0];
+
+    assert_eq!(day.time, -143_496_000);
+    assert!(day.sunrise_time.unwrap() < day.sunset_time.unwrap());
+    assert!(day.sunset_time.unwrap() < 0);
+}
+
+#[test]
+fn test_manifest_fixtures_serde() {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/tests");
+
+    let manifest: serde_json::Value =
+        serde_json::from_reader(File::open(dir.join("manifest.json")).unwrap()).unwrap();
+    let fixtures = manifest["fixtures"].as_array().unwrap();
+
+    assert!(!fixtures.is_empty());
+
+    for fixture in fixtures {
+        test_response_serde(&dir.join(fixture["file"].as_str().unwrap()));
+    }
+}
+
 // tests which perform network calls.
 //
 // To execute these tests, run the following command in the project
@@ -79,13 +124,11 @@ fn test_response_serde_01_21_2018() {
 async fn test_get_forecast_request_default() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
     let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG).build();
 
     let response = api_client.get_forecast(forecast_request).await.unwrap();
@@ -108,18 +151,16 @@ async fn test_get_forecast_request_default() {
 async fn test_get_forecast_request_full() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
-    let mut blocks = vec![ExcludeBlock::Alerts];
+    let blocks = vec![ExcludeBlock::Alerts];
 
     let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG)
         .exclude_block(ExcludeBlock::Flags)
-        .exclude_blocks(&mut blocks)
+        .exclude(&blocks)
         .extend(ExtendBy::Hourly)
         .lang(Lang::Swedish)
         .units(Units::SI)
@@ -146,18 +187,16 @@ async fn test_get_forecast_request_full() {
 async fn test_get_forecast_request_full_asref() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
-    let mut blocks = vec![ExcludeBlock::Alerts];
+    let blocks = vec![ExcludeBlock::Alerts];
 
     let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG)
         .exclude_block(ExcludeBlock::Flags)
-        .exclude_blocks(&mut blocks)
+        .exclude(&blocks)
         .extend(ExtendBy::Hourly)
         .lang(Lang::Swedish)
         .units(Units::SI)
@@ -184,13 +223,11 @@ async fn test_get_forecast_request_full_asref() {
 async fn test_get_time_machine_request_default() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
     let time_machine_request = TimeMachineRequestBuilder::new(
         api_key, LAT, LONG, TIME
     ).build();
@@ -215,20 +252,18 @@ async fn test_get_time_machine_request_default() {
 async fn test_get_time_machine_request_full() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
-    let mut blocks = vec![ExcludeBlock::Daily];
+    let blocks = vec![ExcludeBlock::Daily];
 
     let time_machine_request = TimeMachineRequestBuilder::new(
         api_key, LAT, LONG, TIME
     )
         .exclude_block(ExcludeBlock::Alerts)
-        .exclude_blocks(&mut blocks)
+        .exclude(&blocks)
         .lang(Lang::Arabic)
         .units(Units::SI)
         .build();
@@ -253,13 +288,11 @@ async fn test_get_time_machine_request_full() {
 async fn test_get_time_machine_request_default_asref() {
     let api_key = env!("FORECAST_API_KEY");
 
-    let reqwest_client = Client::builder()
+    let api_client = ApiClientBuilder::new()
         .timeout(Duration::from_secs(TIMEOUT_SECS))
         .build()
         .unwrap();
 
-    let api_client = ApiClient::new(&reqwest_client);
-
     let time_machine_request = TimeMachineRequestBuilder::new(
         api_key, LAT, LONG, TIME
     ).build();
@@ -278,3 +311,154 @@ async fn test_get_time_machine_request_default_asref() {
     // FORECAST_API_KEY=$YOUR_FORECAST_API_KEY cargo test --features integration -- --nocapture
     println!("{}", serde_json::to_string_pretty(&api_response).unwrap());
 }
+
+#[tokio::test]
+#[cfg(feature = "integration")]
+async fn test_get_forecast_parsed() {
+    let api_key = env!("FORECAST_API_KEY");
+
+    let api_client = ApiClientBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .build()
+        .unwrap()
+        .with_retry_policy(RetryPolicy::new(2, Duration::from_secs(1)));
+
+    let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG).build();
+
+    let api_response = api_client.get_forecast_parsed(forecast_request).await.unwrap();
+
+    assert_eq!(api_response.latitude, LAT);
+    assert_eq!(api_response.longitude, LONG);
+}
+
+#[tokio::test]
+#[cfg(feature = "integration")]
+async fn test_get_forecast_parsed_invalid_key() {
+    let api_client = ApiClientBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .build()
+        .unwrap()
+        .with_retry_policy(RetryPolicy::new(2, Duration::from_secs(1)));
+
+    let forecast_request = ForecastRequestBuilder::new("not_a_valid_key", LAT, LONG).build();
+
+    let error = api_client.get_forecast_parsed(forecast_request).await.unwrap_err();
+
+    assert!(error.is_auth());
+}
+
+#[tokio::test]
+#[cfg(feature = "integration")]
+async fn test_get_current_and_get_on() {
+    let api_key = env!("FORECAST_API_KEY");
+
+    let current = forecast::get_current(api_key, LAT, LONG).await.unwrap();
+
+    assert_eq!(current.latitude, LAT);
+    assert!(current.currently.is_some());
+
+    let past = forecast::get_on(api_key, LAT, LONG, TIME).await.unwrap();
+
+    assert_eq!(past.longitude, LONG);
+
+    let history = forecast::get_on(api_key, LAT, LONG, TIME_1965).await.unwrap();
+
+    assert_eq!(history.latitude, LAT);
+    assert!(history.daily.unwrap().data.iter().all(|day| day.time < 0));
+}
+
+#[tokio::test]
+#[cfg(feature = "integration")]
+async fn test_get_forecast_window() {
+    let api_key = env!("FORECAST_API_KEY");
+
+    let api_client = ApiClientBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .build()
+        .unwrap();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    // starts a day ago, so the past is filled in by Time Machine requests
+    let window = now - 86_400..now + 72 * 3600;
+
+    let response = api_client.get_forecast_window(api_key, LAT, LONG, window.clone())
+        .await
+        .unwrap();
+
+    assert!(response.hourly.unwrap().covers(window));
+}
+
+#[test]
+#[cfg(all(feature = "integration", feature = "blocking"))]
+fn test_blocking_get_forecast_parsed() {
+    let api_key = env!("FORECAST_API_KEY");
+
+    let api_client = ApiClientBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .build()
+        .unwrap();
+    let api_client = forecast::blocking::ApiClient::new(api_client).unwrap();
+
+    let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG).build();
+
+    let api_response = api_client.get_forecast_parsed(forecast_request).unwrap();
+
+    assert_eq!(api_response.latitude, LAT);
+    assert_eq!(api_response.longitude, LONG);
+}
+
+// Records fresh fixtures into resources/tests. To execute, run the
+// following command in the project root:
+//
+// FORECAST_API_KEY=$YOUR_FORECAST_API_KEY cargo test --features record-fixtures
+
+#[tokio::test]
+#[cfg(feature = "record-fixtures")]
+async fn test_record_fixtures() {
+    let api_key = env!("FORECAST_API_KEY");
+
+    let api_client = ApiClientBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .build()
+        .unwrap();
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/tests");
+
+    let mut manifest = Manifest::load(&dir).unwrap();
+
+    let recorded_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs() as i64);
+
+    let forecast_request = ForecastRequestBuilder::new(api_key, LAT, LONG).build();
+    let forecast = api_client.get_forecast(forecast_request).await.unwrap();
+
+    assert_eq!(forecast.status(), StatusCode::OK);
+
+    let fixture = Fixture {
+        file: "recorded_forecast_response.json".to_string(),
+        kind: "forecast".to_string(),
+        recorded_at
+    };
+
+    manifest.record(&dir, fixture, &forecast.text().await.unwrap(), api_key).unwrap();
+
+    let time_machine_request = TimeMachineRequestBuilder::new(api_key, LAT, LONG, TIME).build();
+    let time_machine = api_client.get_time_machine(time_machine_request).await.unwrap();
+
+    assert_eq!(time_machine.status(), StatusCode::OK);
+
+    let fixture = Fixture {
+        file: "recorded_time_machine_response.json".to_string(),
+        kind: "time_machine".to_string(),
+        recorded_at
+    };
+
+    manifest.record(&dir, fixture, &time_machine.text().await.unwrap(), api_key).unwrap();
+}
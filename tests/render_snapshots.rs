@@ -0,0 +1,52 @@
+/*Copyright 2016 Jesse C. Grillo
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.*/
+
+// Snapshot tests of the renderers, run against the fixtures in
+// resources/tests. After an intended formatting change, review and
+// accept the new snapshots with `cargo insta review`.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use forecast::{ApiResponse, Lang, Units};
+
+fn fixture(name: &str) -> ApiResponse {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources/tests");
+    path.push(name);
+
+    serde_json::from_reader(File::open(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_render_forecast_10_23_2016() {
+    let response = fixture("forecast_response_10-23-2016.json");
+
+    insta::assert_snapshot!(forecast::render::snapshot(&response, &Lang::English, &Units::Imperial));
+}
+
+#[test]
+fn test_render_forecast_01_21_2018_german() {
+    let response = fixture("forecast_response_01-21-2018.json");
+
+    insta::assert_snapshot!(forecast::render::snapshot(&response, &Lang::German, &Units::Imperial));
+}
+
+// Hand-written rather than recorded, see the integration tests.
+#[test]
+fn test_render_time_machine_06_15_1965() {
+    let response = fixture("synthetic_time_machine_response_06-15-1965.json");
+
+    insta::assert_snapshot!(forecast::render::snapshot(&response, &Lang::English, &Units::Auto));
+}